@@ -0,0 +1,377 @@
+//! Actions the player can take, and queues of them carried out over
+//! several turns.
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::{Ai, hostiles_in_view};
+use crate::branch::is_stairs;
+use crate::equipment::take_off;
+use crate::fov::Fov;
+use crate::game::{Game, next_level};
+use crate::hunger::Hunger;
+use crate::items::{Item, create_item, use_item};
+use crate::locale::display_name;
+use crate::locks::try_lock;
+use crate::map::{is_closed_door, is_locked_door, is_open_door, on_map, set_door, travel_cost};
+use crate::messages::MessageCategory;
+use crate::noise::{SHOUT_NOISE, make_noise};
+use crate::object::{Object, PLAYER};
+use crate::pathfinding;
+use crate::ranged::fire;
+use crate::render::{Animation, COLOR_DOOR};
+use crate::shop::unpaid_keeper;
+use crate::status::StatusKind;
+use crate::tcod_types::input::KeyCode;
+use crate::throw::throw_item;
+use crate::traps::{SEARCH_CHANCE, search_for_traps};
+use crate::ui::{Danger, Tcod, confirm};
+
+pub const BADLY_WOUNDED_DIVISOR: i32 = 3; // Below a third of their hit points, the player is badly wounded
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlayerAction {
+    TookTurn,
+    DidNotTakeTurn,
+    Exit,
+}
+
+/// Something the player can do in the game. The keyboard and the bot both
+/// act through these, so neither needs to know how the other works.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    /// Step by the given offset, attacking or talking to whoever is there.
+    Move(i32, i32),
+    PickUp,
+    UseItem(usize),
+    /// Use the first item of the given kind in the inventory.
+    Use(Item),
+    Descend,
+    /// Open the door at the given offset.
+    OpenDoor(i32, i32),
+    /// Shut the door at the given offset.
+    CloseDoor(i32, i32),
+    /// Throw the item in the given inventory slot at the given tile.
+    Throw(usize, i32, i32),
+    /// Shoot the ranged weapon held at the given tile.
+    Fire(i32, i32),
+    /// Take a step along the way to the given tile. Queued, it goes on one
+    /// step a turn until the player gets there.
+    Travel(i32, i32),
+    Wait,
+    /// Wait a turn. Queued, it goes on until the player is fully healed.
+    Rest,
+    /// Spend a turn looking around for anything hidden close by.
+    Search,
+    /// Shout, for every monster far around to hear.
+    Shout,
+}
+
+impl Action {
+    /// Whether a count typed before the action makes it happen that many times.
+    pub fn repeatable(self) -> bool {
+        matches!(self, Action::Move(..) | Action::Wait | Action::Search)
+    }
+
+    /// The action as a macro should remember it. Items are used by kind,
+    /// since they will have moved around the inventory by the time it is played.
+    pub fn for_macro(self, game: &Game) -> Action {
+        match self {
+            Action::UseItem(index) => match game.inventory.get(index).and_then(|item| item.item) {
+                Some(item) => Action::Use(item),
+                None => self,
+            },
+            _ => self,
+        }
+    }
+}
+
+/// Actions lined up to be done one per turn, for a count typed before a
+/// command or a macro, until they run out or something comes up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActionQueue {
+    pub actions: VecDeque<Action>,
+    /// The player's hit points when the queue began; any hurt stops it.
+    pub hp: i32,
+    /// Whether hostiles were in view when the queue began. If not, the first
+    /// one to show up stops it.
+    pub hostiles: bool,
+    /// How hungry the player was when the queue began; growing hungrier
+    /// stops it.
+    pub hunger: Hunger,
+    /// The last action done, and where the player stood before it.
+    pub last: Option<(Action, (i32, i32))>,
+}
+
+impl ActionQueue {
+    pub fn new(actions: VecDeque<Action>, fov: &Fov, game: &Game, objects: &[Object]) -> Self {
+        ActionQueue {
+            actions: actions,
+            hp: objects[PLAYER].fighter.map_or(0, |f| f.hp),
+            hostiles: hostiles_in_view(fov, objects),
+            hunger: Hunger::of(game.nutrition),
+            last: None,
+        }
+    }
+}
+
+/// Do the next queued action, unless a monster has come into view, the
+/// player got hurt or hungrier, a move ran into a wall, or a key or the
+/// mouse was pressed.
+pub fn continue_queue(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) -> Option<PlayerAction> {
+    let mut queue = tcod.queue.take()?;
+    let hp = objects[PLAYER].fighter.map_or(0, |f| f.hp);
+    let blocked = match queue.last {
+        Some((Action::Move(..), position)) => objects[PLAYER].pos() == position,
+        _ => false,
+    };
+    let ambushed = !queue.hostiles && hostiles_in_view(&tcod.fov, objects);
+    let hungrier = Hunger::of(game.nutrition) > queue.hunger;
+    let interrupted = tcod.key.code != KeyCode::NoKey || tcod.mouse.lbutton_pressed;
+    if ambushed || hp < queue.hp || hungrier || blocked || interrupted {
+        return None;
+    }
+
+    // A journey or a rest that is over gives way to whatever comes after it.
+    let action = loop {
+        let action = queue.actions.pop_front()?;
+        let finished = match action {
+            Action::Travel(x, y) => objects[PLAYER].pos() == (x, y),
+            Action::Rest => {
+                let healed = objects[PLAYER].fighter.map_or(true, |f| f.hp >= f.max_hp);
+                if healed {
+                    game.messages.add(tr!("rest.done"), MessageCategory::Item);
+                }
+                healed
+            }
+            _ => false,
+        };
+        if !finished {
+            break action;
+        }
+    };
+    if let Action::Travel(..) | Action::Rest = action {
+        // Not done yet, so it stays at the front of the queue.
+        queue.actions.push_front(action);
+    }
+    queue.last = Some((action, objects[PLAYER].pos()));
+    if !queue.actions.is_empty() {
+        tcod.queue = Some(queue);
+    }
+    Some(perform_action(action, tcod, game, objects))
+}
+
+/// Look for secret doors in the walls around the player, finding each with
+/// some luck.
+fn search_for_doors(game: &mut Game, objects: &[Object]) {
+    let (px, py) = objects[PLAYER].pos();
+    for x in px - 1..px + 2 {
+        for y in py - 1..py + 2 {
+            if !on_map(x, y) {
+                continue;
+            }
+            let tile = &mut game.map[x as usize][y as usize];
+            if !tile.secret || game.rng.next_f32() >= SEARCH_CHANCE {
+                continue;
+            }
+            tile.secret = false;
+            tile.explored = true;
+            game.messages.add(tr!("door.found"), MessageCategory::Lore);
+            game.animations.push(Animation::Flash {
+                x: x,
+                y: y,
+                color: COLOR_DOOR,
+            });
+        }
+    }
+}
+
+/// Set off resting until the player is healed, if there is anything to heal
+/// and the player is fed well enough to mend.
+pub fn start_resting(tcod: &mut Tcod, game: &mut Game, objects: &[Object]) {
+    let player = &objects[PLAYER];
+    let regenerating = player.effects.iter().any(|e| e.kind == StatusKind::Regeneration);
+    if player.fighter.map_or(true, |f| f.hp >= f.max_hp) {
+        game.messages.add(tr!("rest.unhurt"), MessageCategory::Warning);
+    } else if Hunger::of(game.nutrition) != Hunger::Fed && !regenerating {
+        game.messages.add(tr!("rest.hungry"), MessageCategory::Warning);
+    } else {
+        let actions = std::iter::once(Action::Rest).collect();
+        tcod.queue = Some(ActionQueue::new(actions, &tcod.fov, game, objects));
+    }
+}
+
+/// The next step along the cheapest way to `to` over tiles the player has
+/// explored, or `None` if there is no such way, or the step is blocked by
+/// someone standing in it.
+fn travel_step(game: &Game, objects: &[Object], to: (i32, i32)) -> Option<(i32, i32)> {
+    let from = objects[PLAYER].pos();
+    let path = pathfinding::astar(from, to, |x, y| {
+        let cost = travel_cost(x, y, &game.map, objects)?;
+        if game.map[x as usize][y as usize].explored { Some(cost) } else { None }
+    })?;
+    let &(x, y) = path.first()?;
+    if Object::is_blocked(x, y, &game.map, objects) && !is_closed_door(x, y, &game.map) {
+        return None;
+    }
+    Some((x - from.0, y - from.1))
+}
+
+pub fn perform_action(
+    action: Action,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut Vec<Object>,
+) -> PlayerAction {
+    // A count typed before the action has it go on for that many turns.
+    if let Some(count) = tcod.count.take() {
+        if count > 1 && action.repeatable() {
+            let actions = std::iter::repeat_n(action, count as usize - 1).collect();
+            let mut queue = ActionQueue::new(actions, &tcod.fov, game, objects);
+            queue.last = Some((action, objects[PLAYER].pos()));
+            tcod.queue = Some(queue);
+        }
+    }
+    if tcod.recording.is_some() {
+        let step = action.for_macro(game);
+        if let Some((_, ref mut steps)) = tcod.recording {
+            steps.push(step);
+        }
+    }
+
+    match action {
+        Action::Move(dx, dy) => {
+            if Object::player_move_or_attack(dx, dy, tcod, game, objects) {
+                PlayerAction::TookTurn
+            } else {
+                // Whatever else was lined up is called off too.
+                tcod.queue = None;
+                PlayerAction::DidNotTakeTurn
+            }
+        }
+        Action::PickUp => {
+            let item_id = objects
+                .iter()
+                .position(|object| object.pos() == objects[PLAYER].pos() && object.item.is_some());
+            if let Some(item_id) = item_id {
+                Object::pick_item_up(item_id, game, objects);
+            }
+            PlayerAction::DidNotTakeTurn
+        }
+        Action::UseItem(inventory_index) => {
+            use_item(inventory_index, tcod, game, objects);
+            PlayerAction::DidNotTakeTurn
+        }
+        Action::Use(item) => {
+            match game.inventory.iter().position(|object| object.item == Some(item)) {
+                Some(inventory_index) => use_item(inventory_index, tcod, game, objects),
+                None => {
+                    let name = display_name(&create_item(item, 0, 0).name);
+                    game.messages.add(tr!("macro.missing-item", name), MessageCategory::Warning);
+                    // Whatever comes next was meant to follow the item's use.
+                    tcod.queue = None;
+                }
+            }
+            PlayerAction::DidNotTakeTurn
+        }
+        Action::Descend => {
+            let player_on_stairs = objects
+                .iter()
+                .any(|object| object.pos() == objects[PLAYER].pos() && is_stairs(object));
+            if player_on_stairs {
+                let badly_wounded = objects[PLAYER]
+                    .fighter
+                    .map_or(false, |f| f.hp * BADLY_WOUNDED_DIVISOR < f.max_hp);
+                let enemies_adjacent = objects.iter().any(|object| {
+                    object.alive
+                        && object.fighter.is_some()
+                        && object.ai.as_ref().map_or(false, Ai::is_hostile)
+                        && object.distance_to(&objects[PLAYER]) < 2.0
+                });
+                if badly_wounded && enemies_adjacent
+                    && !confirm(tcod, game, Danger::DescendWounded, &tr!("confirm.descend-wounded"))
+                {
+                    return PlayerAction::DidNotTakeTurn;
+                }
+                // Nobody takes unpaid goods down the stairs, out of the shopkeeper's reach.
+                if let Some(keeper_id) = unpaid_keeper(game, objects) {
+                    let keeper = display_name(&objects[keeper_id].name);
+                    game.messages.add(tr!("shop.pay-first", keeper), MessageCategory::Warning);
+                    return PlayerAction::DidNotTakeTurn;
+                }
+                next_level(tcod, game, objects);
+            }
+            PlayerAction::DidNotTakeTurn
+        }
+        Action::OpenDoor(dx, dy) => {
+            let (x, y) = (objects[PLAYER].x + dx, objects[PLAYER].y + dy);
+            if !is_closed_door(x, y, &game.map) {
+                return PlayerAction::DidNotTakeTurn;
+            }
+            if is_locked_door(x, y, &game.map) {
+                try_lock(x, y, game, objects);
+                return PlayerAction::TookTurn;
+            }
+            set_door(x, y, true, &mut game.map);
+            game.messages.add(tr!("door.opened"), MessageCategory::Lore);
+            PlayerAction::TookTurn
+        }
+        Action::CloseDoor(dx, dy) => {
+            let (x, y) = (objects[PLAYER].x + dx, objects[PLAYER].y + dy);
+            if !is_open_door(x, y, &game.map) {
+                return PlayerAction::DidNotTakeTurn;
+            }
+            if objects.iter().any(|object| object.pos() == (x, y)) {
+                game.messages.add(tr!("door.in-the-way"), MessageCategory::Warning);
+                return PlayerAction::DidNotTakeTurn;
+            }
+            set_door(x, y, false, &mut game.map);
+            game.messages.add(tr!("door.closed"), MessageCategory::Lore);
+            PlayerAction::TookTurn
+        }
+        Action::Throw(index, x, y) => {
+            // Anything worn has to come off first.
+            if !take_off(index, game, objects) {
+                return PlayerAction::DidNotTakeTurn;
+            }
+            throw_item(index, (x, y), game, objects);
+            PlayerAction::TookTurn
+        }
+        Action::Fire(x, y) => {
+            if fire((x, y), game, objects) {
+                PlayerAction::TookTurn
+            } else {
+                PlayerAction::DidNotTakeTurn
+            }
+        }
+        Action::Travel(x, y) => match travel_step(game, objects, (x, y)) {
+            Some((dx, dy)) if Object::player_move_or_attack(dx, dy, tcod, game, objects) => {
+                PlayerAction::TookTurn
+            }
+            Some(_) => {
+                tcod.queue = None;
+                PlayerAction::DidNotTakeTurn
+            }
+            None => {
+                game.messages.add(tr!("travel.no-way"), MessageCategory::Warning);
+                tcod.queue = None;
+                PlayerAction::DidNotTakeTurn
+            }
+        },
+        Action::Wait | Action::Rest => PlayerAction::TookTurn,
+        Action::Search => {
+            search_for_traps(game, objects);
+            search_for_doors(game, objects);
+            PlayerAction::TookTurn
+        }
+        Action::Shout => {
+            game.messages.add(tr!("shout"), MessageCategory::Warning);
+            let (x, y) = objects[PLAYER].pos();
+            make_noise(game, x, y, SHOUT_NOISE);
+            PlayerAction::TookTurn
+        }
+    }
+}
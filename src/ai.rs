@@ -0,0 +1,531 @@
+//! Monster behaviour, and the turn in which every monster gets to act.
+
+use std::cmp;
+
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+use crate::boss::{BOSS_COOLDOWN, BOSS_FIRE_DAMAGE, BOSS_FIRE_TURNS, BOSS_SUMMONS};
+use crate::config::config;
+use crate::faction::{INFIGHTING_SIGHT, are_enemies};
+use crate::fov::{Fov, in_plain_sight};
+use crate::game::{Game, Glimpse};
+use crate::hunger::tick_hunger;
+use crate::lava::tick_lava;
+use crate::locale::display_name;
+use crate::map::{Map, OCCUPIED_STEP_COST, map_blocked, travel_cost};
+use crate::messages::MessageCategory;
+use crate::noise::spread_noises;
+use crate::object::{Object, PLAYER, create_monster, mut_two};
+use crate::pack::{call_pack, flank_spot};
+use crate::pathfinding;
+use crate::render::Animation;
+use crate::respawn::tick_respawn;
+use crate::scavenge::{drink_healing_potion, item_to_grab, pick_up_items};
+use crate::sleep::{WAKE_CHANCE, WAKE_DISTANCE};
+use crate::status::{StatusEffect, StatusKind, random_direction, tick_effects};
+use crate::summon::{
+    SUMMONED_MONSTER, SUMMONER_CAP, SUMMONER_COOLDOWN, SUMMONS_PER_CALL, free_tiles_around,
+    spawn_monster, summons_about,
+};
+use crate::tcod_types::colors::*;
+use crate::ui::Tcod;
+use crate::water::tick_water;
+
+/// Basic Artificial Intelligence Component
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Ai {
+    Basic,
+    /// Tags along after the player without fighting.
+    Follow,
+    /// Minds its own business unless provoked.
+    Idle,
+    /// Runs from the player until it has recovered.
+    Flee,
+    /// Keeps its distance, and shoots from up to `range` tiles away.
+    Ranged {
+        range: i32,
+    },
+    /// Heads for a spot where something gave the player away, then carries
+    /// on as before. Seeing the player cuts that short.
+    Alerted {
+        x: i32,
+        y: i32,
+        previous_ai: Box<Ai>,
+    },
+    /// The lich: fights up close, and otherwise casts a spell whenever the
+    /// cooldown from the last one runs out.
+    Boss {
+        cooldown: i32,
+    },
+    /// Hangs back, and calls up help whenever the cooldown from its last
+    /// call runs out, fighting only when the player gets right up to it.
+    Summoner {
+        cooldown: i32,
+    },
+    /// Hunts with the rest of its pack, calling them in when it sees the
+    /// player and closing in from every side it can.
+    Pack,
+    /// Dozes until something wakes it, then goes back to its usual ways.
+    Sleeping {
+        previous_ai: Box<Ai>,
+    },
+}
+
+impl Ai {
+    /// Whether it attacks the player on sight.
+    pub fn is_hostile(&self) -> bool {
+        matches!(
+            *self,
+            Ai::Basic
+                | Ai::Pack
+                | Ai::Ranged { .. }
+                | Ai::Alerted { .. }
+                | Ai::Boss { .. }
+                | Ai::Summoner { .. }
+        )
+    }
+
+    /// How it behaves when awake.
+    pub fn awake(&self) -> &Ai {
+        match *self {
+            Ai::Sleeping { ref previous_ai } => previous_ai,
+            ref ai => ai,
+        }
+    }
+}
+
+impl Object {
+    pub fn ai_take_turn(
+        monster_id: usize,
+        tcod: &Tcod,
+        game: &mut Game,
+        objects: &mut Vec<Object>,
+    ) {
+        use Ai::*;
+        // Confused or blind, it blunders about whatever it had in mind.
+        if objects[monster_id].is_stumbling() && !objects[monster_id].is_asleep() {
+            Object::stumble(monster_id, tcod, game, objects);
+            return;
+        }
+        if let Some(ai) = objects[monster_id].ai.take() {
+            let new_ai = match ai {
+                Basic => Object::ai_basic(monster_id, tcod, game, objects),
+                Follow => Object::ai_follow(monster_id, tcod, game, objects),
+                Idle => Idle,
+                Flee => Object::ai_flee(monster_id, tcod, game, objects),
+                Ranged { range } => Object::ai_ranged(monster_id, tcod, game, objects, range),
+                Alerted { x, y, previous_ai } => {
+                    Object::ai_alerted(monster_id, tcod, game, objects, (x, y), previous_ai)
+                }
+                Boss { cooldown } => Object::ai_boss(monster_id, tcod, game, objects, cooldown),
+                Pack => Object::ai_pack(monster_id, tcod, game, objects),
+                Summoner { cooldown } => {
+                    Object::ai_summoner(monster_id, tcod, game, objects, cooldown)
+                }
+                Sleeping { previous_ai } => {
+                    Object::ai_sleeping(monster_id, tcod, game, objects, previous_ai)
+                }
+            };
+            objects[monster_id].ai = Some(new_ai);
+        }
+    }
+
+    pub fn ai_follow(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) -> Ai {
+        // Keep close to the player, but leave them room to move.
+        if objects[monster_id].distance_to(&objects[PLAYER]) >= 3.0 {
+            let (player_x, player_y) = objects[PLAYER].pos();
+            Object::move_towards(monster_id, player_x, player_y, &tcod.fov, game, objects);
+        }
+        Ai::Follow
+    }
+
+    pub fn ai_basic(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) -> Ai {
+        // A basic monster takes its turn. If you can see it, it can see you,
+        // and it goes for whichever enemy is nearest, the player or otherwise.
+        let (monster_x, monster_y) = objects[monster_id].pos();
+        if objects[monster_id].badly_hurt() {
+            if drink_healing_potion(monster_id, &tcod.fov, game, objects) {
+                return Ai::Basic;
+            }
+            if tcod.fov.is_in_fov(monster_x, monster_y) {
+                game.messages.add(
+                    tr!("monster-flees", display_name(&objects[monster_id].name)),
+                    MessageCategory::Combat,
+                );
+            }
+            return Object::ai_flee(monster_id, tcod, game, objects);
+        }
+        if tcod.fov.is_in_fov(monster_x, monster_y) {
+            if let Some(target_id) = nearest_enemy(monster_id, &game.map, objects) {
+                let item = item_to_grab(monster_id, target_id, &game.map, objects);
+                if let Some((item_x, item_y)) = item {
+                    // Something worth having lies nearer than the enemy
+                    Object::move_towards(monster_id, item_x, item_y, &tcod.fov, game, objects);
+                } else if objects[monster_id].distance_to(&objects[target_id]) >= 2.0 {
+                    // Move towards the enemy if far away
+                    let (target_x, target_y) = objects[target_id].pos();
+                    Object::move_towards(monster_id, target_x, target_y, &tcod.fov, game, objects);
+                } else {
+                    // If monster is close enough, ATTACK!
+                    let (monster, target) = mut_two(monster_id, target_id, objects);
+                    monster.attack(target, game);
+                }
+            }
+        }
+        Ai::Basic
+    }
+
+    pub fn ai_pack(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) -> Ai {
+        // Like a basic monster, but it never leaves the rest of the pack to fight alone.
+        let (monster_x, monster_y) = objects[monster_id].pos();
+        if !tcod.fov.is_in_fov(monster_x, monster_y) {
+            return Ai::Pack;
+        }
+        call_pack(monster_id, &tcod.fov, objects);
+        if objects[monster_id].distance_to(&objects[PLAYER]) >= 2.0 {
+            // Make for its own side of the player, or straight at them if every side is taken.
+            let (x, y) = flank_spot(monster_id, game, objects).unwrap_or(objects[PLAYER].pos());
+            Object::move_towards(monster_id, x, y, &tcod.fov, game, objects);
+        } else if objects[PLAYER].fighter.map_or(false, |f| f.hp > 0) {
+            let (monster, player) = mut_two(monster_id, PLAYER, objects);
+            monster.attack(player, game);
+        }
+        Ai::Pack
+    }
+
+    pub fn ai_flee(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) -> Ai {
+        // Once it has recovered, it is back to fighting.
+        if !objects[monster_id].badly_hurt() {
+            return Ai::Basic;
+        }
+        // Run to wherever is furthest from the player, going by how far
+        // they would have to walk rather than as the crow flies.
+        let player = objects[PLAYER].pos();
+        let distances = pathfinding::distance_map(player, |x, y| {
+            if map_blocked(x, y, &game.map) { None } else { Some(1) }
+        });
+        let (x, y) = objects[monster_id].pos();
+        let distance = |x: i32, y: i32| distances.get(&(x, y)).cloned().unwrap_or(0);
+        let step = (-1..2)
+            .flat_map(|dx| (-1..2).map(move |dy| (dx, dy)))
+            .filter(|&(dx, dy)| !Object::is_blocked(x + dx, y + dy, &game.map, objects))
+            .max_by_key(|&(dx, dy)| distance(x + dx, y + dy))
+            .filter(|&(dx, dy)| distance(x + dx, y + dy) > distance(x, y));
+        match step {
+            Some((dx, dy)) => Object::move_by(monster_id, dx, dy, &tcod.fov, game, objects),
+            None => {
+                // Cornered, it fights back.
+                let cornered = objects[monster_id].distance_to(&objects[PLAYER]) < 2.0;
+                let player_alive = objects[PLAYER].fighter.map_or(false, |f| f.hp > 0);
+                if cornered && player_alive && tcod.fov.is_in_fov(x, y) {
+                    let (monster, player) = mut_two(monster_id, PLAYER, objects);
+                    monster.attack(player, game);
+                }
+            }
+        }
+        Ai::Flee
+    }
+
+    /// Whether it is hurt badly enough to run away.
+    pub fn badly_hurt(&self) -> bool {
+        self.fighter
+            .map_or(false, |f| f.hp * 100 < f.max_hp * config().gameplay.flee_hp_percent)
+    }
+
+    pub fn ai_ranged(
+        monster_id: usize,
+        tcod: &Tcod,
+        game: &mut Game,
+        objects: &mut [Object],
+        range: i32,
+    ) -> Ai
+    {
+        // A ranged monster keeps the player at arm's length, and shoots when it can see them.
+        let (monster_x, monster_y) = objects[monster_id].pos();
+        let player_alive = objects[PLAYER].fighter.map_or(false, |f| f.hp > 0);
+        if tcod.fov.is_in_fov(monster_x, monster_y) && player_alive {
+            let (player_x, player_y) = objects[PLAYER].pos();
+            let distance = objects[monster_id].distance_to(&objects[PLAYER]);
+            let backed_off = distance < 2.0
+                && Object::move_away(monster_id, player_x, player_y, &tcod.fov, game, objects);
+            if backed_off {
+                // Backed off, to shoot next turn.
+            } else if distance <= range as f32 {
+                let (monster, player) = mut_two(monster_id, PLAYER, objects);
+                monster.shoot(player, game);
+            } else {
+                Object::move_towards(monster_id, player_x, player_y, &tcod.fov, game, objects);
+            }
+        }
+        Ai::Ranged { range: range }
+    }
+
+    pub fn ai_sleeping(
+        monster_id: usize,
+        tcod: &Tcod,
+        game: &mut Game,
+        objects: &mut [Object],
+        previous_ai: Box<Ai>,
+    ) -> Ai
+    {
+        // Only a player in sight can disturb it, and the closer they are, the likelier.
+        let (monster_x, monster_y) = objects[monster_id].pos();
+        let distance = objects[monster_id].distance_to(&objects[PLAYER]);
+        if tcod.fov.is_in_fov(monster_x, monster_y) && distance < WAKE_DISTANCE {
+            let chance = WAKE_CHANCE * (WAKE_DISTANCE - distance) / (WAKE_DISTANCE - 1.0);
+            if game.rng.next_f32() < chance {
+                game.messages.add(
+                    tr!("monster-wakes", display_name(&objects[monster_id].name)),
+                    MessageCategory::Combat,
+                );
+                return *previous_ai;
+            }
+        }
+        Ai::Sleeping {
+            previous_ai: previous_ai,
+        }
+    }
+
+    pub fn ai_alerted(
+        monster_id: usize,
+        tcod: &Tcod,
+        game: &mut Game,
+        objects: &mut Vec<Object>,
+        (x, y): (i32, i32),
+        previous_ai: Box<Ai>,
+    ) -> Ai
+    {
+        let (monster_x, monster_y) = objects[monster_id].pos();
+        if tcod.fov.is_in_fov(monster_x, monster_y) {
+            // The player is right there, so it goes back to its usual ways this very turn.
+            objects[monster_id].ai = Some(*previous_ai);
+            Object::ai_take_turn(monster_id, tcod, game, objects);
+            return objects[monster_id].ai.take().unwrap_or(Ai::Basic);
+        }
+        Object::move_towards(monster_id, x, y, &tcod.fov, game, objects);
+        if objects[monster_id].pos() == (x, y) {
+            *previous_ai
+        } else {
+            Ai::Alerted {
+                x: x,
+                y: y,
+                previous_ai: previous_ai,
+            }
+        }
+    }
+
+    /// Wake the monster, and if it is hostile, make it come and see what is
+    /// going on at the given spot.
+    pub fn alert(&mut self, x: i32, y: i32) {
+        self.wake_up();
+        match self.ai {
+            Some(Ai::Alerted { x: ref mut old_x, y: ref mut old_y, .. }) => {
+                *old_x = x;
+                *old_y = y;
+            }
+            Some(ref ai) if ai.is_hostile() => {
+                let previous_ai = self.ai.take().unwrap_or(Ai::Basic);
+                self.ai = Some(Ai::Alerted {
+                    x: x,
+                    y: y,
+                    previous_ai: Box::new(previous_ai),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    pub fn ai_boss(
+        monster_id: usize,
+        tcod: &Tcod,
+        game: &mut Game,
+        objects: &mut Vec<Object>,
+        cooldown: i32,
+    ) -> Ai
+    {
+        let (monster_x, monster_y) = objects[monster_id].pos();
+        let player_alive = objects[PLAYER].fighter.map_or(false, |f| f.hp > 0);
+        let cooldown = cmp::max(cooldown - 1, 0);
+        if !tcod.fov.is_in_fov(monster_x, monster_y) || !player_alive {
+            return Ai::Boss { cooldown: cooldown };
+        }
+        if objects[monster_id].distance_to(&objects[PLAYER]) < 2.0 {
+            let (monster, player) = mut_two(monster_id, PLAYER, objects);
+            monster.attack(player, game);
+            return Ai::Boss { cooldown: cooldown };
+        }
+        if cooldown > 0 {
+            let (player_x, player_y) = objects[PLAYER].pos();
+            Object::move_towards(monster_id, player_x, player_y, &tcod.fov, game, objects);
+            return Ai::Boss { cooldown: cooldown };
+        }
+
+        let name = display_name(&objects[monster_id].name);
+        if game.rng.gen_range(0, 2) == 0 {
+            // Hurl fire at the player.
+            game.animations.push(Animation::Projectile {
+                from: objects[monster_id].pos(),
+                to: objects[PLAYER].pos(),
+                char: '*',
+                color: ORANGE,
+            });
+            game.messages.add(tr!("boss.fire", name), MessageCategory::Combat);
+            objects[PLAYER].add_effect(StatusEffect {
+                kind: StatusKind::Burning,
+                turns: BOSS_FIRE_TURNS,
+                strength: BOSS_FIRE_DAMAGE,
+            });
+        } else {
+            // Raise skeletons from the floor around it.
+            game.messages.add(tr!("boss.summon", name), MessageCategory::Combat);
+            let tiles = free_tiles_around(monster_x, monster_y, &game.map, objects);
+            for &(x, y) in tiles.iter().take(BOSS_SUMMONS) {
+                spawn_monster(create_monster("skeleton archer", x, y), game, objects);
+            }
+        }
+        Ai::Boss { cooldown: BOSS_COOLDOWN }
+    }
+
+    pub fn ai_summoner(
+        monster_id: usize,
+        tcod: &Tcod,
+        game: &mut Game,
+        objects: &mut Vec<Object>,
+        cooldown: i32,
+    ) -> Ai
+    {
+        let (monster_x, monster_y) = objects[monster_id].pos();
+        let player_alive = objects[PLAYER].fighter.map_or(false, |f| f.hp > 0);
+        let cooldown = cmp::max(cooldown - 1, 0);
+        if !tcod.fov.is_in_fov(monster_x, monster_y) || !player_alive {
+            return Ai::Summoner { cooldown: cooldown };
+        }
+        if objects[monster_id].distance_to(&objects[PLAYER]) < 2.0 {
+            let (monster, player) = mut_two(monster_id, PLAYER, objects);
+            monster.attack(player, game);
+            return Ai::Summoner { cooldown: cooldown };
+        }
+        let wanted = SUMMONER_CAP.saturating_sub(summons_about(monster_id, objects));
+        let tiles = free_tiles_around(monster_x, monster_y, &game.map, objects);
+        if cooldown > 0 || wanted == 0 || tiles.is_empty() {
+            return Ai::Summoner { cooldown: cooldown };
+        }
+
+        // Call up help onto the tiles around it.
+        let name = display_name(&objects[monster_id].name);
+        game.messages.add(tr!("summoner.call", name), MessageCategory::Combat);
+        for &(x, y) in tiles.iter().take(SUMMONS_PER_CALL.min(wanted)) {
+            spawn_monster(create_monster(SUMMONED_MONSTER, x, y), game, objects);
+        }
+        Ai::Summoner { cooldown: SUMMONER_COOLDOWN }
+    }
+
+    /// Move in a random direction. Whoever it stumbles into, friend or foe, gets hit.
+    pub fn stumble(monster_id: usize, tcod: &Tcod, game: &mut Game, objects: &mut [Object]) {
+        let (dx, dy) = random_direction(&mut game.rng);
+        let (x, y) = (objects[monster_id].x + dx, objects[monster_id].y + dy);
+        let victim_id = objects.iter().position(|object| {
+            object.alive && object.fighter.is_some() && object.pos() == (x, y)
+        });
+        match victim_id {
+            Some(victim_id) => {
+                let (monster, victim) = mut_two(monster_id, victim_id, objects);
+                monster.attack(victim, game);
+            }
+            None => Object::move_by(monster_id, dx, dy, &tcod.fov, game, objects),
+        }
+    }
+}
+
+/// The nearest living enemy the monster can see: the player, or a monster
+/// of a rival faction in plain sight not too far off.
+fn nearest_enemy(monster_id: usize, map: &Map, objects: &[Object]) -> Option<usize> {
+    let monster = &objects[monster_id];
+    objects
+        .iter()
+        .enumerate()
+        .filter(|&(id, object)| {
+            if id == monster_id || !object.alive || object.fighter.map_or(true, |f| f.hp <= 0) {
+                return false;
+            }
+            if id == PLAYER {
+                return true;
+            }
+            are_enemies(monster, object)
+                && monster.distance_to(object) <= INFIGHTING_SIGHT
+                && in_plain_sight(map, monster.pos(), object.pos())
+        })
+        .min_by_key(|&(_, object)| (object.x - monster.x).pow(2) + (object.y - monster.y).pow(2))
+        .map(|(id, _)| id)
+}
+
+/// Whether any monster in view is out for the player's blood.
+pub fn hostiles_in_view(fov: &Fov, objects: &[Object]) -> bool {
+    objects.iter().any(|object| {
+        object.alive
+            && object.fighter.is_some()
+            && object.ai.as_ref().map_or(false, Ai::is_hostile)
+            && fov.is_in_fov(object.x, object.y)
+    })
+}
+
+pub fn monsters_take_turns(tcod: &Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    monsters_act(tcod, game, objects);
+
+    game.turn += 1;
+    tick_effects(&tcod.fov, game, objects);
+    tick_hunger(game, objects);
+    tick_water(&tcod.fov, game, objects);
+    tick_lava(&tcod.fov, game, objects);
+    tick_respawn(&tcod.fov, game, objects);
+
+    // Let timed effects on the player run down.
+    if game.telepathy > 0 {
+        game.telepathy -= 1;
+        if game.telepathy == 0 {
+            game.messages.add(tr!("telepathy-ends"), MessageCategory::Item);
+        }
+    }
+}
+
+/// Let every monster act once, without the rest of the world moving on a
+/// turn. A slowed player gives the monsters an extra one of these.
+pub fn monsters_act(tcod: &Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    spread_noises(game, objects);
+    for id in 0..objects.len() {
+        // Take turn only if object is not player; slowed monsters sit out every other turn.
+        let slowed = objects[id].effects.iter().any(|effect| effect.kind == StatusKind::Slow);
+        if objects[id].ai.is_some() && !(slowed && game.turn % 2 == 1) {
+            let (x, y) = objects[id].pos();
+            let seen = tcod.fov.is_in_fov(x, y);
+            let glimpse = Glimpse::of(&objects[id]);
+            Object::ai_take_turn(id, tcod, game, objects);
+            // Walking out of sight leaves an afterimage where it was last seen.
+            let (x, y) = objects[id].pos();
+            if seen && objects[id].alive && !tcod.fov.is_in_fov(x, y) {
+                game.glimpses.push(glimpse);
+            }
+        }
+    }
+    pick_up_items(&tcod.fov, game, objects);
+}
+
+/// The first step along a shortest path from `from` to the nearest tile
+/// satisfying `goal`, or `None` if there is no way there. Walls and blocking
+/// objects are avoided, except on the goal tile itself.
+pub fn first_step_towards<F>(from: (i32, i32), map: &Map, objects: &[Object], goal: F) -> Option<(i32, i32)>
+where
+    F: Fn(i32, i32) -> bool,
+{
+    let path = pathfinding::nearest(from, &goal, |x, y| {
+        // Whatever is being walked up to may well block, like a monster to fight.
+        if goal(x, y) && !map_blocked(x, y, map) {
+            Some(1)
+        } else {
+            travel_cost(x, y, map, objects).filter(|&cost| cost < OCCUPIED_STEP_COST)
+        }
+    })?;
+    path.first().map(|&(x, y)| (x - from.0, y - from.1))
+}
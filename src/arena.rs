@@ -0,0 +1,145 @@
+//! The arena: waves of monsters in a single room, with a shop between them.
+
+use std::cmp;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::branch::Branch;
+use crate::config::config;
+use crate::daily::today;
+use crate::game::{Game, GameMode};
+use crate::items::{Item, add_to_inventory, create_item, item_price};
+use crate::locale::display_name;
+use crate::map::{Map, Rect, Tile, create_room, random_free_tile};
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER, random_monster};
+use crate::save::{SaveFile, load_save, write_save};
+use crate::ui::{INVENTORY_WIDTH, Tcod, menu, msgbox};
+
+pub const ARENA_FILE: &str = "arena.json";
+pub const ARENA_KILL_GOLD: u32 = 10;
+pub const ARENA_WAVE_GOLD: u32 = 25; // Bonus per wave cleared, times the wave number
+
+/// The arena: one big hall, with a few pillars to hide behind.
+pub fn make_arena_map(objects: &mut [Object]) -> Map {
+    let mut map = vec![vec![Tile::wall(); config().map.height as usize]; config().map.width as usize];
+    let arena = Rect::new(10, 5, config().map.width - 21, config().map.height - 11);
+    create_room(arena, &mut map);
+    for &(x, y) in &[(25, 14), (54, 14), (25, 28), (54, 28)] {
+        map[x as usize][y as usize] = Tile::wall();
+    }
+    let (x, y) = arena.center();
+    objects[PLAYER].set_pos(x, y);
+    map
+}
+
+/// Between waves, spend gold on supplies, then let the next wave in.
+pub fn update_arena(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    let wave = match game.mode {
+        GameMode::Arena(wave) => wave,
+        _ => return,
+    };
+    let cleared = !objects
+        .iter()
+        .any(|object| object.alive && object.ai.is_some() && object.fighter.is_some());
+    if !cleared || !objects[PLAYER].alive {
+        return;
+    }
+
+    if wave > 0 {
+        let bonus = ARENA_WAVE_GOLD * wave;
+        game.gold += bonus;
+        game.messages.add(tr!("arena.cleared", wave, bonus), MessageCategory::Lore);
+        arena_shop(tcod, game);
+    }
+
+    // Each wave brings more monsters, and harder-hitting ones.
+    let wave = wave + 1;
+    game.mode = GameMode::Arena(wave);
+    game.messages.add(tr!("arena.wave", wave), MessageCategory::Warning);
+    for _ in 0..wave + 2 {
+        let (x, y) = random_free_tile(&game.map, objects, &mut game.rng);
+        let mut monster = random_monster(x, y, Branch::Main, &mut game.rng);
+        if let Some(fighter) = monster.fighter.as_mut() {
+            fighter.power += (wave / 3) as i32;
+        }
+        objects.push(monster);
+    }
+}
+
+/// The short shop phase between two waves.
+pub fn arena_shop(tcod: &mut Tcod, game: &mut Game) {
+    let stock = [Item::Heal, Item::Lightning, Item::Confuse];
+    loop {
+        let options: Vec<String> = stock
+            .iter()
+            .map(|&item| tr!("shop.price-tag", display_name(&create_item(item, 0, 0).name), item_price(item)))
+            .collect();
+        let header = tr!("arena.shop", game.gold);
+        let item = match menu(&header, &options, INVENTORY_WIDTH, &mut *tcod.ui) {
+            Some(index) => stock[index],
+            None => break,
+        };
+        if game.gold < item_price(item) {
+            game.messages.add(tr!("shop.cannot-afford"), MessageCategory::Warning);
+        } else if let Err(item) = add_to_inventory(&mut game.inventory, create_item(item, 0, 0)) {
+            game.messages.add(tr!("inventory-full", display_name(&item.name)), MessageCategory::Warning);
+        } else {
+            game.gold -= item_price(item);
+        }
+    }
+}
+
+/// Best arena runs, kept apart from the other modes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ArenaRecord {
+    pub scores: Vec<ArenaScore>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArenaScore {
+    pub date: String,
+    pub wave: u32,
+    pub kills: u32,
+    pub score: u32,
+}
+
+impl SaveFile for ArenaRecord {
+    const PATH: &'static str = ARENA_FILE;
+}
+
+impl ArenaRecord {
+    pub fn load() -> Self {
+        load_save().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_save(self)
+    }
+}
+
+/// Score the arena run, and show it against the best ones.
+pub fn record_arena_score(tcod: &mut Tcod, wave: u32, kills: u32) {
+    // Waves only count once they have been cleared.
+    let cleared = wave.saturating_sub(1);
+    let score = ArenaScore {
+        date: today(),
+        wave: cleared,
+        kills: kills,
+        score: cleared * 100 + kills * 10,
+    };
+    let mut record = ArenaRecord::load();
+    record.scores.push(score.clone());
+    record.scores.sort_by_key(|score| cmp::Reverse(score.score));
+    record.scores.truncate(10);
+    // A lost score is no reason to keep the player from the menu.
+    let _ = record.save();
+
+    let mut text = format!("{}\n\n", tr!("arena.over", score.wave, score.kills, score.score));
+    text.push_str(&format!("{}\n", tr!("arena.scores-title")));
+    for score in &record.scores {
+        text.push_str(&format!("{}\n", tr!("arena.score", score.date, score.score, score.wave, score.kills)));
+    }
+    msgbox(&text, 50, &mut *tcod.ui);
+}
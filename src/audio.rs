@@ -0,0 +1,350 @@
+//! Sound effects and music, when built with the `audio` feature.
+
+#[cfg(feature = "audio")]
+use std::fs::File;
+
+#[cfg(feature = "audio")]
+use crate::config::config;
+use crate::game::{Game, GameMode};
+use crate::map::{Map, Terrain};
+use crate::object::{Object, PLAYER};
+use crate::ui::Tcod;
+
+#[cfg(feature = "audio")]
+pub const SOUND_DIR: &str = "sounds";
+#[cfg(feature = "audio")]
+pub const HEARING_RADIUS: f32 = 20.0; // Sounds further away than this are not heard
+#[cfg(feature = "audio")]
+pub const MUSIC_DIR: &str = "music";
+#[cfg(feature = "audio")]
+pub const MUSIC_FADE_SECONDS: f32 = 2.0;
+
+/// The background track that suits where the player is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MusicTheme {
+    Tutorial,
+    Arena,
+    UpperHalls,
+    Caverns,
+    Depths,
+}
+
+impl MusicTheme {
+    pub fn for_game(game: &Game) -> Self {
+        match game.mode {
+            GameMode::Tutorial(_) => MusicTheme::Tutorial,
+            GameMode::Arena(_) => MusicTheme::Arena,
+            _ => match game.dungeon_level {
+                0..=3 => MusicTheme::UpperHalls,
+                4..=7 => MusicTheme::Caverns,
+                _ => MusicTheme::Depths,
+            },
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn track(self) -> &'static str {
+        match self {
+            MusicTheme::Tutorial => "tutorial.ogg",
+            MusicTheme::Arena => "arena.ogg",
+            MusicTheme::UpperHalls => "upper-halls.ogg",
+            MusicTheme::Caverns => "caverns.ogg",
+            MusicTheme::Depths => "depths.ogg",
+        }
+    }
+}
+
+/// A looping track and how loud it currently is, before the player's volume setting.
+#[cfg(feature = "audio")]
+pub struct Layer {
+    pub sink: rodio::Sink,
+    pub gain: f32,
+}
+
+#[cfg(feature = "audio")]
+impl Layer {
+    /// Start streaming a track from the music directory, silent at first.
+    /// Missing or unreadable tracks just mean no music.
+    pub fn start(handle: &rodio::OutputStreamHandle, track: &str) -> Option<Self> {
+        use rodio::Source;
+        let file = File::open(format!("{}/{}", MUSIC_DIR, track)).ok()?;
+        let source = rodio::Decoder::new(std::io::BufReader::new(file)).ok()?;
+        let sink = rodio::Sink::try_new(handle).ok()?;
+        sink.set_volume(0.0);
+        sink.append(source.repeat_infinite());
+        Some(Layer { sink, gain: 0.0 })
+    }
+
+    /// Move one frame's worth of fading towards `target`, returning whether it got there.
+    pub fn fade_towards(&mut self, target: f32, volume: f32) -> bool {
+        let step = 1.0 / (MUSIC_FADE_SECONDS * config().screen.fps as f32);
+        self.gain = if self.gain < target {
+            (self.gain + step).min(target)
+        } else {
+            (self.gain - step).max(target)
+        };
+        self.sink.set_volume(self.gain * volume);
+        self.gain == target
+    }
+}
+
+/// A sound made somewhere on the map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sound {
+    pub effect: SoundEffect,
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SoundEffect {
+    Hit,
+    Death,
+    Thunder,
+    PickUp,
+}
+
+#[cfg(feature = "audio")]
+impl SoundEffect {
+    pub fn file(self) -> &'static str {
+        match self {
+            SoundEffect::Hit => "hit.ogg",
+            SoundEffect::Death => "death.ogg",
+            SoundEffect::Thunder => "thunder.ogg",
+            SoundEffect::PickUp => "pickup.ogg",
+        }
+    }
+}
+
+/// A sound that loops for as long as its source is on the level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AmbientSound {
+    Dripping,
+    /// Given off by lava rather than by an object.
+    LavaRumble,
+}
+
+impl AmbientSound {
+    /// The ambient sound given off by an object, if any.
+    pub fn of(object: &Object) -> Option<Self> {
+        match object.name.as_str() {
+            "fountain" => Some(AmbientSound::Dripping),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn file(self) -> &'static str {
+        match self {
+            AmbientSound::Dripping => "dripping.ogg",
+            AmbientSound::LavaRumble => "lava.ogg",
+        }
+    }
+}
+
+/// Play the sounds made this turn and keep the ambience in step with where
+/// the player stands. Sounds are heard through walls, so they can give away
+/// what goes on out of sight.
+pub fn play_sounds(tcod: &mut Tcod, game: &mut Game, objects: &[Object]) {
+    let (px, py) = objects[PLAYER].pos();
+    let volume = tcod.settings.sound_volume;
+    for sound in game.sounds.drain(..) {
+        tcod.audio.play_effect(sound.effect, sound.x - px, sound.y - py, volume);
+    }
+    let mut sources: Vec<_> = objects
+        .iter()
+        .filter_map(|object| AmbientSound::of(object).map(|sound| (sound, object.x - px, object.y - py)))
+        .collect();
+    // Only the nearest lava is heard, so a whole lake of it is one source.
+    let nearest_lava = lava_tiles(&game.map).min_by_key(|&(x, y)| (x - px).pow(2) + (y - py).pow(2));
+    if let Some((x, y)) = nearest_lava {
+        sources.push((AmbientSound::LavaRumble, x - px, y - py));
+    }
+    tcod.audio.update_ambience(&sources, volume);
+}
+
+/// Where the lava on the level is.
+fn lava_tiles(map: &Map) -> impl Iterator<Item = (i32, i32)> + '_ {
+    map.iter().enumerate().flat_map(|(x, column)| {
+        column
+            .iter()
+            .enumerate()
+            .filter(|&(_, tile)| tile.terrain == Terrain::Lava)
+            .map(move |(y, _)| (x as i32, y as i32))
+    })
+}
+
+/// How loud a sound at the given offset from the player is, from 0 to 1.
+#[cfg(feature = "audio")]
+pub fn audibility(dx: i32, dy: i32) -> f32 {
+    let distance = ((dx.pow(2) + dy.pow(2)) as f32).sqrt();
+    (1.0 - distance / HEARING_RADIUS).max(0.0)
+}
+
+/// Where to put a sound relative to the player's ears (at x = -0.5 and 0.5)
+/// for it to be panned the right way. Distance is left to `audibility`, so
+/// the emitter always sits one unit away in the sound's direction.
+#[cfg(feature = "audio")]
+pub fn emitter_position(dx: i32, dy: i32) -> [f32; 3] {
+    let distance = ((dx.pow(2) + dy.pow(2)) as f32).sqrt();
+    if distance == 0.0 {
+        [0.0, 0.0, 0.0]
+    } else {
+        [dx as f32 / distance, dy as f32 / distance, 0.0]
+    }
+}
+
+#[cfg(feature = "audio")]
+pub const LEFT_EAR: [f32; 3] = [-0.5, 0.0, 0.0];
+#[cfg(feature = "audio")]
+pub const RIGHT_EAR: [f32; 3] = [0.5, 0.0, 0.0];
+
+/// Open a sound file and get a sink ready to play it from the given offset.
+#[cfg(feature = "audio")]
+pub fn spatial_sink(
+    handle: &rodio::OutputStreamHandle,
+    file: &str,
+    dx: i32,
+    dy: i32,
+) -> Option<(rodio::SpatialSink, rodio::Decoder<std::io::BufReader<File>>)> {
+    let file = File::open(format!("{}/{}", SOUND_DIR, file)).ok()?;
+    let source = rodio::Decoder::new(std::io::BufReader::new(file)).ok()?;
+    let sink = rodio::SpatialSink::try_new(handle, emitter_position(dx, dy), LEFT_EAR, RIGHT_EAR).ok()?;
+    Some((sink, source))
+}
+
+/// Background music: one track per theme, crossfaded when the theme changes,
+/// plus a combat layer that swells while hostiles are in view. Also plays
+/// sound effects and ambient loops, panned and attenuated by where they come from.
+#[cfg(feature = "audio")]
+pub struct Audio {
+    // Playback stops as soon as the stream is dropped, so it has to be kept around.
+    pub stream: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
+    pub theme: Option<MusicTheme>,
+    pub track: Option<Layer>,
+    pub fading: Vec<Layer>,
+    pub combat: Option<Layer>,
+    /// Whether the combat layer could not be started, so it is not tried again every frame.
+    pub combat_missing: bool,
+    pub ambience: Vec<(AmbientSound, rodio::SpatialSink)>,
+}
+
+#[cfg(feature = "audio")]
+impl Audio {
+    // Not `Default`, since it opens the sound device.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Audio {
+            // Without a sound device the game simply stays silent.
+            stream: rodio::OutputStream::try_default().ok(),
+            theme: None,
+            track: None,
+            fading: vec![],
+            combat: None,
+            combat_missing: false,
+            ambience: vec![],
+        }
+    }
+
+    /// Advance the music by one frame.
+    pub fn update_music(&mut self, volume: u32, theme: MusicTheme, in_combat: bool) {
+        let handle = match self.stream {
+            Some((_, ref handle)) => handle,
+            None => return,
+        };
+        let volume = volume as f32 / 100.0;
+        if self.theme != Some(theme) {
+            self.theme = Some(theme);
+            self.fading.extend(self.track.take());
+            self.track = Layer::start(handle, theme.track());
+        }
+        if self.combat.is_none() && !self.combat_missing {
+            self.combat = Layer::start(handle, "combat.ogg");
+            self.combat_missing = self.combat.is_none();
+        }
+
+        if let Some(ref mut track) = self.track {
+            track.fade_towards(1.0, volume);
+        }
+        if let Some(ref mut combat) = self.combat {
+            combat.fade_towards(if in_combat { 1.0 } else { 0.0 }, volume);
+        }
+        // Dropping a sink stops it, so tracks that have faded out are let go.
+        self.fading.retain_mut(|layer| !layer.fade_towards(0.0, volume));
+    }
+
+    /// Play a sound effect coming from the given offset from the player.
+    pub fn play_effect(&mut self, effect: SoundEffect, dx: i32, dy: i32, volume: u32) {
+        let gain = audibility(dx, dy);
+        let handle = match self.stream {
+            Some((_, ref handle)) if gain > 0.0 => handle,
+            _ => return,
+        };
+        if let Some((sink, source)) = spatial_sink(handle, effect.file(), dx, dy) {
+            sink.set_volume(gain * volume as f32 / 100.0);
+            sink.append(source);
+            sink.detach();
+        }
+    }
+
+    /// Keep one loop going for each kind of ambient sound on the level,
+    /// coming from its nearest source.
+    pub fn update_ambience(&mut self, sources: &[(AmbientSound, i32, i32)], volume: u32) {
+        let handle = match self.stream {
+            Some((_, ref handle)) => handle,
+            None => return,
+        };
+        // Loops whose sources are all gone are dropped, which stops them.
+        self.ambience
+            .retain(|&(sound, _)| sources.iter().any(|&(source, _, _)| source == sound));
+        for &(sound, dx, dy) in sources {
+            let nearest = sources
+                .iter()
+                .filter(|&&(source, _, _)| source == sound)
+                .all(|&(_, x, y)| x.pow(2) + y.pow(2) >= dx.pow(2) + dy.pow(2));
+            if !nearest {
+                continue;
+            }
+            if !self.ambience.iter().any(|&(playing, _)| playing == sound) {
+                if let Some((sink, source)) = spatial_sink(handle, sound.file(), dx, dy) {
+                    use rodio::Source;
+                    sink.append(source.repeat_infinite());
+                    self.ambience.push((sound, sink));
+                }
+            }
+            if let Some(&(_, ref sink)) = self.ambience.iter().find(|&&(playing, _)| playing == sound) {
+                sink.set_emitter_position(emitter_position(dx, dy));
+                sink.set_volume(audibility(dx, dy) * volume as f32 / 100.0);
+            }
+        }
+    }
+
+    /// Silence everything, e.g. when going back to the main menu.
+    pub fn stop(&mut self) {
+        self.theme = None;
+        self.track = None;
+        self.fading.clear();
+        self.combat = None;
+        self.ambience.clear();
+    }
+}
+
+/// Stands in for the audio player when the game is built without audio.
+#[cfg(not(feature = "audio"))]
+#[derive(Default)]
+pub struct Audio;
+
+#[cfg(not(feature = "audio"))]
+impl Audio {
+    pub fn new() -> Self {
+        Audio
+    }
+
+    pub fn update_music(&mut self, _volume: u32, _theme: MusicTheme, _in_combat: bool) {}
+
+    pub fn play_effect(&mut self, _effect: SoundEffect, _dx: i32, _dy: i32, _volume: u32) {}
+
+    pub fn update_ambience(&mut self, _sources: &[(AmbientSound, i32, i32)], _volume: u32) {}
+
+    pub fn stop(&mut self) {}
+}
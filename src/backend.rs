@@ -0,0 +1,156 @@
+//! The seams between the game and the window it is played in. Everything the
+//! game draws goes through a `Renderer`, and everything the player presses
+//! comes in through an `InputSource`. A backend is one of each: a libtcod
+//! window in `libtcod.rs`, a terminal in `terminal.rs`, or a script played
+//! with nothing to show it in, in `headless.rs`.
+//!
+//! Colors, keys and mouse events keep libtcod's types, which are plain data,
+//! so that the rest of the game need not change with the backend. Built
+//! without the `tcod` feature, they are the stand-ins in `tcod_types.rs`,
+//! and only the other backends are there.
+
+use crate::tcod_types::colors::*;
+use crate::tcod_types::console::*;
+use crate::tcod_types::input::{Event, EventFlags, Key};
+
+/// The consoles the game draws on. The map and the panel are drawn off
+/// screen and then copied onto it; menus and windows are drawn on a console
+/// of their own, over a copy of what was on the screen before they opened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layer {
+    Screen,
+    /// The whole map, of which the part around the player is shown.
+    Map,
+    Panel,
+    Window,
+    Backdrop,
+}
+
+/// A grid of character cells for each layer, drawn with a foreground and
+/// background color that are set beforehand, as in libtcod.
+pub trait Renderer {
+    /// The size of a layer, in cells.
+    fn size(&self, layer: Layer) -> (i32, i32);
+
+    /// Make a layer the given size, and blank. Resizing the screen resizes
+    /// the window.
+    fn resize(&mut self, layer: Layer, width: i32, height: i32);
+
+    fn set_default_foreground(&mut self, layer: Layer, color: Color);
+    fn set_default_background(&mut self, layer: Layer, color: Color);
+    fn clear(&mut self, layer: Layer);
+    fn put_char(&mut self, layer: Layer, x: i32, y: i32, glyph: char, flag: BackgroundFlag);
+    fn set_char_background(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        color: Color,
+        flag: BackgroundFlag,
+    );
+    fn rect(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        flag: BackgroundFlag,
+    );
+    fn print_ex(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    );
+
+    /// Print text wrapped inside a rectangle; a height of 0 leaves it
+    /// unbounded. Returns the number of lines it took.
+    fn print_rect(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+    ) -> i32;
+
+    /// The number of lines `print_rect` would take for the text.
+    fn get_height_rect(
+        &self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+    ) -> i32;
+
+    /// The character in a cell, with its foreground and background colors.
+    fn cell(&self, layer: Layer, x: i32, y: i32) -> (char, Color, Color);
+
+    /// Copy part of a layer onto the screen, blending its colors in by the
+    /// given amounts.
+    fn blit(
+        &mut self,
+        from: Layer,
+        position: (i32, i32),
+        size: (i32, i32),
+        to: (i32, i32),
+        foreground_alpha: f32,
+        background_alpha: f32,
+    );
+
+    /// Copy the whole screen onto another layer, to draw back later.
+    fn copy_screen(&mut self, to: Layer);
+
+    /// Show what was drawn on the screen.
+    fn flush(&mut self);
+
+    fn is_fullscreen(&self) -> bool;
+    fn set_fullscreen(&mut self, fullscreen: bool);
+
+    /// Whether the player has closed the window.
+    fn window_closed(&self) -> bool;
+
+    /// Save a layer as a PNG image.
+    fn save_image(&self, layer: Layer, path: &str);
+
+    fn width(&self) -> i32 {
+        self.size(Layer::Screen).0
+    }
+
+    fn height(&self) -> i32 {
+        self.size(Layer::Screen).1
+    }
+}
+
+/// Key presses and mouse events. Each character typed arrives once, as a
+/// `KeyCode::Char` press whose `printable` is the character itself, shift and
+/// all; other keys arrive with their own codes.
+pub trait InputSource {
+    /// The next event of the kinds asked for, if one has come in.
+    fn check_for_event(&mut self, mask: EventFlags) -> Option<Event>;
+
+    /// Wait for the next event of the kinds asked for, dropping anything
+    /// that came in before.
+    fn wait_for_event(&mut self, mask: EventFlags) -> Option<Event>;
+
+    /// Wait for the player to press a key, dropping anything pressed before.
+    fn wait_for_keypress(&mut self) -> Key;
+}
+
+/// Something to draw on and read from: the whole of what the game needs of
+/// the window it is played in.
+pub trait Backend: Renderer + InputSource {}
+
+impl<T: Renderer + InputSource> Backend for T {}
+
+/// Whether the event mask takes in the given kind of event.
+pub fn wants(mask: EventFlags, kind: EventFlags) -> bool {
+    mask | kind == mask
+}
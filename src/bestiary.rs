@@ -0,0 +1,103 @@
+//! The bestiary, which fills in as the player meets and kills monsters.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::Backend;
+use crate::fov::Fov;
+use crate::locale::{display_name, lookup};
+use crate::object::Object;
+use crate::save::{SaveFile, load_save, write_save};
+use crate::ui::msgbox;
+
+pub const BESTIARY_FILE: &str = "bestiary.json";
+
+/// What the player has learned about each kind of monster, across all runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Bestiary {
+    /// Keyed by monster name.
+    pub entries: BTreeMap<String, BestiaryEntry>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BestiaryEntry {
+    pub glyph: char,
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    pub kills: u32,
+}
+
+/// Kills needed before a monster's stats, and then its lore, are revealed.
+pub const BESTIARY_STATS_KILLS: u32 = 1;
+pub const BESTIARY_LORE_KILLS: u32 = 3;
+
+impl SaveFile for Bestiary {
+    const PATH: &'static str = BESTIARY_FILE;
+}
+
+impl Bestiary {
+    pub fn load() -> Self {
+        load_save().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_save(self)
+    }
+
+    /// Record every monster in the player's field of view as encountered.
+    pub fn observe(&mut self, objects: &[Object], fov_map: &Fov) {
+        for object in objects {
+            if let (Some(fighter), Some(_)) = (object.fighter, object.ai.as_ref()) {
+                if fov_map.is_in_fov(object.x, object.y) {
+                    self.entries.entry(object.name.clone()).or_insert(BestiaryEntry {
+                        glyph: object.char,
+                        max_hp: fighter.max_hp,
+                        defense: fighter.defense,
+                        power: fighter.power,
+                        kills: 0,
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn record_kill(&mut self, name: &str) {
+        if let Some(entry) = self.entries.get_mut(name) {
+            entry.kills += 1;
+        }
+    }
+}
+
+/// Flavor text and special abilities of each kind of monster.
+pub fn monster_lore(name: &str) -> (String, String) {
+    match (lookup(&format!("lore.{}", name)), lookup(&format!("abilities.{}", name))) {
+        (Some(flavor), Some(abilities)) => (flavor, abilities),
+        _ => (tr!("lore.unknown"), tr!("abilities.unknown")),
+    }
+}
+
+/// Show every kind of monster encountered so far, revealing more as more are killed.
+pub fn show_bestiary(bestiary: &Bestiary, ui: &mut dyn Backend) {
+    let mut text = format!("{}\n\n", tr!("bestiary.title"));
+    if bestiary.entries.is_empty() {
+        text.push_str(&format!("{}\n", tr!("bestiary.empty")));
+    }
+    for (name, entry) in &bestiary.entries {
+        text.push_str(&tr!("bestiary.entry", entry.glyph, display_name(name), entry.kills));
+        text.push('\n');
+        if entry.kills >= BESTIARY_STATS_KILLS {
+            text.push_str(&format!(
+                "  {}\n",
+                tr!("bestiary.stats", entry.max_hp, entry.power, entry.defense)
+            ));
+        }
+        if entry.kills >= BESTIARY_LORE_KILLS {
+            let (flavor, abilities) = monster_lore(name);
+            text.push_str(&format!("  {}\n  {}\n", tr!("bestiary.abilities", abilities), flavor));
+        }
+    }
+    msgbox(&text, 60, ui);
+}
@@ -0,0 +1,123 @@
+//! Bones: levels left behind by dead characters, for later ones to find.
+
+use std::error::Error;
+use std::fs;
+
+use rand::StdRng;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::Ai;
+use crate::config::config;
+use crate::game::{Game, GameMode};
+use crate::items::{Item, create_item};
+use crate::locale::display_name;
+use crate::map::{Map, random_free_tile};
+use crate::messages::MessageCategory;
+use crate::object::{DeathCallback, Fighter, Object};
+use crate::save::{SaveFile, load_save, write_save};
+use crate::tcod_types::colors::*;
+
+pub const BONES_FILE: &str = "bones.json";
+pub const BONES_CHANCE: f32 = 0.5; // Chance that a dead character leaves bones behind
+
+/// What is left of a dead character: where it fell, what it carried, and
+/// enough of its stats to raise it again as a ghost.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bones {
+    pub dungeon_level: u32,
+    pub x: i32,
+    pub y: i32,
+    pub name: String,
+    pub max_hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    pub items: Vec<Item>,
+}
+
+impl Bones {
+    pub fn new(player: &Object, game: &Game) -> Self {
+        let fighter = player.fighter.expect("The player must be a fighter");
+        Bones {
+            dungeon_level: game.dungeon_level,
+            x: player.x,
+            y: player.y,
+            name: player.name.clone(),
+            max_hp: fighter.max_hp,
+            defense: fighter.defense,
+            power: fighter.power,
+            items: game
+                .inventory
+                .iter()
+                .flat_map(|object| vec![object.item; object.count as usize])
+                .flatten()
+                .collect(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_save(self)
+    }
+
+    /// Take the bones left on the given dungeon level, if any. Bones are
+    /// removed from disk once found, so each ghost only haunts a single run.
+    pub fn take(dungeon_level: u32) -> Option<Self> {
+        let bones: Bones = load_save()?;
+        if bones.dungeon_level != dungeon_level {
+            return None;
+        }
+        let _ = fs::remove_file(BONES_FILE);
+        Some(bones)
+    }
+}
+
+impl SaveFile for Bones {
+    const PATH: &'static str = BONES_FILE;
+}
+
+/// Raise the ghost of a dead character on the current level, guarding its old gear.
+pub fn place_bones(bones: &Bones, map: &Map, objects: &mut Vec<Object>, rng: &mut StdRng) {
+    // The level has been generated anew, so the spot it died on may now be rock.
+    let (x, y) = if bones.x >= 0
+        && bones.y >= 0
+        && bones.x < config().map.width
+        && bones.y < config().map.height
+        && !Object::is_blocked(bones.x, bones.y, map, objects)
+    {
+        (bones.x, bones.y)
+    } else {
+        random_free_tile(map, objects, rng)
+    };
+
+    for &item in &bones.items {
+        objects.push(create_item(item, x, y));
+    }
+
+    let mut ghost = Object::new(x, y, '@', "ghost", LIGHT_GREY, true);
+    ghost.alive = true;
+    ghost.fighter = Some(Fighter {
+        max_hp: bones.max_hp,
+        hp: bones.max_hp,
+        defense: bones.defense,
+        power: bones.power,
+        xp: 100,
+        venom: None,
+        on_death: DeathCallback::Monster,
+    });
+    ghost.ai = Some(Ai::Basic);
+    objects.push(ghost);
+}
+
+/// Raise the ghost of a previous character if it died on this level.
+pub fn haunt_level(game: &mut Game, objects: &mut Vec<Object>) {
+    if game.mode != GameMode::Normal {
+        return;
+    }
+    if let Some(bones) = Bones::take(game.dungeon_level) {
+        place_bones(&bones, &game.map, objects, &mut game.rng);
+        game.messages.add(
+            tr!("restless-spirit", display_name(&bones.name)),
+            MessageCategory::Lore,
+        );
+    }
+}
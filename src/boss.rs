@@ -0,0 +1,58 @@
+//! The lich who waits at the bottom of the dungeon, and the end of the game
+//! that comes with its death.
+
+use crate::ai::Ai;
+use crate::game::Game;
+use crate::messages::MessageCategory;
+use crate::object::{DeathCallback, Fighter, Object, PLAYER};
+use crate::tcod_types::colors::*;
+use crate::ui::{Tcod, msgbox};
+
+pub const BOSS_NAME: &str = "lich";
+pub const FINAL_LEVEL: u32 = 12; // The deepest level, with no stairs further down
+pub const BOSS_COOLDOWN: i32 = 3; // Turns between the lich's spells
+pub const BOSS_FIRE_DAMAGE: i32 = 3;
+pub const BOSS_FIRE_TURNS: i32 = 3;
+pub const BOSS_SUMMONS: usize = 2; // Skeletons raised by each summoning
+
+/// Create the lich.
+pub fn create_boss(x: i32, y: i32) -> Object {
+    let mut boss = Object::new(x, y, 'L', BOSS_NAME, LIGHT_PURPLE, true);
+    boss.fighter = Some(Fighter {
+        max_hp: 60,
+        hp: 60,
+        defense: 3,
+        power: 8,
+        xp: 1000,
+        venom: None,
+        on_death: DeathCallback::Monster,
+    });
+    boss.ai = Some(Ai::Boss { cooldown: BOSS_COOLDOWN });
+    boss.alive = true;
+    boss
+}
+
+/// On the final level, put the lich where the stairs down would have been.
+pub fn place_boss(game: &mut Game, objects: &mut Vec<Object>) {
+    if game.dungeon_level != FINAL_LEVEL {
+        return;
+    }
+    if let Some(stairs) = objects.iter().position(|object| object.name == "stairs") {
+        let (x, y) = objects[stairs].pos();
+        objects.remove(stairs);
+        objects.push(create_boss(x, y));
+        game.messages.add(tr!("boss.near"), MessageCategory::Warning);
+    }
+}
+
+/// The end-of-game screen for a run that killed the lich.
+pub fn show_victory(tcod: &mut Tcod, game: &Game, objects: &[Object]) {
+    let text = tr!(
+        "boss.victory",
+        objects[PLAYER].level,
+        game.turn,
+        game.kills,
+        game.gold
+    );
+    msgbox(&format!("\n{}\n", text), 50, &mut *tcod.ui);
+}
@@ -0,0 +1,181 @@
+//! The bot that plays the game by itself, for demos and balance testing.
+
+use crate::action::{Action, PlayerAction, perform_action};
+use crate::ai::{Ai, first_step_towards};
+use crate::audio::play_sounds;
+use crate::backend::Layer;
+use crate::config::config;
+use crate::fov::{Fov, compute_fov, update_doors};
+use crate::game::{Game, GameMode, new_game, process_events, world_takes_turn};
+use crate::gold::collect_gold;
+use crate::hunger::Hunger;
+use crate::items::{INVENTORY_SLOTS, Item};
+use crate::light::sight_radius;
+use crate::lighting::Lighting;
+use crate::object::{Object, PLAYER, level_up};
+use crate::profile::PlayerClass;
+use crate::render::{play_animations, render_all};
+use crate::status::StatusKind;
+use crate::tcod_types::input::{self, Event};
+use crate::ui::Tcod;
+
+pub const BOT_MAX_TURNS: u32 = 5000; // The bot gives up on runs longer than this
+
+/// Decide what the bot does this turn: heal when hurt, eat when hungry,
+/// fight what it sees, pick up loot, then explore.
+pub fn bot_action(fov: &Fov, game: &Game, objects: &[Object]) -> Action {
+    let player = &objects[PLAYER];
+    let find_item = |kind: Item| game.inventory.iter().position(|item| item.item == Some(kind));
+
+    if let Some(fighter) = player.fighter {
+        if fighter.hp < fighter.max_hp * 2 / 5 {
+            if let Some(index) = find_item(Item::Heal) {
+                return Action::UseItem(index);
+            }
+        }
+    }
+    if Hunger::of(game.nutrition) != Hunger::Fed {
+        if let Some(index) = find_item(Item::Ration) {
+            return Action::UseItem(index);
+        }
+    }
+
+    // Fight the nearest monster in view.
+    let enemy = objects
+        .iter()
+        .filter(|object| object.fighter.is_some() && fov.is_in_fov(object.x, object.y))
+        .filter(|object| !matches!(object.ai, Some(Ai::Idle) | Some(Ai::Follow) | None))
+        .min_by_key(|object| (player.distance_to(object) * 10.0) as i32);
+    if let Some(enemy) = enemy {
+        let tough = enemy.fighter.map_or(false, |f| f.hp > 10);
+        if tough && player.distance_to(enemy) <= config().gameplay.lightning_range as f32 {
+            if let Some(index) = find_item(Item::Lightning) {
+                return Action::UseItem(index);
+            }
+        }
+        let target = enemy.pos();
+        if let Some((dx, dy)) = first_step_towards(player.pos(), &game.map, objects, |x, y| (x, y) == target) {
+            return Action::Move(dx, dy);
+        }
+    }
+
+    // Loot, but never shop goods: the bot has no way of paying.
+    let loot = |x: i32, y: i32| {
+        objects
+            .iter()
+            .any(|object| object.pos() == (x, y) && object.item.is_some() && object.price.is_none())
+    };
+    if game.inventory.len() < INVENTORY_SLOTS {
+        if loot(player.x, player.y) {
+            return Action::PickUp;
+        }
+        let seen_loot = |x: i32, y: i32| fov.is_in_fov(x, y) && loot(x, y);
+        if let Some((dx, dy)) = first_step_towards(player.pos(), &game.map, objects, seen_loot) {
+            return Action::Move(dx, dy);
+        }
+    }
+
+    // Explore, then head down.
+    let unexplored = |x: i32, y: i32| !game.map[x as usize][y as usize].explored;
+    if let Some((dx, dy)) = first_step_towards(player.pos(), &game.map, objects, unexplored) {
+        return Action::Move(dx, dy);
+    }
+    let stairs = |x: i32, y: i32| objects.iter().any(|object| object.pos() == (x, y) && object.name == "stairs");
+    if stairs(player.x, player.y) {
+        return Action::Descend;
+    }
+    if let Some((dx, dy)) = first_step_towards(player.pos(), &game.map, objects, stairs) {
+        return Action::Move(dx, dy);
+    }
+    // Nowhere left to go, so the way on must be hidden.
+    Action::Search
+}
+
+/// How a run played by the bot ended.
+#[derive(Clone, Copy, Debug)]
+pub struct BotRun {
+    pub survived: bool,
+    pub depth: u32,
+    pub kills: u32,
+    pub turns: u32,
+}
+
+/// Let the bot play a whole run. When `watch` is set, every turn is drawn,
+/// and any key stops the run; otherwise it plays as fast as it can.
+pub fn run_bot(tcod: &mut Tcod, watch: bool) -> Option<BotRun> {
+    let (mut game, mut objects) = new_game(tcod, GameMode::Demo, PlayerClass::Fighter);
+    let mut turns = 0;
+    while objects[PLAYER].alive && !game.won && turns < BOT_MAX_TURNS && !tcod.ui.window_closed() {
+        if watch {
+            tcod.ui.clear(Layer::Map);
+            render_all(tcod, &mut game, &objects, true);
+            tcod.ui.flush();
+            if let Some(Event::Key(_)) = tcod.ui.check_for_event(input::KEY_PRESS) {
+                return None;
+            }
+        } else {
+            // Nothing is drawn, but the bot still needs to know what it can see.
+            let lighting = Lighting::of(&game.map, &objects);
+            let blind = objects[PLAYER].has_effect(StatusKind::Blindness);
+            let radius = sight_radius(&game, &objects[PLAYER]);
+            compute_fov(tcod, objects[PLAYER].pos(), radius, Some(&lighting).filter(|_| !blind));
+            for y in 0..config().map.height {
+                for x in 0..config().map.width {
+                    if tcod.fov.is_in_fov(x, y) {
+                        game.map[x as usize][y as usize].explored = true;
+                    }
+                }
+            }
+        }
+
+        let action = bot_action(&tcod.fov, &game, &objects);
+        let player_action = perform_action(action, tcod, &mut game, &mut objects);
+        if objects[PLAYER].alive && player_action == PlayerAction::TookTurn {
+            world_takes_turn(tcod, &mut game, &mut objects);
+        }
+        update_doors(tcod, &game.map);
+        collect_gold(&mut game, &mut objects);
+        process_events(&mut game, &mut objects);
+        level_up(tcod, &mut game, &mut objects);
+        tcod.observer.publish(&game, &objects, &tcod.fov);
+        if watch {
+            play_sounds(tcod, &mut game, &objects);
+            play_animations(tcod, &mut game, &objects);
+        } else {
+            game.sounds.clear();
+            game.animations.clear();
+        }
+        turns += 1;
+    }
+    Some(BotRun {
+        survived: objects[PLAYER].alive,
+        depth: game.dungeon_level,
+        kills: game.kills,
+        turns: turns,
+    })
+}
+
+/// Attract mode: watch the bot play until a key is pressed.
+pub fn play_demo(tcod: &mut Tcod) {
+    while !tcod.ui.window_closed() {
+        if run_bot(tcod, true).is_none() {
+            break;
+        }
+    }
+}
+
+/// Let the bot play many runs, and report how it fared, to help balance the game.
+pub fn run_balance_test(tcod: &mut Tcod, runs: u32) {
+    let results: Vec<BotRun> = (0..runs).filter_map(|_| run_bot(tcod, false)).collect();
+    if results.is_empty() {
+        return;
+    }
+    let count = results.len() as f32;
+    let survived = results.iter().filter(|run| run.survived).count();
+    let average = |value: fn(&BotRun) -> u32| results.iter().map(value).sum::<u32>() as f32 / count;
+    println!("Bot runs:       {}", results.len());
+    println!("Survived:       {} ({:.1}%)", survived, survived as f32 / count * 100.0);
+    println!("Average depth:  {:.2}", average(|run| run.depth));
+    println!("Average kills:  {:.2}", average(|run| run.kills));
+    println!("Average turns:  {:.1}", average(|run| run.turns));
+}
@@ -0,0 +1,181 @@
+//! Branches: side passages off the main dungeon, each with its own look,
+//! its own monsters and its own way of laying out levels. A branch is
+//! entered by special stairs on one level of the main dungeon, runs a few
+//! levels deep, and then leads back down into the main dungeon.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Generator, config};
+use crate::game::Game;
+use crate::map::random_free_tile;
+use crate::messages::MessageCategory;
+use crate::object::Object;
+use crate::render::{COLOR_DARK_GROUND, COLOR_DARK_WALL, COLOR_LIGHT_GROUND, COLOR_LIGHT_WALL};
+use crate::tcod_types::colors::*;
+
+pub const BRANCH_STAIRS: &str = "branch stairs";
+pub const BRANCH_LENGTH: u32 = 3; // Levels in each branch before it rejoins the main dungeon
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Branch {
+    #[default]
+    Main,
+    /// Flooded tunnels full of spiders.
+    Sewers,
+    /// Tidy halls of the dead.
+    Crypt,
+    /// Natural caverns where the trolls live.
+    Caves,
+}
+
+/// The colors a branch's walls and floor are drawn in.
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    pub dark_wall: Color,
+    pub light_wall: Color,
+    pub dark_ground: Color,
+    pub light_ground: Color,
+}
+
+impl Branch {
+    pub const SIDE_BRANCHES: [Branch; 3] = [Branch::Sewers, Branch::Crypt, Branch::Caves];
+
+    pub fn name(self) -> String {
+        match self {
+            Branch::Main => tr!("branch.main"),
+            Branch::Sewers => tr!("branch.sewers"),
+            Branch::Crypt => tr!("branch.crypt"),
+            Branch::Caves => tr!("branch.caves"),
+        }
+    }
+
+    /// The level of the main dungeon that holds the stairs into the branch.
+    pub fn entrance(self) -> Option<u32> {
+        match self {
+            Branch::Main => None,
+            Branch::Sewers => Some(2),
+            Branch::Crypt => Some(4),
+            Branch::Caves => Some(6),
+        }
+    }
+
+    /// The branch whose stairs are found on the given level of the main dungeon.
+    pub fn entered_at(depth: u32) -> Option<Branch> {
+        Branch::SIDE_BRANCHES
+            .iter()
+            .cloned()
+            .find(|branch| branch.entrance() == Some(depth))
+    }
+
+    /// Whether the given depth is the deepest level of the branch, whose
+    /// stairs lead back to the main dungeon.
+    pub fn ends_at(self, depth: u32) -> bool {
+        self.entrance().map_or(false, |entrance| depth >= entrance + BRANCH_LENGTH)
+    }
+
+    pub fn generator(self, depth: u32) -> Generator {
+        match self {
+            Branch::Main => config().map.generator.for_depth(depth),
+            Branch::Sewers => Generator::Rooms,
+            Branch::Crypt => Generator::Bsp,
+            Branch::Caves => Generator::Caves,
+        }
+    }
+
+    /// The monsters found in the branch, each with its chance of turning up.
+    pub fn monsters(self) -> &'static [(&'static str, f32)] {
+        match self {
+            Branch::Main => &[
+                ("orc", 0.45),
+                ("orc shaman", 0.05),
+                ("jackal", 0.1),
+                ("giant spider", 0.1),
+                ("skeleton archer", 0.1),
+                ("troll", 0.2),
+            ],
+            Branch::Sewers => &[("giant spider", 0.6), ("orc", 0.4)],
+            Branch::Crypt => &[("skeleton archer", 0.7), ("orc", 0.2), ("troll", 0.1)],
+            Branch::Caves => &[
+                ("troll", 0.35),
+                ("giant spider", 0.25),
+                ("jackal", 0.2),
+                ("orc", 0.2),
+            ],
+        }
+    }
+
+    /// Chance of a pool of water in each room.
+    pub fn pool_chance(self) -> f32 {
+        match self {
+            Branch::Main => 0.1,
+            Branch::Sewers => 0.5,
+            Branch::Crypt => 0.0,
+            Branch::Caves => 0.2,
+        }
+    }
+
+    /// Chance of a patch of lava in each room.
+    pub fn lava_chance(self) -> f32 {
+        match self {
+            Branch::Main => 0.03,
+            Branch::Caves => 0.15,
+            _ => 0.0,
+        }
+    }
+
+    /// Chance of a brazier burning in each room.
+    pub fn brazier_chance(self) -> f32 {
+        match self {
+            Branch::Main => 0.15,
+            Branch::Crypt => 0.3,
+            _ => 0.0,
+        }
+    }
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Branch::Main => Palette {
+                dark_wall: COLOR_DARK_WALL,
+                light_wall: COLOR_LIGHT_WALL,
+                dark_ground: COLOR_DARK_GROUND,
+                light_ground: COLOR_LIGHT_GROUND,
+            },
+            Branch::Sewers => Palette {
+                dark_wall: Color { r: 20, g: 50, b: 30 },
+                light_wall: Color { r: 70, g: 110, b: 60 },
+                dark_ground: Color { r: 30, g: 60, b: 60 },
+                light_ground: Color { r: 90, g: 140, b: 120 },
+            },
+            Branch::Crypt => Palette {
+                dark_wall: Color { r: 40, g: 40, b: 50 },
+                light_wall: Color { r: 120, g: 120, b: 130 },
+                dark_ground: Color { r: 60, g: 60, b: 70 },
+                light_ground: Color { r: 170, g: 170, b: 180 },
+            },
+            Branch::Caves => Palette {
+                dark_wall: Color { r: 50, g: 30, b: 10 },
+                light_wall: Color { r: 110, g: 70, b: 40 },
+                dark_ground: Color { r: 70, g: 50, b: 30 },
+                light_ground: Color { r: 160, g: 120, b: 80 },
+            },
+        }
+    }
+}
+
+/// Whether the object is a way down: the stairs, or the stairs into a branch.
+pub fn is_stairs(object: &Object) -> bool {
+    object.name == "stairs" || object.name == BRANCH_STAIRS
+}
+
+/// On a level of the main dungeon with a branch off it, put down the stairs
+/// into the branch somewhere on it.
+pub fn place_branch_stairs(game: &mut Game, objects: &mut Vec<Object>) {
+    if game.branch != Branch::Main {
+        return;
+    }
+    if let Some(branch) = Branch::entered_at(game.dungeon_level) {
+        let (x, y) = random_free_tile(&game.map, objects, &mut game.rng);
+        objects.push(Object::new(x, y, '>', BRANCH_STAIRS, LIGHT_GREEN, false));
+        game.messages.add(tr!("branch.stairs", branch.name()), MessageCategory::Lore);
+    }
+}
@@ -0,0 +1,143 @@
+//! The rules of combat. A blow lands, on average, for the attacker's power
+//! less the target's defense, and the dice of whatever it was struck with
+//! decide how far above or below that each one falls. Now and then a blow
+//! is a critical hit, and lands for a good deal more.
+
+use std::fmt;
+
+use rand::{Rng, StdRng};
+
+use crate::equipment::Slot;
+use crate::game::Game;
+use crate::items::Item;
+use crate::object::{DeathCallback, Object};
+
+pub const CRITICAL_CHANCE: f32 = 0.05; // Chance of a blow that lands at all being a critical hit
+pub const CRITICAL_MULTIPLIER: i32 = 2; // Damage done by a critical hit is multiplied by this
+
+/// Fists, claws and teeth: what everyone without a weapon strikes with.
+pub const NATURAL_DICE: Dice = Dice { count: 1, sides: 3 };
+
+/// Dice as in "2d4": so many dice of so many sides each, rolled and added up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dice {
+    pub count: i32,
+    pub sides: i32,
+}
+
+impl Dice {
+    pub fn roll(self, rng: &mut StdRng) -> i32 {
+        (0..self.count).map(|_| rng.gen_range(1, self.sides + 1)).sum()
+    }
+
+    /// What the dice roll on average, rounded down.
+    pub fn middle(self) -> i32 {
+        self.count * (self.sides + 1) / 2
+    }
+
+    /// How far above the middle a roll can fall.
+    pub fn spread(self) -> i32 {
+        self.count * self.sides - self.middle()
+    }
+}
+
+impl fmt::Display for Dice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)
+    }
+}
+
+/// The dice of blows struck with the item, if it is a weapon held in the hand.
+pub fn weapon_dice(item: Item) -> Option<Dice> {
+    match item {
+        Item::Sword => Some(Dice { count: 2, sides: 4 }),
+        Item::Pickaxe => Some(Dice { count: 1, sides: 5 }),
+        _ => None,
+    }
+}
+
+/// The dice the fighter strikes with: those of the weapon the player
+/// wields, if they wield one, or else its own.
+pub fn dice_of(attacker: &Object, game: &Game) -> Dice {
+    if attacker.fighter.map_or(true, |f| f.on_death != DeathCallback::Player) {
+        return NATURAL_DICE;
+    }
+    game.inventory
+        .iter()
+        .filter(|item| item.equipment.map_or(false, |e| e.equipped && e.slot == Slot::Hand))
+        .find_map(|item| item.item.and_then(weapon_dice))
+        .unwrap_or(NATURAL_DICE)
+}
+
+/// How hard a blow landed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Blow {
+    pub damage: i32,
+    pub critical: bool,
+}
+
+/// Roll a blow of the given power, struck with the given dice, against a
+/// target of the given defense. Only a blow that does some damage can be
+/// a critical hit.
+pub fn roll_blow(power: i32, dice: Dice, defense: i32, rng: &mut StdRng) -> Blow {
+    let damage = power + dice.roll(rng) - dice.middle() - defense;
+    let critical = damage > 0 && rng.next_f32() < CRITICAL_CHANCE;
+    Blow {
+        damage: if critical { damage * CRITICAL_MULTIPLIER } else { damage },
+        critical: critical,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn rng(seed: usize) -> StdRng {
+        SeedableRng::from_seed(&[seed][..])
+    }
+
+    #[test]
+    fn dice_roll_within_their_range() {
+        let dice = Dice { count: 2, sides: 4 };
+        let mut rng = rng(1);
+        for _ in 0..1000 {
+            let roll = dice.roll(&mut rng);
+            assert!((2..=8).contains(&roll), "2d4 rolled {}", roll);
+        }
+        assert_eq!(dice.middle(), 5);
+        assert_eq!(dice.spread(), 3);
+    }
+
+    #[test]
+    fn blows_fall_within_the_dice_of_the_average() {
+        let dice = Dice { count: 2, sides: 4 };
+        let (power, defense) = (8, 2);
+        let lowest = power - defense - (dice.middle() - dice.count);
+        let highest = power - defense + dice.spread();
+        let mut seen_critical = false;
+        for seed in 0..2000 {
+            let blow = roll_blow(power, dice, defense, &mut rng(seed));
+            if blow.critical {
+                seen_critical = true;
+                assert_eq!(blow.damage % CRITICAL_MULTIPLIER, 0);
+                let damage = blow.damage / CRITICAL_MULTIPLIER;
+                assert!((lowest..=highest).contains(&damage), "critical of {}", blow.damage);
+            } else {
+                assert!((lowest..=highest).contains(&blow.damage), "blow of {}", blow.damage);
+            }
+        }
+        assert!(seen_critical);
+    }
+
+    #[test]
+    fn blows_that_do_no_damage_are_never_critical() {
+        let dice = NATURAL_DICE;
+        for seed in 0..2000 {
+            let blow = roll_blow(1, dice, 10, &mut rng(seed));
+            assert!(blow.damage <= 0);
+            assert!(!blow.critical);
+        }
+    }
+}
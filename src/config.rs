@@ -0,0 +1,329 @@
+//! Configuration read from `config.toml`, which the player edits by hand.
+
+use std::cmp;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "tcod")]
+use tcod::map::FovAlgorithm;
+
+use crate::render::PANEL_HEIGHT;
+use crate::save::LOAD_ERRORS;
+
+pub const CONFIG_FILE: &str = "config.toml";
+// The tutorial's rooms need at least this much space.
+pub const MIN_MAP_WIDTH: i32 = 64;
+pub const MIN_MAP_HEIGHT: i32 = 28;
+// Levels from this depth on are partitioned rather than scattered.
+pub const BSP_DEPTH: u32 = 4;
+// Every this many levels, the dungeon gives way to natural caves.
+pub const CAVE_INTERVAL: u32 = 3;
+
+/// Tunables that used to be compiled in, read from `config.toml` so they can
+/// be changed without rebuilding. The file is written with the defaults on
+/// first run, and any setting left out of it keeps its default.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub screen: ScreenConfig,
+    pub fov: FovConfig,
+    pub map: MapConfig,
+    pub gameplay: GameplayConfig,
+    pub macros: MacroConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenConfig {
+    pub width: i32,
+    pub height: i32,
+    /// Frames-per-second maximum.
+    pub fps: i32,
+}
+
+impl Default for ScreenConfig {
+    fn default() -> Self {
+        ScreenConfig {
+            width: 80,
+            height: 50,
+            fps: 20,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FovConfig {
+    pub algorithm: FovSetting,
+    pub light_walls: bool,
+    pub torch_radius: i32,
+}
+
+impl Default for FovConfig {
+    fn default() -> Self {
+        FovConfig {
+            algorithm: FovSetting::Symmetric,
+            light_walls: true,
+            torch_radius: 10,
+        }
+    }
+}
+
+/// The FOV algorithms offered by libtcod, by the names used in `config.toml`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FovSetting {
+    /// Computed by the game itself rather than libtcod: whatever the player
+    /// can see can also see the player.
+    Symmetric,
+    Basic,
+    Diamond,
+    Shadow,
+    Permissive0,
+    Permissive1,
+    Permissive2,
+    Permissive3,
+    Permissive4,
+    Permissive5,
+    Permissive6,
+    Permissive7,
+    Permissive8,
+    Restrictive,
+}
+
+impl FovSetting {
+    pub const ALL: [FovSetting; 14] = [
+        FovSetting::Symmetric,
+        FovSetting::Basic,
+        FovSetting::Diamond,
+        FovSetting::Shadow,
+        FovSetting::Permissive0,
+        FovSetting::Permissive1,
+        FovSetting::Permissive2,
+        FovSetting::Permissive3,
+        FovSetting::Permissive4,
+        FovSetting::Permissive5,
+        FovSetting::Permissive6,
+        FovSetting::Permissive7,
+        FovSetting::Permissive8,
+        FovSetting::Restrictive,
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            FovSetting::Symmetric => tr!("fov.symmetric"),
+            FovSetting::Basic => tr!("fov.basic"),
+            FovSetting::Diamond => tr!("fov.diamond"),
+            FovSetting::Shadow => tr!("fov.shadow"),
+            FovSetting::Permissive0 => tr!("fov.permissive", 0),
+            FovSetting::Permissive1 => tr!("fov.permissive", 1),
+            FovSetting::Permissive2 => tr!("fov.permissive", 2),
+            FovSetting::Permissive3 => tr!("fov.permissive", 3),
+            FovSetting::Permissive4 => tr!("fov.permissive", 4),
+            FovSetting::Permissive5 => tr!("fov.permissive", 5),
+            FovSetting::Permissive6 => tr!("fov.permissive", 6),
+            FovSetting::Permissive7 => tr!("fov.permissive", 7),
+            FovSetting::Permissive8 => tr!("fov.permissive", 8),
+            FovSetting::Restrictive => tr!("fov.restrictive"),
+        }
+    }
+
+    /// The libtcod algorithm, for the settings that have one.
+    #[cfg(feature = "tcod")]
+    pub fn to_tcod(self) -> Option<FovAlgorithm> {
+        let algorithm = match self {
+            FovSetting::Symmetric => return None,
+            FovSetting::Basic => FovAlgorithm::Basic,
+            FovSetting::Diamond => FovAlgorithm::Diamond,
+            FovSetting::Shadow => FovAlgorithm::Shadow,
+            FovSetting::Permissive0 => FovAlgorithm::Permissive0,
+            FovSetting::Permissive1 => FovAlgorithm::Permissive1,
+            FovSetting::Permissive2 => FovAlgorithm::Permissive2,
+            FovSetting::Permissive3 => FovAlgorithm::Permissive3,
+            FovSetting::Permissive4 => FovAlgorithm::Permissive4,
+            FovSetting::Permissive5 => FovAlgorithm::Permissive5,
+            FovSetting::Permissive6 => FovAlgorithm::Permissive6,
+            FovSetting::Permissive7 => FovAlgorithm::Permissive7,
+            FovSetting::Permissive8 => FovAlgorithm::Permissive8,
+            FovSetting::Restrictive => FovAlgorithm::Restrictive,
+        };
+        Some(algorithm)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapConfig {
+    pub width: i32,
+    pub height: i32,
+    pub room_max_size: i32,
+    pub room_min_size: i32,
+    pub max_rooms: i32,
+    pub max_room_monsters: i32,
+    pub max_room_items: i32,
+    pub generator: GeneratorSetting,
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        MapConfig {
+            width: 80,
+            height: 43,
+            room_max_size: 10,
+            room_min_size: 6,
+            max_rooms: 30,
+            max_room_monsters: 3,
+            max_room_items: 2,
+            generator: GeneratorSetting::ByDepth,
+        }
+    }
+}
+
+/// How levels are laid out, by the names used in `config.toml`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeneratorSetting {
+    /// Each depth has its own kind of level, so deeper floors feel different.
+    ByDepth,
+    Rooms,
+    Bsp,
+    Caves,
+}
+
+/// The ways a level can be generated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Generator {
+    /// Rooms scattered at random.
+    Rooms,
+    /// Binary space partitioning.
+    Bsp,
+    /// Caverns grown by a cellular automaton.
+    Caves,
+}
+
+impl GeneratorSetting {
+    pub fn for_depth(self, depth: u32) -> Generator {
+        match self {
+            GeneratorSetting::ByDepth if depth.is_multiple_of(CAVE_INTERVAL) => Generator::Caves,
+            GeneratorSetting::ByDepth if depth >= BSP_DEPTH => Generator::Bsp,
+            GeneratorSetting::ByDepth => Generator::Rooms,
+            GeneratorSetting::Rooms => Generator::Rooms,
+            GeneratorSetting::Bsp => Generator::Bsp,
+            GeneratorSetting::Caves => Generator::Caves,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameplayConfig {
+    pub heal_amount: i32,
+    pub lightning_damage: i32,
+    pub lightning_range: i32,
+    pub confuse_range: i32,
+    pub confuse_num_turns: i32,
+    pub starting_gold: u32,
+    pub telepathy_turns: u32,
+    pub clairvoyance_radius: i32,
+    /// Share of the player's hit points restored on the way down the stairs, in percent.
+    pub descend_heal_percent: i32,
+    pub fire_range: i32,
+    pub fire_damage: i32,
+    pub fire_turns: i32,
+    pub regeneration_amount: i32,
+    pub regeneration_turns: i32,
+    /// Monsters flee once their hit points drop below this share of the maximum, in percent.
+    pub flee_hp_percent: i32,
+    /// Turns' worth of food the player can hold, and starts out with.
+    pub max_nutrition: i32,
+    /// Turns' worth of food in a ration.
+    pub ration_nutrition: i32,
+}
+
+impl Default for GameplayConfig {
+    fn default() -> Self {
+        GameplayConfig {
+            heal_amount: 4,
+            lightning_damage: 40,
+            lightning_range: 5,
+            confuse_range: 8,
+            confuse_num_turns: 10,
+            starting_gold: 50,
+            telepathy_turns: 30,
+            clairvoyance_radius: 6,
+            descend_heal_percent: 20,
+            fire_range: 6,
+            fire_damage: 3,
+            fire_turns: 5,
+            regeneration_amount: 1,
+            regeneration_turns: 20,
+            flee_hp_percent: 25,
+            max_nutrition: 1500,
+            ration_nutrition: 800,
+        }
+    }
+}
+
+/// The keys that play macros, one slot each and numbered in order. With
+/// Shift held, they record one instead.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MacroConfig {
+    /// Named as in `keybindings.toml`.
+    pub keys: Vec<String>,
+}
+
+impl Default for MacroConfig {
+    fn default() -> Self {
+        MacroConfig {
+            keys: ["F1", "F2", "F3", "F4"].iter().map(|&key| key.into()).collect(),
+        }
+    }
+}
+
+impl Config {
+    /// Read `config.toml`, creating it if it is missing. A file that cannot
+    /// be parsed is reported, and the defaults are used instead.
+    pub fn load() -> Self {
+        let config = match fs::read_to_string(CONFIG_FILE) {
+            Ok(text) => toml::from_str(&text).unwrap_or_else(|e| {
+                let error = tr!("config.invalid", CONFIG_FILE, e);
+                LOAD_ERRORS.with(|errors| errors.borrow_mut().push(error));
+                Config::default()
+            }),
+            Err(_) => {
+                let config = Config::default();
+                if let Ok(text) = toml::to_string(&config) {
+                    let _ = fs::write(CONFIG_FILE, text);
+                }
+                config
+            }
+        };
+        config.sanitized()
+    }
+
+    /// Keep the values within what the game can cope with: rooms have to fit
+    /// in the map. A map bigger than the window scrolls with the player.
+    pub fn sanitized(mut self) -> Self {
+        self.screen.width = cmp::max(self.screen.width, MIN_MAP_WIDTH);
+        self.screen.height = cmp::max(self.screen.height, MIN_MAP_HEIGHT + PANEL_HEIGHT);
+        self.screen.fps = cmp::max(self.screen.fps, 1);
+        self.map.width = cmp::max(self.map.width, MIN_MAP_WIDTH);
+        self.map.height = cmp::max(self.map.height, MIN_MAP_HEIGHT);
+        self.map.room_min_size = cmp::max(self.map.room_min_size, 3);
+        self.map.room_max_size = cmp::min(
+            cmp::max(self.map.room_max_size, self.map.room_min_size),
+            cmp::min(self.map.width, self.map.height) - 1,
+        );
+        self.map.room_min_size = cmp::min(self.map.room_min_size, self.map.room_max_size);
+        self
+    }
+}
+
+pub static CONFIG: std::sync::OnceLock<Config> = std::sync::OnceLock::new();
+
+/// The tunables from `config.toml`, loaded the first time they are needed.
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::load)
+}
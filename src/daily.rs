@@ -0,0 +1,108 @@
+//! Daily challenges, played on a dungeon seeded from the date.
+
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::Backend;
+use crate::game::{GameMode, new_game, play_game};
+use crate::profile::PlayerClass;
+use crate::save::{SaveFile, load_save, write_save};
+use crate::ui::{Tcod, msgbox};
+
+pub const DAILY_FILE: &str = "daily.json";
+
+/// Persistent record of daily challenge attempts, kept apart from regular games.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DailyRecord {
+    pub last_attempt: String,
+    pub scores: Vec<DailyScore>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DailyScore {
+    pub date: String,
+    pub kills: u32,
+    pub survived: bool,
+}
+
+impl SaveFile for DailyRecord {
+    const PATH: &'static str = DAILY_FILE;
+}
+
+impl DailyRecord {
+    /// Load the record from disk, starting a fresh one if there is none yet.
+    pub fn load() -> Self {
+        load_save().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_save(self)
+    }
+}
+
+/// Today's date (UTC, so every player shares the same day) as `YYYY-MM-DD`.
+pub fn today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+/// Derive the dungeon seed from a `YYYY-MM-DD` date, e.g. 2019-10-31 -> 20191031.
+pub fn daily_seed(date: &str) -> usize {
+    date.replace('-', "").parse().unwrap_or(0)
+}
+
+/// Start today's daily challenge, unless it has already been attempted.
+pub fn play_daily(tcod: &mut Tcod) {
+    let date = today();
+    let mut record = DailyRecord::load();
+    if record.last_attempt == date {
+        msgbox(
+            &format!("\n{}\n", tr!("daily.already-attempted")),
+            24,
+            &mut *tcod.ui,
+        );
+        return;
+    }
+
+    // Lock the day out as soon as the run starts, so quitting does not allow a retry.
+    record.last_attempt = date.clone();
+    if let Err(e) = record.save() {
+        msgbox(&format!("\n{}\n", tr!("daily.record-failed", e)), 50, &mut *tcod.ui);
+        return;
+    }
+
+    let (mut game, mut objects) = new_game(tcod, GameMode::Daily(date), PlayerClass::Fighter);
+    play_game(tcod, &mut game, &mut objects);
+}
+
+pub fn record_daily_score(date: &str, kills: u32, survived: bool) {
+    let mut record = DailyRecord::load();
+    record.scores.push(DailyScore {
+        date: date.into(),
+        kills: kills,
+        survived: survived,
+    });
+    // There is nowhere left to report the error, so a lost score is all we can do.
+    let _ = record.save();
+}
+
+/// Show the best daily challenge results, best first.
+pub fn show_daily_scores(ui: &mut dyn Backend) {
+    let mut scores = DailyRecord::load().scores;
+    scores.sort_by(|a, b| b.kills.cmp(&a.kills).then_with(|| b.date.cmp(&a.date)));
+
+    let mut text = format!("{}\n\n", tr!("daily.scores-title"));
+    if scores.is_empty() {
+        text.push_str(&format!("{}\n", tr!("daily.no-scores")));
+    }
+    for score in scores.iter().take(10) {
+        let outcome = if score.survived {
+            tr!("daily.survived")
+        } else {
+            tr!("daily.died")
+        };
+        text.push_str(&tr!("daily.score", score.date, format!("{:>4}", score.kills), outcome));
+        text.push('\n');
+    }
+    msgbox(&text, 40, ui);
+}
@@ -0,0 +1,39 @@
+//! Digging through rock: a tunnel at once with a wand of digging, or a tile
+//! at a time by walking into the rock with a pickaxe in hand. Neither gets
+//! through the edge of the map, or the walls of vaults.
+
+use crate::config::config;
+use crate::game::Game;
+use crate::items::Item;
+use crate::map::Map;
+use crate::ui::Tcod;
+
+/// Whether the tile is rock that can be dug through.
+pub fn is_diggable(x: i32, y: i32, map: &Map) -> bool {
+    let (width, height) = (config().map.width, config().map.height);
+    if x <= 0 || y <= 0 || x >= width - 1 || y >= height - 1 {
+        return false;
+    }
+    let tile = &map[x as usize][y as usize];
+    tile.blocked && !tile.door && !tile.undiggable
+}
+
+/// Turn the tile into floor, if it can be dug. Returns whether it was.
+pub fn dig(x: i32, y: i32, tcod: &mut Tcod, map: &mut Map) -> bool {
+    if !is_diggable(x, y, map) {
+        return false;
+    }
+    let tile = &mut map[x as usize][y as usize];
+    tile.blocked = false;
+    tile.block_sight = false;
+    tcod.fov.set(x, y, true, true);
+    tcod.fov_stale = true;
+    true
+}
+
+/// Whether the player has a pickaxe in hand.
+pub fn wields_pickaxe(game: &Game) -> bool {
+    game.inventory.iter().any(|item| {
+        item.item == Some(Item::Pickaxe) && item.equipment.map_or(false, |e| e.equipped)
+    })
+}
@@ -0,0 +1,327 @@
+//! The prefab editor, and the prefabs it saves to `prefabs/`.
+
+use std::cmp;
+use std::error::Error;
+use std::fs;
+
+use crate::backend::Layer;
+use crate::config::config;
+use crate::items::{Item, create_item};
+use crate::map::{Map, Tile, on_map};
+use crate::object::{Object, PLAYER, create_monster};
+use crate::render::{
+    COLOR_LIGHT_GROUND, COLOR_LIGHT_WALL, PANEL_HEIGHT, camera_position, mouse_tile, viewport_size,
+};
+use crate::tcod_types::colors::*;
+use crate::tcod_types::console::{BackgroundFlag, TextAlignment};
+use crate::tcod_types::input::{self, Event, Key};
+use crate::ui::{Tcod, menu, msgbox, text_prompt};
+
+pub const PREFAB_DIR: &str = "prefabs";
+
+/// A handmade piece of map, stored as a text file with one character per tile.
+///
+/// `#` (or a space) is wall, `.` is floor and `+` a closed door. Any other character in
+/// `PREFAB_BRUSHES` is floor with something on it.
+#[derive(Clone, Debug)]
+pub struct Prefab {
+    pub rows: Vec<Vec<char>>,
+}
+
+/// Everything that can be painted in the editor, with the locale key of its name.
+pub const PREFAB_BRUSHES: [(char, &str); 10] = [
+    ('#', "editor.wall"),
+    ('.', "editor.floor"),
+    ('+', "editor.door"),
+    ('@', "editor.start"),
+    ('>', "name.stairs"),
+    ('o', "name.orc"),
+    ('T', "name.troll"),
+    ('!', "name.healing potion"),
+    ('L', "name.scroll of lightning bolt"),
+    ('C', "name.scroll of confusion"),
+];
+
+impl Prefab {
+    /// An empty room filling the whole map.
+    pub fn blank() -> Self {
+        let rows = (0..config().map.height)
+            .map(|y| {
+                (0..config().map.width)
+                    .map(|x| if within_border(x, y) { '.' } else { '#' })
+                    .collect()
+            })
+            .collect();
+        Prefab { rows: rows }
+    }
+
+    pub fn load(name: &str) -> Result<Self, Box<dyn Error>> {
+        Prefab::load_from(PREFAB_DIR, name)
+    }
+
+    /// Load the template with the given name from some other directory.
+    pub fn load_from(dir: &str, name: &str) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(template_path(dir, name))?;
+        let rows = text
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+        Ok(Prefab { rows: rows })
+    }
+
+    pub fn save(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(PREFAB_DIR)?;
+        let mut text = String::new();
+        for row in &self.rows {
+            text.extend(row.iter());
+            text.push('\n');
+        }
+        fs::write(template_path(PREFAB_DIR, name), text)?;
+        Ok(())
+    }
+
+    /// Every tile of the prefab inside the map's border, with its position.
+    fn inner_tiles(&self) -> impl Iterator<Item = (i32, i32, char)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(x, &glyph)| (x as i32, y as i32, glyph))
+                .filter(|&(x, y, _)| within_border(x, y))
+        })
+    }
+
+    /// Whether the prefab says where the player starts. An `@` on the
+    /// border does not count, since the border is always wall.
+    pub fn has_player(&self) -> bool {
+        self.inner_tiles().any(|(_, _, glyph)| glyph == '@')
+    }
+
+    /// Whether there is anywhere in the prefab to stand at all.
+    pub fn has_floor(&self) -> bool {
+        self.inner_tiles()
+            .any(|(_, _, glyph)| glyph != '#' && glyph != ' ' && glyph != '+')
+    }
+
+    pub fn width(&self) -> i32 {
+        self.rows.iter().map(|row| row.len()).max().unwrap_or(0) as i32
+    }
+
+    pub fn height(&self) -> i32 {
+        self.rows.len() as i32
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> char {
+        self.rows
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .cloned()
+            .unwrap_or(' ')
+    }
+
+    /// Paint a tile. The border stays wall, as on every other map.
+    pub fn set(&mut self, x: i32, y: i32, glyph: char) {
+        if !within_border(x, y) {
+            return;
+        }
+        while self.rows.len() <= y as usize {
+            self.rows.push(vec![]);
+        }
+        let row = &mut self.rows[y as usize];
+        while row.len() <= x as usize {
+            row.push(' ');
+        }
+        row[x as usize] = glyph;
+    }
+
+    /// Carve the prefab into the map with its top-left corner at the given
+    /// position, and create the things standing on it. Whatever the file
+    /// says, the map's border is left as wall with nothing on it.
+    pub fn apply(&self, x0: i32, y0: i32, map: &mut Map, objects: &mut Vec<Object>) {
+        for (dy, row) in self.rows.iter().enumerate() {
+            for (dx, &glyph) in row.iter().enumerate() {
+                let (x, y) = (x0 + dx as i32, y0 + dy as i32);
+                if !on_map(x, y) {
+                    continue;
+                }
+                if !within_border(x, y) {
+                    map[x as usize][y as usize] = Tile::wall();
+                    continue;
+                }
+                map[x as usize][y as usize] = match glyph {
+                    '#' | ' ' => Tile::wall(),
+                    '+' => Tile::door(),
+                    _ => Tile::empty(),
+                };
+                if glyph == '@' {
+                    objects[PLAYER].set_pos(x, y);
+                } else if let Some(object) = prefab_object(glyph, x, y) {
+                    objects.push(object);
+                }
+            }
+        }
+    }
+}
+
+/// Whether a tile is inside the wall that runs around the edge of every map.
+fn within_border(x: i32, y: i32) -> bool {
+    x > 0 && y > 0 && x < config().map.width - 1 && y < config().map.height - 1
+}
+
+/// The object a prefab character stands for, if any.
+pub fn prefab_object(glyph: char, x: i32, y: i32) -> Option<Object> {
+    match glyph {
+        '>' => Some(Object::new(x, y, '>', "stairs", WHITE, false)),
+        'o' => Some(create_monster("orc", x, y)),
+        'T' => Some(create_monster("troll", x, y)),
+        '!' => Some(create_item(Item::Heal, x, y)),
+        'L' => Some(create_item(Item::Lightning, x, y)),
+        'C' => Some(create_item(Item::Confuse, x, y)),
+        _ => None,
+    }
+}
+
+/// The file a template of the given name is kept in. The name is typed in
+/// by the player, so anything that could lead out of the directory goes.
+fn template_path(dir: &str, name: &str) -> String {
+    let safe_name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}/{}.txt", dir, safe_name)
+}
+
+/// Names of the prefabs in the prefab directory.
+pub fn available_prefabs() -> Vec<String> {
+    template_names(PREFAB_DIR)
+}
+
+/// Names of the text templates in a directory, in order.
+pub fn template_names(dir: &str) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().map_or(false, |e| e == "txt"))
+                .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+pub fn choose_prefab(tcod: &mut Tcod) -> Option<String> {
+    let names = available_prefabs();
+    if names.is_empty() {
+        msgbox(&format!("\n{}\n", tr!("editor.no-prefabs")), 40, &mut *tcod.ui);
+        return None;
+    }
+    menu(&tr!("editor.choose"), &names, 40, &mut *tcod.ui).map(|index| names[index].clone())
+}
+
+/// Paint a map on a blank canvas, and save it as a prefab.
+pub fn run_editor(tcod: &mut Tcod) {
+    use crate::tcod_types::input::KeyCode::*;
+
+    let mut prefab = Prefab::blank();
+    let mut brush = '#';
+    let (mut x, mut y) = (config().map.width / 2, config().map.height / 2);
+
+    while !tcod.ui.window_closed() {
+        // Draw the prefab as it would look in the game, all lit up.
+        tcod.ui.clear(Layer::Map);
+        for cy in 0..config().map.height {
+            for cx in 0..config().map.width {
+                let glyph = prefab.get(cx, cy);
+                let color = match glyph {
+                    '#' | ' ' => COLOR_LIGHT_WALL,
+                    _ => COLOR_LIGHT_GROUND,
+                };
+                tcod.ui.set_char_background(Layer::Map, cx, cy, color, BackgroundFlag::Set);
+                if glyph == '@' {
+                    tcod.ui.set_default_foreground(Layer::Map, WHITE);
+                    tcod.ui.put_char(Layer::Map, cx, cy, '@', BackgroundFlag::None);
+                } else if let Some(object) = prefab_object(glyph, cx, cy) {
+                    object.draw(&mut *tcod.ui);
+                }
+            }
+        }
+        tcod.ui.set_char_background(Layer::Map, x, y, LIGHT_RED, BackgroundFlag::Set);
+        tcod.camera = camera_position(&*tcod.ui, (x, y));
+        let viewport = viewport_size(&*tcod.ui);
+        tcod.ui.blit(Layer::Map, tcod.camera, viewport, (0, 0), 1.0, 1.0);
+
+        tcod.ui.set_default_background(Layer::Panel, BLACK);
+        tcod.ui.clear(Layer::Panel);
+        tcod.ui.set_default_foreground(Layer::Panel, WHITE);
+        let name = PREFAB_BRUSHES
+            .iter()
+            .find(|&&(glyph, _)| glyph == brush)
+            .map_or_else(String::new, |&(_, key)| tr!(key));
+        let brush_line = tr!("editor.brush", brush, name);
+        let (none, left) = (BackgroundFlag::None, TextAlignment::Left);
+        tcod.ui.print_ex(Layer::Panel, 1, 1, none, left, &brush_line);
+        let legend: Vec<String> = PREFAB_BRUSHES
+            .iter()
+            .map(|&(glyph, key)| format!("{} {}", glyph, tr!(key)))
+            .collect();
+        let width = tcod.ui.width();
+        tcod.ui.print_rect(Layer::Panel, 1, 2, width - 2, 3, &legend.join(", "));
+        let help = tr!("editor.help");
+        tcod.ui.print_ex(Layer::Panel, 1, PANEL_HEIGHT - 1, none, left, &help);
+        let panel_y = tcod.ui.height() - PANEL_HEIGHT;
+        tcod.ui.blit(Layer::Panel, (0, 0), (width, PANEL_HEIGHT), (0, panel_y), 1.0, 1.0);
+        tcod.ui.flush();
+
+        let (key, mouse) = match tcod.ui.wait_for_event(input::MOUSE | input::KEY_PRESS) {
+            Some(Event::Key(k)) => (k, None),
+            Some(Event::Mouse(m)) => (Default::default(), Some(m)),
+            None => continue,
+        };
+
+        // Follow the mouse, and paint while its button is held down.
+        if let Some(mouse) = mouse {
+            if let Some((mx, my)) = mouse_tile(mouse, tcod) {
+                x = mx;
+                y = my;
+                if mouse.lbutton {
+                    prefab.set(x, y, brush);
+                }
+            }
+            continue;
+        }
+
+        match (key, key.printable) {
+            (Key { code: Escape, .. }, _) => break,
+            (Key { code: Up, .. }, _) => y = cmp::max(y - 1, 0),
+            (Key { code: Down, .. }, _) => y = cmp::min(y + 1, config().map.height - 1),
+            (Key { code: Left, .. }, _) => x = cmp::max(x - 1, 0),
+            (Key { code: Right, .. }, _) => x = cmp::min(x + 1, config().map.width - 1),
+            (Key { code: Spacebar, .. }, _) | (Key { code: Enter, .. }, _) => prefab.set(x, y, brush),
+            (Key { code: Char, .. }, 'S') => {
+                if let Some(name) = text_prompt(&tr!("editor.save-as"), tcod) {
+                    let message = match prefab.save(&name) {
+                        Ok(()) => tr!("editor.saved", name),
+                        Err(e) => tr!("editor.save-failed", e),
+                    };
+                    msgbox(&format!("\n{}\n", message), 40, &mut *tcod.ui);
+                }
+            }
+            (Key { code: Char, .. }, 'O') => {
+                if let Some(name) = choose_prefab(tcod) {
+                    match Prefab::load(&name) {
+                        Ok(loaded) => prefab = loaded,
+                        Err(e) => msgbox(&format!("\n{}\n", tr!("editor.load-failed", e)), 40, &mut *tcod.ui),
+                    }
+                }
+            }
+            (Key { code: Char, .. }, typed)
+                if PREFAB_BRUSHES.iter().any(|&(glyph, _)| glyph == typed) =>
+            {
+                brush = typed;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Equipment: weapons and armour the player puts on for their bonuses. Some
+//! of it is cursed, which only shows once it is on, and then it will not
+//! come off again short of a scroll of remove curse.
+
+use rand::{Rng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::items::UseResult;
+use crate::locale::display_name;
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+use crate::ui::Tcod;
+
+pub const CURSE_CHANCE: f32 = 0.25; // Chance of equipment found in the dungeon being cursed
+
+/// Where on the body a piece of equipment goes; one piece per slot.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Slot {
+    Hand,
+    OffHand,
+    /// Where the torch or lantern goes.
+    Light,
+}
+
+impl Slot {
+    pub fn name(self) -> String {
+        match self {
+            Slot::Hand => tr!("slot.hand"),
+            Slot::OffHand => tr!("slot.off-hand"),
+            Slot::Light => tr!("slot.light"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Equipment {
+    pub slot: Slot,
+    pub equipped: bool,
+    pub power_bonus: i32,
+    pub defense_bonus: i32,
+    pub cursed: bool,
+    /// Whether the player knows if it is cursed, which they find out by
+    /// putting it on.
+    pub curse_known: bool,
+}
+
+impl Equipment {
+    pub fn new(slot: Slot, power_bonus: i32, defense_bonus: i32) -> Self {
+        Equipment {
+            slot: slot,
+            equipped: false,
+            power_bonus: power_bonus,
+            defense_bonus: defense_bonus,
+            cursed: false,
+            curse_known: false,
+        }
+    }
+}
+
+/// Curse a piece of equipment found in the dungeon, now and then. Half of
+/// the cursed pieces hinder whoever wears them instead of helping. Lights
+/// are never cursed.
+pub fn maybe_curse(object: &mut Object, rng: &mut StdRng) {
+    if let Some(equipment) = object.equipment.as_mut().filter(|e| e.slot != Slot::Light) {
+        if rng.next_f32() < CURSE_CHANCE {
+            equipment.cursed = true;
+            if rng.gen() {
+                equipment.power_bonus = -equipment.power_bonus;
+                equipment.defense_bonus = -equipment.defense_bonus;
+            }
+        }
+    }
+}
+
+/// Put the item in the given inventory slot on, or take it off if it is
+/// already on.
+pub fn toggle_equipment(
+    inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    let equipment = match game.inventory[inventory_id].equipment {
+        Some(equipment) => equipment,
+        None => return UseResult::Cancelled,
+    };
+    if equipment.equipped {
+        return if take_off(inventory_id, game, objects) {
+            UseResult::UsedAndKept
+        } else {
+            UseResult::Cancelled
+        };
+    }
+    // Whatever is already in the slot comes off first.
+    let worn = game.inventory.iter().position(|item| {
+        item.equipment
+            .map_or(false, |other| other.equipped && other.slot == equipment.slot)
+    });
+    if let Some(worn) = worn {
+        if !take_off(worn, game, objects) {
+            return UseResult::Cancelled;
+        }
+    }
+    put_on(inventory_id, game, objects);
+    UseResult::UsedAndKept
+}
+
+fn put_on(inventory_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let name = display_name(&game.inventory[inventory_id].name);
+    let equipment = match game.inventory[inventory_id].equipment.as_mut() {
+        Some(equipment) => equipment,
+        None => return,
+    };
+    equipment.equipped = true;
+    equipment.curse_known = true;
+    let equipment = *equipment;
+    if let Some(fighter) = objects[PLAYER].fighter.as_mut() {
+        fighter.power += equipment.power_bonus;
+        fighter.defense += equipment.defense_bonus;
+    }
+    let message = match equipment.slot {
+        Slot::Light => tr!("light.lit", name),
+        slot => tr!("equipment.put-on", name, slot.name()),
+    };
+    game.messages.add(message, MessageCategory::Item);
+    if equipment.cursed {
+        game.messages.add(tr!("equipment.cursed", name), MessageCategory::Warning);
+    }
+}
+
+/// Take off the item in the given inventory slot, if it is on. Returns
+/// false if it is cursed, and stays on.
+pub fn take_off(inventory_id: usize, game: &mut Game, objects: &mut [Object]) -> bool {
+    let name = display_name(&game.inventory[inventory_id].name);
+    let equipment = match game.inventory[inventory_id].equipment.as_mut() {
+        Some(equipment) if equipment.equipped => equipment,
+        _ => return true,
+    };
+    if equipment.cursed {
+        game.messages.add(tr!("equipment.stuck", name), MessageCategory::Warning);
+        return false;
+    }
+    equipment.equipped = false;
+    let equipment = *equipment;
+    if let Some(fighter) = objects[PLAYER].fighter.as_mut() {
+        fighter.power -= equipment.power_bonus;
+        fighter.defense -= equipment.defense_bonus;
+    }
+    let message = match equipment.slot {
+        Slot::Light => tr!("light.doused", name),
+        slot => tr!("equipment.took-off", name, slot.name()),
+    };
+    game.messages.add(message, MessageCategory::Item);
+    true
+}
+
+pub fn cast_remove_curse(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    _objects: &mut [Object],
+) -> UseResult
+{
+    // Lift the curse from everything in the pack.
+    let mut lifted = false;
+    for equipment in game.inventory.iter_mut().filter_map(|item| item.equipment.as_mut()) {
+        if equipment.cursed {
+            equipment.cursed = false;
+            equipment.curse_known = true;
+            lifted = true;
+        }
+    }
+    if lifted {
+        game.messages.add(tr!("remove-curse.lifted"), MessageCategory::Item);
+    } else {
+        game.messages.add(tr!("remove-curse.nothing"), MessageCategory::Item);
+    }
+    UseResult::UsedUp
+}
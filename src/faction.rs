@@ -0,0 +1,132 @@
+//! Factions, and the player's reputation with each.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::Ai;
+use crate::game::Game;
+use crate::messages::MessageCategory;
+use crate::object::Object;
+
+// Reputation with factions
+pub const HOSTILE_REPUTATION: i32 = -10; // Below this, a faction's members attack on sight
+pub const FRIENDLY_REPUTATION: i32 = 20; // From this on, a faction's traders will deal with you
+pub const KILL_REPUTATION: i32 = 5;
+pub const RIVAL_KILL_REPUTATION: i32 = 2;
+pub const QUEST_REPUTATION: i32 = 10;
+pub const THEFT_REPUTATION: i32 = 30;
+
+pub const INFIGHTING_SIGHT: f32 = 8.0; // How far off a monster spots an enemy that isn't the player
+
+/// A group of creatures whose members share the same attitude to the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Faction {
+    Orcs,
+    Trolls,
+    /// The few living souls who dwell in the tombs: sages, pilgrims, traders.
+    Tombfolk,
+}
+
+impl Faction {
+    pub const ALL: [Faction; 3] = [Faction::Orcs, Faction::Trolls, Faction::Tombfolk];
+
+    pub fn name(self) -> String {
+        match self {
+            Faction::Orcs => tr!("faction.orcs"),
+            Faction::Trolls => tr!("faction.trolls"),
+            Faction::Tombfolk => tr!("faction.tombfolk"),
+        }
+    }
+
+    pub fn initial_reputation(self) -> i32 {
+        match self {
+            Faction::Orcs | Faction::Trolls => -20,
+            Faction::Tombfolk => 0,
+        }
+    }
+
+    /// The faction that is glad to see this one's members die.
+    pub fn rival(self) -> Option<Faction> {
+        match self {
+            Faction::Orcs => Some(Faction::Trolls),
+            Faction::Trolls => Some(Faction::Orcs),
+            Faction::Tombfolk => None,
+        }
+    }
+
+    /// Name and glyph of the trader who sells to friends of this faction.
+    pub fn vendor(self) -> (&'static str, char) {
+        match self {
+            Faction::Orcs => ("orc trader", 'o'),
+            Faction::Trolls => ("troll trader", 'T'),
+            Faction::Tombfolk => ("merchant", '@'),
+        }
+    }
+}
+
+/// Whether the two monsters fight each other on sight. Members of rival
+/// factions do, whatever terms either is on with the player.
+pub fn are_enemies(monster: &Object, other: &Object) -> bool {
+    match (monster.faction, other.faction) {
+        (Some(faction), Some(other)) => faction.rival() == Some(other),
+        _ => false,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Standing {
+    Hostile,
+    Neutral,
+    Friendly,
+}
+
+impl Standing {
+    pub fn name(self) -> String {
+        match self {
+            Standing::Hostile => tr!("standing.hostile"),
+            Standing::Neutral => tr!("standing.neutral"),
+            Standing::Friendly => tr!("standing.friendly"),
+        }
+    }
+}
+
+pub fn standing(game: &Game, faction: Faction) -> Standing {
+    let reputation = game.reputation.get(&faction).cloned().unwrap_or(0);
+    if reputation < HOSTILE_REPUTATION {
+        Standing::Hostile
+    } else if reputation >= FRIENDLY_REPUTATION {
+        Standing::Friendly
+    } else {
+        Standing::Neutral
+    }
+}
+
+pub fn adjust_reputation(game: &mut Game, faction: Faction, amount: i32) {
+    let before = standing(game, faction);
+    *game.reputation.entry(faction).or_insert(0) += amount;
+    let after = standing(game, faction);
+    if before != after {
+        game.messages.add(
+            tr!("reputation.changed", faction.name(), after.name()),
+            MessageCategory::Lore,
+        );
+    }
+}
+
+/// Calm the members of factions the player isn't hostile with.
+pub fn apply_reputation(game: &Game, objects: &mut [Object]) {
+    for object in objects.iter_mut() {
+        let faction = match object.faction {
+            Some(faction) => faction,
+            None => continue,
+        };
+        // A sleeper wakes to whatever terms its faction is on.
+        let ai = match object.ai {
+            Some(Ai::Sleeping { ref mut previous_ai }) => &mut **previous_ai,
+            Some(ref mut ai) => ai,
+            None => continue,
+        };
+        if *ai == Ai::Basic && standing(game, faction) != Standing::Hostile {
+            *ai = Ai::Idle;
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! The field of view, computed either by libtcod or by the game's own
+//! shadowcasting, as set in `config.toml`. Built without the `tcod` feature,
+//! it is always the game's own.
+
+#[cfg(feature = "tcod")]
+use tcod::map::{FovAlgorithm, Map as FovMap};
+
+use crate::backend::Layer;
+use crate::config::{FovSetting, config};
+use crate::lighting::Lighting;
+use crate::map::Map;
+use crate::throw::trajectory;
+use crate::ui::Tcod;
+
+/// What can be seen from where the player stands.
+pub struct Fov {
+    pub width: i32,
+    pub height: i32,
+    pub transparent: Vec<bool>,
+    pub visible: Vec<bool>,
+    /// Used for every algorithm but symmetric shadowcasting, so only built
+    /// once one of them is chosen.
+    #[cfg(feature = "tcod")]
+    pub tcod: Option<FovMap>,
+}
+
+/// A row of tiles scanned by symmetric shadowcasting, `depth` tiles away from
+/// the origin and between two slopes. Slopes are kept as exact fractions.
+pub struct ShadowRow {
+    pub depth: i32,
+    pub start: (i32, i32),
+    pub end: (i32, i32),
+}
+
+impl Fov {
+    pub fn new(width: i32, height: i32) -> Self {
+        let size = (width * height) as usize;
+        Fov {
+            width: width,
+            height: height,
+            transparent: vec![false; size],
+            visible: vec![false; size],
+            #[cfg(feature = "tcod")]
+            tcod: None,
+        }
+    }
+
+    pub fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= 0 && y >= 0 && x < self.width && y < self.height {
+            Some((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    #[cfg_attr(not(feature = "tcod"), allow(unused_variables))]
+    pub fn set(&mut self, x: i32, y: i32, transparent: bool, walkable: bool) {
+        if let Some(index) = self.index(x, y) {
+            self.transparent[index] = transparent;
+            self.visible[index] = false;
+        }
+        #[cfg(feature = "tcod")]
+        if let Some(ref mut tcod) = self.tcod {
+            tcod.set(x, y, transparent, walkable);
+        }
+    }
+
+    pub fn is_in_fov(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).map_or(false, |index| self.visible[index])
+    }
+
+    /// Work out what can be seen from `(x, y)` up to `radius` tiles away, or
+    /// without limit if `radius` is 0. Only libtcod has the algorithms other
+    /// than symmetric shadowcasting.
+    #[cfg_attr(not(feature = "tcod"), allow(unused_variables))]
+    pub fn compute_fov(&mut self, x: i32, y: i32, radius: i32, light_walls: bool, algorithm: FovSetting) {
+        #[cfg(feature = "tcod")]
+        {
+            if let Some(algorithm) = algorithm.to_tcod() {
+                self.compute_tcod_fov(x, y, radius, light_walls, algorithm);
+                return;
+            }
+        }
+        self.shadowcast(x, y, radius, light_walls);
+    }
+
+    /// `compute_fov` with one of libtcod's algorithms, on its own map of
+    /// what can be seen through, built the first time it is needed.
+    #[cfg(feature = "tcod")]
+    fn compute_tcod_fov(&mut self, x: i32, y: i32, radius: i32, light_walls: bool, algorithm: FovAlgorithm) {
+        let (width, height) = (self.width, self.height);
+        let transparent = &self.transparent;
+        let tcod = self.tcod.get_or_insert_with(|| {
+            // Only what can be seen through matters to the FOV.
+            let mut tcod = FovMap::new(width, height);
+            for ty in 0..height {
+                for tx in 0..width {
+                    let see_through = transparent[(ty * width + tx) as usize];
+                    tcod.set(tx, ty, see_through, see_through);
+                }
+            }
+            tcod
+        });
+        tcod.compute_fov(x, y, radius, light_walls, algorithm);
+        for ty in 0..height {
+            for tx in 0..width {
+                let index = (ty * width + tx) as usize;
+                self.visible[index] = tcod.is_in_fov(tx, ty);
+            }
+        }
+    }
+
+    /// Symmetric shadowcasting, after Albert Ford's description: a floor
+    /// tile is only seen if the origin would be seen from it in turn.
+    pub fn shadowcast(&mut self, ox: i32, oy: i32, radius: i32, light_walls: bool) {
+        for visible in self.visible.iter_mut() {
+            *visible = false;
+        }
+        if let Some(index) = self.index(ox, oy) {
+            self.visible[index] = true;
+        }
+        let in_radius = |depth: i32, col: i32| radius <= 0 || depth * depth + col * col <= radius * radius;
+
+        for quadrant in 0..4 {
+            // Turn (depth, column) in this quadrant into map coordinates.
+            let tile = |depth: i32, col: i32| match quadrant {
+                0 => (ox + col, oy - depth),
+                1 => (ox + col, oy + depth),
+                2 => (ox + depth, oy + col),
+                _ => (ox - depth, oy + col),
+            };
+            let mut rows = vec![ShadowRow {
+                depth: 1,
+                start: (-1, 1),
+                end: (1, 1),
+            }];
+            while let Some(mut row) = rows.pop() {
+                if radius > 0 && row.depth > radius {
+                    continue;
+                }
+                let depth = row.depth;
+                // The columns the row's slopes cover, rounding ties towards the middle.
+                let (num, den) = row.start;
+                let min_col = (2 * depth * num + den).div_euclid(2 * den);
+                let (num, den) = row.end;
+                let max_col = -(den - 2 * depth * num).div_euclid(2 * den);
+
+                let mut previous_wall = None;
+                for col in min_col..=max_col {
+                    let (x, y) = tile(depth, col);
+                    let index = self.index(x, y);
+                    let wall = index.map_or(true, |index| !self.transparent[index]);
+                    let symmetric = col * row.start.1 >= depth * row.start.0
+                        && col * row.end.1 <= depth * row.end.0;
+                    if let Some(index) = index {
+                        if in_radius(depth, col) && ((wall && light_walls) || (!wall && symmetric)) {
+                            self.visible[index] = true;
+                        }
+                    }
+                    let slope = (2 * col - 1, 2 * depth);
+                    if previous_wall == Some(true) && !wall {
+                        row.start = slope;
+                    }
+                    if previous_wall == Some(false) && wall {
+                        rows.push(ShadowRow {
+                            depth: depth + 1,
+                            start: row.start,
+                            end: slope,
+                        });
+                    }
+                    previous_wall = Some(wall);
+                }
+                if previous_wall == Some(false) {
+                    rows.push(ShadowRow {
+                        depth: depth + 1,
+                        start: row.start,
+                        end: row.end,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Compute what can be seen from the given position, the way the player
+/// chose: everything within the radius, and, however far off, whatever is
+/// in sight and lit by something else, if the lighting is given. A blinded
+/// player gets none.
+pub fn compute_fov(
+    tcod: &mut Tcod,
+    (x, y): (i32, i32),
+    radius: i32,
+    lighting: Option<&Lighting>,
+) {
+    let algorithm = tcod.settings.fov_algorithm();
+    let light_walls = tcod.settings.light_walls();
+    tcod.fov.compute_fov(x, y, 0, light_walls, algorithm);
+    let in_sight = tcod.fov.visible.clone();
+    tcod.fov.compute_fov(x, y, radius, light_walls, algorithm);
+    let lighting = match lighting {
+        Some(lighting) => lighting,
+        None => return,
+    };
+    let fov = &mut tcod.fov;
+    for ty in 0..fov.height {
+        for tx in 0..fov.width {
+            let index = (ty * fov.width + tx) as usize;
+            if in_sight[index] && lighting.is_lit(tx, ty) {
+                fov.visible[index] = true;
+            }
+        }
+    }
+}
+
+/// Whether nothing stands in the way of seeing from one tile to another.
+pub fn in_plain_sight(map: &Map, from: (i32, i32), to: (i32, i32)) -> bool {
+    trajectory(from, to)
+        .into_iter()
+        .take_while(|&tile| tile != to)
+        .all(|(x, y)| !map[x as usize][y as usize].block_sight)
+}
+
+/// Let the FOV map know about doors opened or shut since it was last told.
+pub fn update_doors(tcod: &mut Tcod, map: &Map) {
+    for y in 0..config().map.height {
+        for x in 0..config().map.width {
+            let tile = map[x as usize][y as usize];
+            let index = tcod.fov.index(x, y);
+            let fov = &tcod.fov;
+            let changed = index.map_or(false, |index| fov.transparent[index] == tile.block_sight);
+            if tile.door && changed {
+                tcod.fov.set(x, y, !tile.block_sight, !tile.blocked);
+                tcod.fov_stale = true;
+            }
+        }
+    }
+}
+
+pub fn initialise_fov(tcod: &mut Tcod, map: &Map) {
+    // Populate the FOV map, according to the generated map
+    for y in 0..config().map.height {
+        for x in 0..config().map.width {
+            tcod.fov.set(
+                x,
+                y,
+                !map[x as usize][y as usize].block_sight,
+                !map[x as usize][y as usize].blocked,
+            );
+        }
+    }
+
+    // Unexplored areas start black (which is the default background color).
+    tcod.ui.clear(Layer::Map);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A room with pillars and a wall with a gap, drawn as rows of text: `#`
+    /// blocks sight and anything else is floor.
+    const ROOM: [&str; 9] = [
+        "###############",
+        "#.............#",
+        "#..#.....#....#",
+        "#.......#.....#",
+        "#####.#####.###",
+        "#.....#.......#",
+        "#..#......#...#",
+        "#.............#",
+        "###############",
+    ];
+
+    fn room_fov() -> Fov {
+        let mut fov = Fov::new(ROOM[0].len() as i32, ROOM.len() as i32);
+        for (y, row) in ROOM.iter().enumerate() {
+            for (x, glyph) in row.bytes().enumerate() {
+                let floor = glyph != b'#';
+                fov.set(x as i32, y as i32, floor, floor);
+            }
+        }
+        fov
+    }
+
+    fn floor_tiles() -> Vec<(i32, i32)> {
+        let mut tiles = vec![];
+        for (y, row) in ROOM.iter().enumerate() {
+            for (x, glyph) in row.bytes().enumerate() {
+                if glyph != b'#' {
+                    tiles.push((x as i32, y as i32));
+                }
+            }
+        }
+        tiles
+    }
+
+    fn seen_from(fov: &mut Fov, (x, y): (i32, i32)) -> Vec<(i32, i32)> {
+        fov.shadowcast(x, y, 0, true);
+        floor_tiles().into_iter().filter(|&(x, y)| fov.is_in_fov(x, y)).collect()
+    }
+
+    #[test]
+    fn shadowcasting_is_symmetric() {
+        let mut fov = room_fov();
+        for from in floor_tiles() {
+            for to in seen_from(&mut fov, from) {
+                assert!(
+                    seen_from(&mut fov, to).contains(&from),
+                    "{:?} sees {:?} but not the other way round",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shadowcasting_sees_the_origin_and_its_neighbours() {
+        let mut fov = room_fov();
+        fov.shadowcast(2, 1, 0, true);
+        for &(x, y) in &[(2, 1), (1, 1), (3, 1), (1, 2), (2, 2), (3, 2)] {
+            assert!(fov.is_in_fov(x, y));
+        }
+        // The wall tiles around it are lit too.
+        assert!(fov.is_in_fov(2, 0));
+    }
+
+    #[test]
+    fn walls_block_shadowcasting() {
+        let mut fov = room_fov();
+        fov.shadowcast(1, 1, 0, true);
+        // Behind the solid wall across the middle, away from its gaps.
+        assert!(!fov.is_in_fov(1, 6));
+        assert!(!fov.is_in_fov(3, 7));
+    }
+
+    #[test]
+    fn shadowcasting_keeps_to_the_radius() {
+        let mut fov = room_fov();
+        fov.shadowcast(1, 1, 3, true);
+        assert!(fov.is_in_fov(4, 1));
+        assert!(!fov.is_in_fov(5, 1));
+    }
+}
@@ -0,0 +1,698 @@
+//! The game itself: its state, the events it raises, and the main loop.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::action::{PlayerAction, continue_queue};
+use crate::ai::{Ai, hostiles_in_view, monsters_act, monsters_take_turns};
+use crate::arena::{ARENA_KILL_GOLD, make_arena_map, record_arena_score, update_arena};
+use crate::audio::{MusicTheme, Sound, play_sounds};
+use crate::backend::Layer;
+use crate::bestiary::Bestiary;
+use crate::bones::haunt_level;
+use crate::boss::{BOSS_NAME, place_boss, show_victory};
+use crate::branch::{BRANCH_STAIRS, Branch, place_branch_stairs};
+use crate::config::config;
+use crate::daily::{daily_seed, record_daily_score};
+use crate::editor::Prefab;
+use crate::faction::{
+    Faction, KILL_REPUTATION, RIVAL_KILL_REPUTATION, THEFT_REPUTATION, adjust_reputation,
+    apply_reputation,
+};
+use crate::fov::{initialise_fov, update_doors};
+use crate::gold::{collect_gold, drop_gold};
+use crate::hunger::full_stomach;
+use crate::identify::Identification;
+use crate::input::{handle_keys, poll_input};
+use crate::items::{Item, add_to_inventory, create_item};
+use crate::light::tick_light;
+use crate::locale::display_name;
+use crate::map::{Map, Tile, make_map, random_free_tile};
+use crate::messages::{MessageCategory, Messages};
+use crate::morgue::write_morgue;
+use crate::noise::Noise;
+use crate::object::{Object, PLAYER, level_up};
+use crate::observer::Observation;
+use crate::online::submit_run_summary;
+use crate::profile::{
+    Achievement, DELVER_DEPTH, PlayerClass, Profile, deposit_in_stash, earn_achievement,
+    record_first_kill, withdraw_from_stash,
+};
+use crate::quest::{
+    ARTIFACT_LEVEL, ARTIFACT_NAME, Objective, Quest, UNIQUE_MONSTERS, complete_quest,
+    main_quest_completed, place_artifact, place_quest_giver, spawn_quest_targets,
+};
+use crate::render::{Animation, play_animations, render_all};
+use crate::save::{SaveFile, load_save, report_load_errors, write_save_as};
+use crate::scores::{record_high_score, show_high_scores};
+use crate::settings::Settings;
+use crate::shop::{Shop, place_shop, place_vendors, watch_shop};
+use crate::status::StatusKind;
+use crate::tcod_types::colors::*;
+use crate::tutorial::{TutorialStep, make_tutorial_map, update_tutorial};
+use crate::ui::{Tcod, msgbox};
+
+pub const SAVEGAME_FILE: &str = "savegame";
+
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub map: Map,
+    pub messages: Messages,
+    pub inventory: Vec<Object>,
+    pub mode: GameMode,
+    pub kills: u32,
+    pub dungeon_level: u32,
+    /// Kept in its own file, and read again when a saved game is loaded.
+    #[serde(skip)]
+    pub bestiary: Bestiary,
+    #[serde(skip)]
+    pub events: Vec<GameEvent>,
+    pub quests: Vec<Quest>,
+    pub reputation: BTreeMap<Faction, i32>,
+    /// Factions whose shops the player has robbed.
+    pub thief_of: BTreeSet<Faction>,
+    pub gold: u32,
+    pub shop: Option<Shop>,
+    /// Kept in its own file, and read again when a saved game is loaded.
+    #[serde(skip)]
+    pub profile: Profile,
+    /// Items added to the spawn pool by the profile's unlocks.
+    pub unlocked_items: Vec<Item>,
+    /// Seed the first level was generated from.
+    pub seed: usize,
+    /// Where all of the run's chance comes from, so that a seed replays the
+    /// whole run. A loaded game picks up from its seed and turn.
+    #[serde(skip, default = "unseeded_rng")]
+    pub rng: StdRng,
+    /// The last monster to hit the player.
+    pub last_attacker: Option<String>,
+    /// Sounds made this turn, waiting to be played.
+    #[serde(skip)]
+    pub sounds: Vec<Sound>,
+    /// Noises made since the monsters last acted, waiting to be heard.
+    #[serde(skip)]
+    pub noises: Vec<Noise>,
+    /// Set while one monster strikes another, or the dungeon itself hurts a
+    /// monster, so that whatever it kills is not put down to the player.
+    #[serde(skip)]
+    pub infighting: bool,
+    /// What monsters that died this turn let fall, waiting to be put on the map.
+    #[serde(skip)]
+    pub dropped: Vec<Object>,
+    /// Turns left during which every monster on the level can be sensed.
+    pub telepathy: u32,
+    /// Things glimpsed out of sight, shown where they were until seen again.
+    pub glimpses: Vec<Glimpse>,
+    /// Items and stairs as the player last saw them, for maps of what they know.
+    #[serde(default)]
+    pub remembered: Vec<Glimpse>,
+    /// Visual effects from this turn, waiting to be played.
+    #[serde(skip)]
+    pub animations: Vec<Animation>,
+    /// Turns the player has taken so far.
+    pub turn: u32,
+    /// Notable moments of the run, oldest first.
+    pub journal: Vec<JournalEntry>,
+    /// The branch of the dungeon the player is in; `dungeon_level` is still
+    /// counted from the top.
+    #[serde(default)]
+    pub branch: Branch,
+    /// Turns left before the player starts to starve.
+    #[serde(default = "full_stomach")]
+    pub nutrition: i32,
+    /// What the potions and scrolls of this run look like, and which of
+    /// them the player knows.
+    #[serde(default)]
+    pub identification: Identification,
+    /// How many of each monster the player has killed this run.
+    #[serde(default)]
+    pub kill_counts: BTreeMap<String, u32>,
+    /// Set once the lich falls, which ends the run. A won game is never saved.
+    #[serde(skip)]
+    pub won: bool,
+}
+
+/// A line in the adventure journal, noted down as it happened.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub turn: u32,
+    pub depth: u32,
+    pub text: String,
+}
+
+fn unseeded_rng() -> StdRng {
+    SeedableRng::from_seed(&[0][..])
+}
+
+impl Game {
+    /// Note something down in the journal, dated with the current turn and depth.
+    pub fn write_journal(&mut self, text: String) {
+        self.journal.push(JournalEntry {
+            turn: self.turn,
+            depth: self.dungeon_level,
+            text: text,
+        });
+    }
+}
+
+/// How something out of sight looked when the player last knew of it: a
+/// monster that walked out of view, or whatever clairvoyance showed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Glimpse {
+    pub x: i32,
+    pub y: i32,
+    pub char: char,
+    pub color: Color,
+}
+
+impl Glimpse {
+    pub fn of(object: &Object) -> Self {
+        Glimpse {
+            x: object.x,
+            y: object.y,
+            char: object.char,
+            color: object.color,
+        }
+    }
+}
+
+/// Something notable that happened in the game. Events are queued as they
+/// happen, and handed once per turn to the systems that care about them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A monster died, at the player's hands unless `by_player` says otherwise.
+    MonsterKilled {
+        name: String,
+        faction: Option<Faction>,
+        xp: i32,
+        pos: (i32, i32),
+        by_player: bool,
+    },
+    ItemPickedUp { name: String },
+    LevelEntered { depth: u32 },
+    /// An escorted NPC reached the stairs alongside the player.
+    EscortArrived { name: String },
+    /// The player walked out of a shop without paying.
+    Theft { faction: Faction },
+}
+
+/// The kind of run being played.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    Normal,
+    /// Daily challenge for the given date (`YYYY-MM-DD`).
+    Daily(String),
+    /// Scripted tutorial level, at the given step.
+    Tutorial(TutorialStep),
+    /// Waves of monsters in a single arena, currently on the given wave.
+    Arena(u32),
+    /// A normal run whose first level is the prefab with the given name.
+    Custom(String),
+    /// A normal run played by the bot, or by a script in a headless run,
+    /// which leaves no trace in the profile.
+    Demo,
+}
+
+impl GameMode {
+    /// Whether a run of this kind can be saved and carried on later.
+    pub fn can_save(&self) -> bool {
+        !matches!(*self, GameMode::Tutorial(_) | GameMode::Demo)
+    }
+
+    /// Whether the run is played casually, saves surviving death, when the
+    /// player asks for that. A daily challenge is one attempt a day, so never.
+    pub fn is_casual(&self, settings: &Settings) -> bool {
+        match *self {
+            GameMode::Daily(_) => false,
+            _ => settings.casual,
+        }
+    }
+}
+
+/// A run put aside to be carried on later: the game and everything on the
+/// current level.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub game: Game,
+    pub objects: Vec<Object>,
+}
+
+impl SaveFile for SavedGame {
+    const PATH: &'static str = SAVEGAME_FILE;
+}
+
+/// Borrows what `SavedGame` owns, so the game can be saved in place.
+#[derive(Serialize)]
+struct SavingGame<'a> {
+    game: &'a Game,
+    objects: &'a [Object],
+}
+
+pub fn save_game(game: &Game, objects: &[Object]) -> Result<(), Box<dyn Error>> {
+    write_save_as::<SavedGame, _>(&SavingGame {
+        game: game,
+        objects: objects,
+    })
+}
+
+/// Throw the saved game away, once the run it holds is over or taken up.
+pub fn delete_save() {
+    // A save that is already gone is just as good.
+    let _ = fs::remove_file(SAVEGAME_FILE);
+}
+
+/// Load the saved game, if there is one that can be read. Unless the run is
+/// played casually, the save is used up in loading it, so that a run cannot
+/// be taken back by quitting without saving.
+pub fn load_game(tcod: &mut Tcod) -> Option<(Game, Vec<Object>)> {
+    let SavedGame { mut game, objects } = load_save()?;
+    if !game.mode.is_casual(&tcod.settings) {
+        delete_save();
+    }
+    game.rng = SeedableRng::from_seed(&[game.seed, game.turn as usize][..]);
+    game.bestiary = Bestiary::load();
+    game.profile = Profile::load();
+    initialise_fov(tcod, &game.map);
+    Some((game, objects))
+}
+
+pub fn new_game(tcod: &mut Tcod, mode: GameMode, class: PlayerClass) -> (Game, Vec<Object>) {
+    // Create object representing the player
+    let mut player = Object::new(0, 0, '@', "player", WHITE, true);
+    player.alive = true;
+    player.fighter = Some(class.fighter());
+
+    // Daily challenges must be the same for everyone, so unlocks don't apply.
+    let profile = Profile::load();
+    let unlocked_items = match mode {
+        GameMode::Normal => profile.unlocked_items(),
+        _ => vec![],
+    };
+
+    // list of objects with those two
+    let mut objects = vec![player];
+
+    let prefab = match mode {
+        GameMode::Custom(ref name) => Prefab::load(name).ok(),
+        _ => None,
+    };
+
+    // Daily challenges share a seed derived from the date; everything else is
+    // random, unless a seed was given on the command line.
+    let seed = match mode {
+        GameMode::Daily(ref date) => daily_seed(date),
+        _ => tcod.seed.unwrap_or_else(|| rand::thread_rng().gen()),
+    };
+    let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+
+    // Generate map (at this point it is not drawn to the screen)
+    let ((map, rooms), dungeon_level) = match mode {
+        GameMode::Tutorial(_) => ((make_tutorial_map(&mut objects), vec![]), 0),
+        GameMode::Arena(_) => ((make_arena_map(&mut objects), vec![]), 0),
+        GameMode::Custom(_) => match prefab {
+            Some(ref prefab) if prefab.has_floor() => {
+                let mut map = vec![vec![Tile::wall(); config().map.height as usize]; config().map.width as usize];
+                prefab.apply(0, 0, &mut map, &mut objects);
+                if !prefab.has_player() {
+                    // Without an @ the player would start in the wall in the corner.
+                    let (x, y) = random_free_tile(&map, &objects, &mut rng);
+                    objects[PLAYER].set_pos(x, y);
+                }
+                ((map, vec![]), 1)
+            }
+            // The file went missing since it was picked, or there is nowhere
+            // in it to stand, so make do with a random level.
+            _ => (make_map(Branch::Main, 1, &mut objects, &unlocked_items, &mut rng), 1),
+        },
+        _ => (make_map(Branch::Main, 1, &mut objects, &unlocked_items, &mut rng), 1),
+    };
+    // The tutorial and the arena sell and teach items by name.
+    let identification = match mode {
+        GameMode::Tutorial(_) | GameMode::Arena(_) => Identification::default(),
+        _ => Identification::shuffled(&mut rng),
+    };
+
+    let mut game = Game {
+        map: map,
+        messages: Messages::new(),
+        inventory: vec![],
+        mode: mode,
+        kills: 0,
+        dungeon_level: dungeon_level,
+        bestiary: Bestiary::load(),
+        events: vec![],
+        // Every run has the same goal.
+        quests: vec![Quest::new(
+            Objective::Retrieve {
+                item: ARTIFACT_NAME.into(),
+                depth: ARTIFACT_LEVEL,
+            },
+            None,
+            None,
+        )],
+        reputation: Faction::ALL
+            .iter()
+            .map(|&faction| (faction, faction.initial_reputation()))
+            .collect(),
+        thief_of: BTreeSet::new(),
+        gold: config().gameplay.starting_gold,
+        shop: None,
+        profile: profile,
+        unlocked_items: unlocked_items,
+        seed: seed,
+        rng: rng,
+        last_attacker: None,
+        sounds: vec![],
+        noises: vec![],
+        infighting: false,
+        dropped: vec![],
+        telepathy: 0,
+        glimpses: vec![],
+        remembered: vec![],
+        animations: vec![],
+        turn: 0,
+        journal: vec![],
+        branch: Branch::Main,
+        identification: identification,
+        nutrition: full_stomach(),
+        kill_counts: BTreeMap::new(),
+        won: false,
+    };
+
+    place_shop(&rooms, &mut game, &mut objects);
+    initialise_fov(tcod, &game.map);
+
+    // Print a welcome message. 
+    game.messages.add(
+        tr!("welcome", seed),
+        MessageCategory::Lore,
+    );
+
+    haunt_level(&mut game, &mut objects);
+    if game.dungeon_level > 0 {
+        place_quest_giver(&mut game, &mut objects);
+        place_vendors(&mut game, &mut objects);
+    }
+    apply_reputation(&game, &mut objects);
+
+    // The pack starts out empty, so there is room for all of this.
+    for &item in class.kit() {
+        let _ = add_to_inventory(&mut game.inventory, create_item(item, 0, 0));
+    }
+    // Everyone sets out with something to eat, and a lit torch.
+    let _ = add_to_inventory(&mut game.inventory, create_item(Item::Ration, 0, 0));
+    let mut torch = create_item(Item::Torch, 0, 0);
+    if let Some(ref mut equipment) = torch.equipment {
+        equipment.equipped = true;
+    }
+    let _ = add_to_inventory(&mut game.inventory, torch);
+    if game.mode == GameMode::Normal {
+        withdraw_from_stash(tcod, &mut game);
+    }
+
+    if let GameMode::Daily(ref date) = game.mode {
+        // Everyone starts the daily challenge with the same kit.
+        for &item in &[Item::Heal, Item::Heal, Item::Confuse] {
+            let _ = add_to_inventory(&mut game.inventory, create_item(item, 0, 0));
+        }
+        game.messages.add(tr!("daily.welcome", date), MessageCategory::Lore);
+    }
+    game.write_journal(tr!("journal.began"));
+
+    (game, objects)
+}
+
+/// Hand this turn's events to everything that reacts to them.
+pub fn process_events(game: &mut Game, objects: &mut Vec<Object>) {
+    let events: Vec<GameEvent> = game.events.drain(..).collect();
+    for event in &events {
+        match *event {
+            GameEvent::LevelEntered { depth } => {
+                game.write_journal(tr!("journal.entered", depth));
+                spawn_quest_targets(depth, game, objects);
+                if depth >= DELVER_DEPTH {
+                    earn_achievement(game, Achievement::Delver);
+                }
+            }
+            GameEvent::Theft { faction } => {
+                adjust_reputation(game, faction, -THEFT_REPUTATION);
+                game.thief_of.insert(faction);
+            }
+            GameEvent::ItemPickedUp { ref name } if name == ARTIFACT_NAME => {
+                game.write_journal(tr!("journal.found", display_name(name)));
+            }
+            GameEvent::MonsterKilled { ref name, faction, xp, pos, by_player } => {
+                drop_gold(pos, game, objects);
+                objects.append(&mut game.dropped);
+                if name == BOSS_NAME {
+                    game.write_journal(tr!("journal.killed", display_name(name)));
+                    game.won = true;
+                }
+                // What the monsters do to each other earns the player nothing.
+                if !by_player {
+                    continue;
+                }
+                record_first_kill(game, name);
+                if let Some(fighter) = objects[PLAYER].fighter.as_mut() {
+                    fighter.xp += xp;
+                }
+                if UNIQUE_MONSTERS.contains(&name.as_str()) {
+                    game.write_journal(tr!("journal.killed", display_name(name)));
+                }
+                if let GameMode::Arena(_) = game.mode {
+                    game.gold += ARENA_KILL_GOLD;
+                }
+                if name == "troll" {
+                    earn_achievement(game, Achievement::TrollSlayer);
+                }
+                if let Some(faction) = faction {
+                    adjust_reputation(game, faction, -KILL_REPUTATION);
+                    if let Some(rival) = faction.rival() {
+                        adjust_reputation(game, rival, RIVAL_KILL_REPUTATION);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        let mut completed = vec![];
+        for quest in game.quests.iter_mut() {
+            if quest.advance(event, &game.inventory) {
+                completed.push(quest.clone());
+            }
+        }
+        for quest in completed {
+            complete_quest(&quest, game, objects);
+        }
+    }
+}
+
+/// Let the monsters, and everything else that goes on by itself, take their
+/// turn after the player's. A slowed player loses every other turn to the
+/// monsters, who get to act twice; the rest of the world still only moves on
+/// one turn.
+pub fn world_takes_turn(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    monsters_take_turns(tcod, game, objects);
+    tick_light(tcod, game);
+    let slowed = objects[PLAYER].has_effect(StatusKind::Slow);
+    if slowed && objects[PLAYER].alive && game.turn % 2 == 1 {
+        monsters_act(tcod, game, objects);
+    }
+}
+
+/// Advance to the next level.
+pub fn next_level(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    if let GameMode::Tutorial(_) = game.mode {
+        msgbox(
+            &format!("\n{}\n", tr!("tutorial.done")),
+            40,
+            &mut *tcod.ui,
+        );
+        game.mode = GameMode::Normal;
+    }
+    if let GameMode::Custom(_) = game.mode {
+        game.mode = GameMode::Normal;
+    }
+
+    game.messages.add(
+        tr!("descend"),
+        MessageCategory::Lore,
+    );
+    let rest = objects[PLAYER]
+        .fighter
+        .map_or(0, |f| f.max_hp * config().gameplay.descend_heal_percent / 100);
+    objects[PLAYER].heal(rest);
+    // Anyone following alive and right beside the player makes it to the stairs too.
+    for object in objects.iter() {
+        let beside = object.distance_to(&objects[PLAYER]) < 1.5;
+        if object.ai == Some(Ai::Follow) && object.alive && beside {
+            game.events.push(GameEvent::EscortArrived {
+                name: object.name.clone(),
+            });
+        }
+    }
+
+    // Stairs into a branch lead into it, and the stairs at its bottom lead back out.
+    let player_pos = objects[PLAYER].pos();
+    let into_branch = objects
+        .iter()
+        .any(|object| object.pos() == player_pos && object.name == BRANCH_STAIRS);
+    if into_branch {
+        if let Some(branch) = Branch::entered_at(game.dungeon_level) {
+            game.branch = branch;
+            game.messages.add(tr!("branch.enter", branch.name()), MessageCategory::Lore);
+            game.write_journal(tr!("journal.branch", branch.name()));
+        }
+    } else if game.branch.ends_at(game.dungeon_level) {
+        game.branch = Branch::Main;
+        game.messages.add(tr!("branch.leave"), MessageCategory::Lore);
+    }
+
+    game.dungeon_level += 1;
+    game.events.push(GameEvent::LevelEntered {
+        depth: game.dungeon_level,
+    });
+
+    // Only the player comes along; everything else stays behind.
+    objects.truncate(PLAYER + 1);
+    game.glimpses.clear();
+    game.remembered.clear();
+    let (map, rooms) = make_map(
+        game.branch,
+        game.dungeon_level,
+        objects,
+        &game.unlocked_items,
+        &mut game.rng,
+    );
+    game.map = map;
+    place_shop(&rooms, game, objects);
+    haunt_level(game, objects);
+    place_quest_giver(game, objects);
+    place_vendors(game, objects);
+    place_artifact(game, objects);
+    place_boss(game, objects);
+    place_branch_stairs(game, objects);
+    apply_reputation(game, objects);
+    initialise_fov(tcod, &game.map);
+}
+
+pub fn play_game(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    // Loading the profile and bestiary may have run into damaged files.
+    report_load_errors(&mut *tcod.ui);
+
+    // Force FOV "recompute" first time through the game loop
+    let mut previous_player_position = (-1, -1);
+
+    while !tcod.ui.window_closed() {
+        // Check for mouse or keyboard input
+        poll_input(tcod);
+
+        // Clear previous frame
+        tcod.ui.clear(Layer::Map);
+
+        // Render the screen
+        let fov_recompute = previous_player_position != objects[PLAYER].pos();
+        render_all(tcod, game, objects, fov_recompute);
+        tcod.ui.flush();
+
+        // Note any monsters that just came into view.
+        game.bestiary.observe(objects, &tcod.fov);
+
+        let in_combat = hostiles_in_view(&tcod.fov, objects);
+        tcod.audio.update_music(tcod.settings.music_volume, MusicTheme::for_game(game), in_combat);
+        play_sounds(tcod, game, objects);
+
+        // Guide tutorial players through their next objective.
+        update_tutorial(tcod, game, objects);
+
+        // Send in the next wave once the arena is clear.
+        update_arena(tcod, game, objects);
+
+        // Handle keys and exit game if needed, unless an action is being repeated
+        previous_player_position = objects[PLAYER].pos();
+        let player_action = match continue_queue(tcod, game, objects) {
+            Some(player_action) => player_action,
+            None => handle_keys(tcod, game, objects),
+        };
+        if player_action == PlayerAction::Exit {
+            break;
+        }
+
+        // Let monsters take their turn
+        if objects[PLAYER].alive && player_action != PlayerAction::DidNotTakeTurn { // NOTE: Should this be `player_action == PlayerAction::TookTurn`?
+            world_takes_turn(tcod, game, objects);
+            // Back to the latest messages, to see what the turn brought.
+            tcod.message_scroll = 0;
+        }
+        // Doors opened or shut this turn change what can be seen.
+        update_doors(tcod, &game.map);
+
+        watch_shop(game, objects);
+        collect_gold(game, objects);
+        process_events(game, objects);
+        level_up(tcod, game, objects);
+        tcod.observer.publish(game, objects, &tcod.fov);
+        if tcod.snapshots {
+            let observation = Observation::of(game, objects, &tcod.fov);
+            if let Ok(json) = serde_json::to_string(&observation) {
+                println!("{}", json);
+            }
+        }
+
+        // Show what just happened before the next key is read.
+        play_animations(tcod, game, objects);
+
+        if game.won {
+            show_victory(tcod, game, objects);
+            break;
+        }
+    }
+
+    tcod.audio.stop();
+    // Losing the bestiary only means having to rediscover some monsters.
+    let _ = game.bestiary.save();
+
+    // Leaving while still alive only puts the run aside, to be continued later.
+    let over = main_quest_completed(game) || game.won;
+    if objects[PLAYER].alive && !over && game.mode.can_save() {
+        match save_game(game, objects) {
+            Ok(()) => return,
+            Err(e) => msgbox(&format!("\n{}\n", tr!("savegame.failed", e)), 50, &mut *tcod.ui),
+        }
+    }
+
+    // Death is final, unless the player would rather carry on from their last save.
+    if !objects[PLAYER].alive && !game.mode.is_casual(&tcod.settings) && game.mode.can_save() {
+        delete_save();
+    }
+
+    // A run over, in death or victory, goes in the morgue. Demo runs leave no
+    // trace, not even there.
+    if (!objects[PLAYER].alive || game.won) && game.mode != GameMode::Demo {
+        if let Err(e) = write_morgue(game, objects, &tcod.settings.player_name) {
+            msgbox(&format!("\n{}\n", tr!("morgue.failed", e)), 50, &mut *tcod.ui);
+        }
+    }
+
+    if let Some(score) = record_high_score(game, objects, &tcod.settings.player_name) {
+        show_high_scores(Some(&score), &mut *tcod.ui);
+    }
+
+    if let GameMode::Daily(ref date) = game.mode {
+        record_daily_score(date, game.kills, objects[PLAYER].alive);
+    }
+    if let GameMode::Arena(wave) = game.mode {
+        record_arena_score(tcod, wave, game.kills);
+    }
+    submit_run_summary(tcod, game, objects);
+
+    // Whether the run ended in death or victory, some of its loot can be kept.
+    if game.mode == GameMode::Normal && (!objects[PLAYER].alive || over) {
+        deposit_in_stash(tcod, game);
+    }
+}
@@ -0,0 +1,69 @@
+//! Gold lying about the dungeon, and dropped by the monsters that fall in it.
+//! It is picked up just by walking over it.
+
+use std::cmp;
+
+use rand::{Rng, StdRng};
+
+use crate::game::{Game, GameMode};
+use crate::map::{Map, Rect};
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+use crate::tcod_types::colors::*;
+
+pub const GOLD_NAME: &str = "gold";
+pub const GOLD_PILE_CHANCE: f32 = 0.3; // Chance of a pile of gold in each room
+pub const GOLD_PILE_MAX: u32 = 25;
+pub const GOLD_DROP_CHANCE: f32 = 0.4; // Chance of a monster dropping gold when it dies
+pub const GOLD_DROP_PER_DEPTH: u32 = 5; // Most gold a monster drops, per level of depth
+
+/// A pile of gold; like a stack of items, its count says how much is in it.
+pub fn create_gold(amount: u32, x: i32, y: i32) -> Object {
+    let mut gold = Object::new(x, y, '$', GOLD_NAME, GOLD, false);
+    gold.count = amount;
+    gold
+}
+
+pub fn is_gold(object: &Object) -> bool {
+    object.name == GOLD_NAME && object.item.is_none() && object.fighter.is_none()
+}
+
+/// Sometimes leave a pile of gold somewhere on the room's floor.
+pub fn place_gold(room: Rect, map: &Map, objects: &mut Vec<Object>, rng: &mut StdRng) {
+    if rng.next_f32() >= GOLD_PILE_CHANCE {
+        return;
+    }
+    let x = rng.gen_range(room.x1 + 1, room.x2);
+    let y = rng.gen_range(room.y1 + 1, room.y2);
+    if !Object::is_blocked(x, y, map, objects) {
+        let amount = rng.gen_range(1, GOLD_PILE_MAX + 1);
+        objects.push(create_gold(amount, x, y));
+    }
+}
+
+/// Sometimes leave gold where a monster died; deeper monsters carry more.
+/// Arena monsters leave none, since the arena pays for kills itself.
+pub fn drop_gold(pos: (i32, i32), game: &mut Game, objects: &mut Vec<Object>) {
+    if let GameMode::Arena(_) = game.mode {
+        return;
+    }
+    if game.rng.next_f32() >= GOLD_DROP_CHANCE {
+        return;
+    }
+    let most = GOLD_DROP_PER_DEPTH * cmp::max(game.dungeon_level, 1);
+    let amount = game.rng.gen_range(1, most + 1);
+    objects.push(create_gold(amount, pos.0, pos.1));
+}
+
+/// Pick up any gold on the player's tile.
+pub fn collect_gold(game: &mut Game, objects: &mut Vec<Object>) {
+    let pos = objects[PLAYER].pos();
+    while let Some(index) = objects
+        .iter()
+        .position(|object| is_gold(object) && object.pos() == pos)
+    {
+        let gold = objects.swap_remove(index);
+        game.gold += gold.count;
+        game.messages.add(tr!("gold.picked-up", gold.count), MessageCategory::Item);
+    }
+}
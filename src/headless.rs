@@ -0,0 +1,291 @@
+//! Running the game with no window at all, for tests and CI. Keys come from a
+//! script instead of a player, nothing is drawn, and the state of the game is
+//! printed as a line of JSON after every frame, to be checked afterwards.
+//!
+//! A script has a key on each line, optionally followed by how many times to
+//! press it; blank lines and lines starting with `#` are skipped:
+//!
+//! ```text
+//! # Walk east, then wait a while.
+//! l 5
+//! Right
+//! .  10
+//! ```
+//!
+//! Keys are named as in `keybindings.toml`: `Enter`, `Escape`, `Up`, `NumPad5`
+//! and so on, or the character they type. `examples/explore.txt` is a whole
+//! script, which the tests in `tests/headless.rs` play.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Read};
+
+use crate::backend::{InputSource, Layer, Renderer, wants};
+use crate::config::config;
+use crate::game::{GameMode, new_game, play_game};
+use crate::keybindings::key_code;
+use crate::profile::PlayerClass;
+use crate::tcod_types::colors::*;
+use crate::tcod_types::console::{BackgroundFlag, TextAlignment};
+use crate::tcod_types::input::{self, Event, EventFlags, Key, KeyCode};
+use crate::ui::Tcod;
+
+/// Frames between one scripted key and the next. Prompts drop any keys
+/// pressed before they opened, and this keeps them from dropping the key
+/// meant for them.
+pub const FRAMES_PER_KEY: u32 = 2;
+
+/// A backend that draws nothing and plays back a script of keys.
+pub struct HeadlessBackend {
+    sizes: [(i32, i32); 5],
+    /// Keys still to be pressed, in order.
+    script: VecDeque<Key>,
+    /// The release of the key just pressed, if it has not been handed out.
+    release: Option<Key>,
+    /// Frames shown since the last key was pressed.
+    frames: u32,
+    fullscreen: bool,
+}
+
+impl HeadlessBackend {
+    /// Read a script from a file, or from standard input if the path is `-`.
+    pub fn from_script(path: &str) -> io::Result<Self> {
+        let text = if path == "-" {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        } else {
+            fs::read_to_string(path)?
+        };
+        let script = parse_script(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let screen = (config().screen.width, config().screen.height);
+        Ok(HeadlessBackend {
+            sizes: [screen, (1, 1), (1, 1), (1, 1), (1, 1)],
+            script: script,
+            release: None,
+            frames: FRAMES_PER_KEY,
+            fullscreen: false,
+        })
+    }
+
+    /// Press the next key of the script, if any are left.
+    fn press(&mut self) -> Option<Key> {
+        let key = self.script.pop_front()?;
+        let mut release = key;
+        release.pressed = false;
+        self.release = Some(release);
+        self.frames = 0;
+        Some(key)
+    }
+}
+
+fn parse_script(text: &str) -> Result<VecDeque<Key>, String> {
+    let mut keys = VecDeque::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let times = match words.next() {
+            Some(times) => times
+                .parse()
+                .map_err(|_| format!("line {}: \"{}\" is not a count", number + 1, times))?,
+            None => 1,
+        };
+        let key = scripted_key(name)
+            .ok_or_else(|| format!("line {}: no key called \"{}\"", number + 1, name))?;
+        keys.extend((0..times).map(|_| key));
+    }
+    Ok(keys)
+}
+
+/// The press of the key with the given name, or of the key typing the given
+/// character.
+// libtcod's keys have private fields, so they cannot be built with `..Default::default()`.
+#[allow(clippy::field_reassign_with_default)]
+fn scripted_key(name: &str) -> Option<Key> {
+    let mut key = Key::default();
+    key.pressed = true;
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(printable), None) => {
+            key.code = KeyCode::Char;
+            key.printable = printable;
+            key.shift = printable.is_uppercase();
+        }
+        _ => {
+            key.code = key_code(name)?;
+            if key.code == KeyCode::Spacebar {
+                key.printable = ' ';
+            }
+        }
+    }
+    Some(key)
+}
+
+fn index(layer: Layer) -> usize {
+    layer as usize
+}
+
+/// The number of lines the text takes when wrapped to the width, roughly:
+/// long lines are cut anywhere, not only between words.
+fn wrapped_lines(width: i32, height: i32, text: &str) -> i32 {
+    let width = width.max(1) as usize;
+    let lines: usize = text
+        .split('\n')
+        .map(|line| line.chars().count().div_ceil(width))
+        .map(|lines| lines.max(1))
+        .sum();
+    let lines = lines as i32;
+    if height > 0 { lines.min(height) } else { lines }
+}
+
+impl Renderer for HeadlessBackend {
+    fn size(&self, layer: Layer) -> (i32, i32) {
+        self.sizes[index(layer)]
+    }
+
+    fn resize(&mut self, layer: Layer, width: i32, height: i32) {
+        self.sizes[index(layer)] = (width, height);
+    }
+
+    fn set_default_foreground(&mut self, _layer: Layer, _color: Color) {}
+    fn set_default_background(&mut self, _layer: Layer, _color: Color) {}
+    fn clear(&mut self, _layer: Layer) {}
+    fn put_char(&mut self, _layer: Layer, _x: i32, _y: i32, _glyph: char, _flag: BackgroundFlag) {}
+
+    fn set_char_background(
+        &mut self,
+        _layer: Layer,
+        _x: i32,
+        _y: i32,
+        _color: Color,
+        _flag: BackgroundFlag,
+    ) {
+    }
+
+    fn rect(
+        &mut self,
+        _layer: Layer,
+        _x: i32,
+        _y: i32,
+        _width: i32,
+        _height: i32,
+        _flag: BackgroundFlag,
+    ) {
+    }
+
+    fn print_ex(
+        &mut self,
+        _layer: Layer,
+        _x: i32,
+        _y: i32,
+        _flag: BackgroundFlag,
+        _alignment: TextAlignment,
+        _text: &str,
+    ) {
+    }
+
+    fn print_rect(
+        &mut self,
+        _layer: Layer,
+        _x: i32,
+        _y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+    ) -> i32 {
+        wrapped_lines(width, height, text)
+    }
+
+    fn get_height_rect(
+        &self,
+        _layer: Layer,
+        _x: i32,
+        _y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+    ) -> i32 {
+        wrapped_lines(width, height, text)
+    }
+
+    fn cell(&self, _layer: Layer, _x: i32, _y: i32) -> (char, Color, Color) {
+        (' ', WHITE, BLACK)
+    }
+
+    fn blit(
+        &mut self,
+        _from: Layer,
+        _position: (i32, i32),
+        _size: (i32, i32),
+        _to: (i32, i32),
+        _foreground_alpha: f32,
+        _background_alpha: f32,
+    ) {
+    }
+
+    fn copy_screen(&mut self, to: Layer) {
+        self.sizes[index(to)] = self.sizes[index(Layer::Screen)];
+    }
+
+    fn flush(&mut self) {
+        self.frames = self.frames.saturating_add(1);
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+    }
+
+    /// The run is over once the script is.
+    fn window_closed(&self) -> bool {
+        self.script.is_empty() && self.release.is_none()
+    }
+
+    fn save_image(&self, _layer: Layer, _path: &str) {}
+}
+
+impl InputSource for HeadlessBackend {
+    fn check_for_event(&mut self, mask: EventFlags) -> Option<Event> {
+        if let Some(release) = self.release.take() {
+            if wants(mask, input::KEY_RELEASE) {
+                return Some(Event::Key(release));
+            }
+        }
+        if !wants(mask, input::KEY_PRESS) || self.frames < FRAMES_PER_KEY {
+            return None;
+        }
+        self.press().map(Event::Key)
+    }
+
+    fn wait_for_event(&mut self, mask: EventFlags) -> Option<Event> {
+        self.release = None;
+        if !wants(mask, input::KEY_PRESS) {
+            return None;
+        }
+        self.press().map(Event::Key)
+    }
+
+    fn wait_for_keypress(&mut self) -> Key {
+        match self.wait_for_event(input::KEY_PRESS) {
+            Some(Event::Key(key)) => key,
+            _ => Key::default(),
+        }
+    }
+}
+
+/// Play a run from the script, as a fighter. The run is a demo run, so it
+/// leaves no trace in the profile and is not saved; `--seed` makes it the
+/// same every time.
+pub fn play_headless(tcod: &mut Tcod) {
+    tcod.snapshots = true;
+    let (mut game, mut objects) = new_game(tcod, GameMode::Demo, PlayerClass::Fighter);
+    play_game(tcod, &mut game, &mut objects);
+}
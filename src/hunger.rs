@@ -0,0 +1,83 @@
+//! Hunger. The player's stomach empties a little every turn; a well fed body
+//! slowly mends its wounds, at the cost of emptying faster, and an empty one
+//! starves.
+
+use std::cmp;
+
+use crate::config::config;
+use crate::game::Game;
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+
+pub const HUNGRY_AT: i32 = 300; // Nutrition left when the player starts to feel hungry
+pub const WEAK_AT: i32 = 100;
+pub const STARVATION_DAMAGE: i32 = 1; // Hit points lost each turn with nothing left to digest
+pub const MEND_INTERVAL: u32 = 10; // Turns between hit points regained while well fed
+pub const MEND_NUTRITION: i32 = 10; // Extra nutrition each hit point regained costs
+
+/// How hungry the player is, from best to worst.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Hunger {
+    Fed,
+    Hungry,
+    Weak,
+    Starving,
+}
+
+impl Hunger {
+    pub fn of(nutrition: i32) -> Hunger {
+        if nutrition <= 0 {
+            Hunger::Starving
+        } else if nutrition <= WEAK_AT {
+            Hunger::Weak
+        } else if nutrition <= HUNGRY_AT {
+            Hunger::Hungry
+        } else {
+            Hunger::Fed
+        }
+    }
+
+    /// What the panel says about it; nothing while the player is fed.
+    pub fn name(self) -> Option<String> {
+        match self {
+            Hunger::Fed => None,
+            Hunger::Hungry => Some(tr!("hunger.hungry")),
+            Hunger::Weak => Some(tr!("hunger.weak")),
+            Hunger::Starving => Some(tr!("hunger.starving")),
+        }
+    }
+}
+
+/// The nutrition a new run starts with, and that older saves are given.
+pub fn full_stomach() -> i32 {
+    config().gameplay.max_nutrition
+}
+
+/// Let a turn's worth of food be digested.
+pub fn tick_hunger(game: &mut Game, objects: &mut [Object]) {
+    let before = Hunger::of(game.nutrition);
+    game.nutrition = cmp::max(game.nutrition - 1, 0);
+
+    let hurt = objects[PLAYER].fighter.map_or(false, |f| f.hp < f.max_hp);
+    if before == Hunger::Fed && hurt && game.turn.is_multiple_of(MEND_INTERVAL) {
+        objects[PLAYER].heal(1);
+        game.nutrition = cmp::max(game.nutrition - MEND_NUTRITION, 0);
+    }
+
+    let after = Hunger::of(game.nutrition);
+    if after != before {
+        let message = match after {
+            Hunger::Fed => None,
+            Hunger::Hungry => Some(tr!("hunger.becomes-hungry")),
+            Hunger::Weak => Some(tr!("hunger.becomes-weak")),
+            Hunger::Starving => Some(tr!("hunger.becomes-starving")),
+        };
+        if let Some(message) = message {
+            game.messages.add(message, MessageCategory::Warning);
+        }
+    }
+    if after == Hunger::Starving && objects[PLAYER].alive {
+        game.last_attacker = Some(tr!("hunger.starvation"));
+        objects[PLAYER].take_damage(STARVATION_DAMAGE, game);
+    }
+}
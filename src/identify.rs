@@ -0,0 +1,107 @@
+//! Unidentified items. Each run, potions and scrolls are handed out looks at
+//! random ("bubbling blue potion", "scroll labelled ZELGO MER"), and go by
+//! those until the player finds out what they are, by using one or with a
+//! scroll of identify.
+
+use rand::{Rng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::items::Item;
+use crate::locale::display_name;
+use crate::object::Object;
+
+pub const POTIONS: [Item; 3] = [Item::Heal, Item::Telepathy, Item::Regeneration];
+pub const SCROLLS: [Item; 7] = [
+    Item::Lightning,
+    Item::Confuse,
+    Item::Teleport,
+    Item::Clairvoyance,
+    Item::Fire,
+    Item::Identify,
+    Item::RemoveCurse,
+];
+
+/// The looks potions can be given; there are more than there are potions.
+pub const POTION_LOOKS: [&str; 6] = [
+    "bubbling blue potion",
+    "murky green potion",
+    "fizzing red potion",
+    "smoky black potion",
+    "glowing yellow potion",
+    "milky white potion",
+];
+
+/// The labels scrolls can be given; there are more than there are scrolls.
+pub const SCROLL_LOOKS: [&str; 8] = [
+    "scroll labelled ZELGO MER",
+    "scroll labelled FOOBIE BLETCH",
+    "scroll labelled XIXAXA",
+    "scroll labelled ELBIB YLOH",
+    "scroll labelled VERR YED HORRE",
+    "scroll labelled KIRJE",
+    "scroll labelled THARR",
+    "scroll labelled ANDOVA BEGARIN",
+];
+
+/// What each kind of potion and scroll looks like this run, and which kinds
+/// the player has learned to tell apart. Kinds without a look, such as in
+/// runs saved before items needed identifying, are known from the start.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Identification {
+    looks: Vec<(Item, String)>,
+    known: Vec<Item>,
+}
+
+impl Identification {
+    /// Hand out looks to the potions and scrolls at random.
+    pub fn shuffled(rng: &mut StdRng) -> Self {
+        let mut potion_looks = POTION_LOOKS;
+        let mut scroll_looks = SCROLL_LOOKS;
+        rng.shuffle(&mut potion_looks);
+        rng.shuffle(&mut scroll_looks);
+        let looks = POTIONS
+            .iter()
+            .zip(potion_looks.iter())
+            .chain(SCROLLS.iter().zip(scroll_looks.iter()))
+            .map(|(&item, &look)| (item, look.to_string()))
+            .collect();
+        Identification {
+            looks: looks,
+            known: vec![],
+        }
+    }
+
+    pub fn is_known(&self, item: Item) -> bool {
+        self.known.contains(&item) || !self.looks.iter().any(|&(kind, _)| kind == item)
+    }
+
+    /// Learn what a kind of item is. Returns whether it was news.
+    pub fn learn(&mut self, item: Item) -> bool {
+        if self.is_known(item) {
+            return false;
+        }
+        self.known.push(item);
+        true
+    }
+
+    /// The look of a kind of item that has not been identified yet.
+    pub fn look_of(&self, item: Item) -> Option<&str> {
+        if self.known.contains(&item) {
+            return None;
+        }
+        self.looks
+            .iter()
+            .find(|&&(kind, _)| kind == item)
+            .map(|(_, look)| look.as_str())
+    }
+
+    /// The name an object goes by: what it looks like if it is an item the
+    /// player has yet to identify, or else its own.
+    pub fn name_of(&self, object: &Object) -> String {
+        match object.item.and_then(|item| self.look_of(item)) {
+            Some(look) => display_name(look),
+            None => display_name(&object.name),
+        }
+    }
+}
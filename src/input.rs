@@ -0,0 +1,537 @@
+//! Reading the keyboard, mouse and game controller, and turning what the
+//! player pressed into commands.
+
+use std::cell::RefCell;
+use std::cmp;
+use std::time::{Duration, Instant};
+
+use crate::action::{Action, ActionQueue, PlayerAction, perform_action, start_resting};
+use crate::backend::Backend;
+use crate::bestiary::show_bestiary;
+use crate::branch::is_stairs;
+use crate::config::config;
+use crate::game::{Game, save_game};
+use crate::keybindings::{Command, is_bound, keybindings};
+use crate::map::{is_closed_door, is_open_door};
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+use crate::quest::show_quest_log;
+use crate::ranged::ready_to_fire;
+use crate::render::{export_map, mouse_tile, take_screenshot};
+use crate::tcod_types::input::{self, Event, Key, KeyCode};
+use crate::throw::THROW_RANGE;
+use crate::ui::{
+    Tcod, inventory_menu, look_around, options_menu, pick_tile, show_help, show_journal,
+    show_message_history,
+};
+
+pub const MSG_SCROLL_STEP: usize = 3; // Messages scrolled by PageUp and PageDown
+
+pub const KEY_BUFFER_SIZE: usize = 8; // Key presses kept while the game is busy
+pub const KEY_REPEAT_DELAYS: [u32; 4] = [0, 150, 300, 500]; // In milliseconds; 0 turns repeat off
+pub const KEY_REPEAT_INTERVAL_MS: u64 = 100;
+pub const KEY_REPEAT_STALE_MS: u64 = 1000;
+pub const MAX_COUNT: u32 = 999; // Largest count that can be typed before a command
+/// The direction a stick has to be pushed past, from 0 to 1, to count.
+#[cfg(feature = "gamepad")]
+pub const STICK_DEADZONE: f32 = 0.5;
+
+/// Controllers, read through gilrs. Their buttons and sticks are turned into
+/// the key presses the keyboard would send: the d-pad and left stick into
+/// arrows (and the numeric keypad for diagonals), South into Enter, East into
+/// Backspace, West into Tab and Start into Escape.
+#[cfg(feature = "gamepad")]
+pub struct Gamepad {
+    pub gilrs: Option<gilrs::Gilrs>,
+    pub stick: (f32, f32),
+    /// The way the left stick points, so pushing it only moves once.
+    pub direction: (i32, i32),
+}
+
+#[cfg(feature = "gamepad")]
+impl Gamepad {
+    // Not `Default`, since it opens the controllers.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Gamepad {
+            // Without controller support from the system, there is just the keyboard.
+            gilrs: gilrs::Gilrs::new().ok(),
+            stick: (0.0, 0.0),
+            direction: (0, 0),
+        }
+    }
+
+    pub fn connected(&self) -> bool {
+        self.gilrs
+            .as_ref()
+            .map_or(false, |gilrs| gilrs.gamepads().any(|(_, pad)| pad.is_connected()))
+    }
+
+    /// The key presses made on any controller since the last poll.
+    pub fn poll(&mut self) -> Vec<Key> {
+        use gilrs::{Axis, Button, EventType};
+        use crate::tcod_types::input::KeyCode::*;
+
+        let mut keys = vec![];
+        let gilrs = match self.gilrs {
+            Some(ref mut gilrs) => gilrs,
+            None => return keys,
+        };
+        while let Some(gilrs::Event { event, .. }) = gilrs.next_event() {
+            match event {
+                EventType::ButtonPressed(button, _) => {
+                    let code = match button {
+                        Button::DPadUp => Up,
+                        Button::DPadDown => Down,
+                        Button::DPadLeft => Left,
+                        Button::DPadRight => Right,
+                        Button::South => Enter,
+                        Button::East => Backspace,
+                        Button::West => Tab,
+                        Button::Start => Escape,
+                        _ => continue,
+                    };
+                    keys.push(key_press(code));
+                }
+                EventType::AxisChanged(Axis::LeftStickX, value, _) => self.stick.0 = value,
+                EventType::AxisChanged(Axis::LeftStickY, value, _) => self.stick.1 = value,
+                _ => {}
+            }
+        }
+
+        let push = |value: f32| {
+            if value > STICK_DEADZONE {
+                1
+            } else if value < -STICK_DEADZONE {
+                -1
+            } else {
+                0
+            }
+        };
+        // Up is positive on the stick, but up the screen on the map.
+        let direction = (push(self.stick.0), -push(self.stick.1));
+        if direction != self.direction {
+            self.direction = direction;
+            let code = match direction {
+                (0, -1) => Some(Up),
+                (0, 1) => Some(Down),
+                (-1, 0) => Some(Left),
+                (1, 0) => Some(Right),
+                (-1, -1) => Some(NumPad7),
+                (1, -1) => Some(NumPad9),
+                (-1, 1) => Some(NumPad1),
+                (1, 1) => Some(NumPad3),
+                _ => None,
+            };
+            keys.extend(code.map(key_press));
+        }
+        keys
+    }
+}
+
+/// Stands in for the controllers when the game is built without gamepad support.
+#[cfg(not(feature = "gamepad"))]
+#[derive(Default)]
+pub struct Gamepad;
+
+#[cfg(not(feature = "gamepad"))]
+impl Gamepad {
+    pub fn new() -> Self {
+        Gamepad
+    }
+
+    pub fn connected(&self) -> bool {
+        false
+    }
+
+    pub fn poll(&mut self) -> Vec<Key> {
+        vec![]
+    }
+}
+
+thread_local! {
+    /// Kept apart from `Tcod` so that menus, which only get the backend, can read it too.
+    pub static GAMEPAD: RefCell<Gamepad> = RefCell::new(Gamepad::new());
+}
+
+/// A press of the given key, as the keyboard would send it.
+#[cfg(feature = "gamepad")]
+pub fn key_press(code: KeyCode) -> Key {
+    let mut key = Key::default();
+    key.code = code;
+    key.pressed = true;
+    key
+}
+
+/// Wait for a key press, from the keyboard or a controller. Anything pressed
+/// before the wait began is dropped.
+pub fn wait_for_key(ui: &mut dyn Backend) -> Key {
+    if !GAMEPAD.with(|pad| pad.borrow().connected()) {
+        return ui.wait_for_keypress();
+    }
+    while ui.check_for_event(input::KEY_PRESS | input::MOUSE).is_some() {}
+    GAMEPAD.with(|pad| pad.borrow_mut().poll());
+    while !ui.window_closed() {
+        if let Some(Event::Key(key)) = ui.check_for_event(input::KEY_PRESS) {
+            return key;
+        }
+        if let Some(key) = GAMEPAD.with(|pad| pad.borrow_mut().poll()).into_iter().next() {
+            return key;
+        }
+        // Nothing new is drawn; this only keeps the wait to the frame rate.
+        ui.flush();
+    }
+    Key::default()
+}
+
+/// Read all the input that arrived since the last frame, and take the next
+/// key press to handle. Presses are queued, so none are lost while the game
+/// is busy, and a held movement key repeats once the player's delay is up.
+pub fn poll_input(tcod: &mut Tcod) {
+    let now = Instant::now();
+    // A click counts for the frame it arrives in, and no longer.
+    tcod.mouse.lbutton_pressed = false;
+    while let Some(event) = tcod.ui.check_for_event(input::MOUSE | input::KEY) {
+        let key = match event {
+            Event::Mouse(m) => {
+                tcod.mouse = m;
+                // The wheel scrolls the message panel.
+                if m.wheel_up {
+                    tcod.message_scroll += 1;
+                } else if m.wheel_down {
+                    tcod.message_scroll = tcod.message_scroll.saturating_sub(1);
+                }
+                continue;
+            }
+            Event::Key(k) => k,
+        };
+        let held = tcod.held_key.map_or(false, |(held, _)| key_id(held) == key_id(key));
+        if !key.pressed {
+            if held {
+                tcod.held_key = None;
+            }
+        } else if !held {
+            // Presses of a key already held are the system's own repeat, which is not used.
+            if tcod.keys.len() < KEY_BUFFER_SIZE {
+                tcod.keys.push_back(key);
+            }
+            let delay = u64::from(tcod.settings.key_repeat_delay);
+            tcod.held_key = if delay > 0 && is_movement_key(key) {
+                Some((key, now + Duration::from_millis(delay)))
+            } else {
+                None
+            };
+        }
+    }
+
+    // Long overdue means another screen had the keyboard, and may have taken the release.
+    let overdue = tcod.held_key.map_or(false, |(_, repeat_at)| {
+        now > repeat_at + Duration::from_millis(KEY_REPEAT_STALE_MS)
+    });
+    if overdue {
+        tcod.held_key = None;
+    }
+    for key in GAMEPAD.with(|pad| pad.borrow_mut().poll()) {
+        if tcod.keys.len() < KEY_BUFFER_SIZE {
+            tcod.keys.push_back(key);
+        }
+    }
+    if let Some((key, ref mut repeat_at)) = tcod.held_key {
+        if now >= *repeat_at && tcod.keys.is_empty() {
+            tcod.keys.push_back(key);
+            *repeat_at = now + Duration::from_millis(KEY_REPEAT_INTERVAL_MS);
+        }
+    }
+    tcod.key = tcod.keys.pop_front().unwrap_or_default();
+}
+
+/// What tells keys apart.
+pub fn key_id(key: Key) -> (KeyCode, char) {
+    (key.code, key.printable)
+}
+
+pub fn is_movement_key(key: Key) -> bool {
+    matches!(keybindings().command(key), Some(Command::Move(..)))
+}
+
+/// The macro slot a key press plays or records, if any, numbered from 1.
+pub fn macro_slot(key: Key) -> Option<u32> {
+    config()
+        .macros
+        .keys
+        .iter()
+        .position(|name| is_bound(name, key))
+        .map(|index| index as u32 + 1)
+}
+
+/// Start recording a macro for the given slot, or stop the one being
+/// recorded and keep it in the settings.
+pub fn toggle_recording(tcod: &mut Tcod, game: &mut Game, slot: u32) {
+    match tcod.recording.take() {
+        Some((slot, steps)) => {
+            if steps.is_empty() {
+                tcod.settings.macros.remove(&slot);
+                game.messages.add(tr!("macro.cleared", slot), MessageCategory::System);
+            } else {
+                tcod.settings.macros.insert(slot, steps);
+                game.messages.add(tr!("macro.saved", slot), MessageCategory::System);
+            }
+            let _ = tcod.settings.save();
+        }
+        None => {
+            tcod.recording = Some((slot, vec![]));
+            game.messages.add(tr!("macro.recording", slot), MessageCategory::System);
+        }
+    }
+}
+
+/// Line up the actions of the macro in the given slot.
+pub fn play_macro(tcod: &mut Tcod, game: &mut Game, objects: &[Object], slot: u32) {
+    match tcod.settings.macros.get(&slot) {
+        Some(steps) => {
+            let actions = steps.iter().cloned().collect();
+            tcod.queue = Some(ActionQueue::new(actions, &tcod.fov, game, objects));
+        }
+        None => game.messages.add(tr!("macro.empty", slot), MessageCategory::Warning),
+    }
+}
+
+pub fn handle_keys(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) -> PlayerAction {
+    use crate::tcod_types::input::KeyCode::*;
+
+    // Digits make up a count for the command that follows, unless they are
+    // bound to a command of their own.
+    if let Some(digit) = tcod.key.printable.to_digit(10) {
+        if tcod.key.code == Char && keybindings().command(tcod.key).is_none() {
+            let count = tcod.count.unwrap_or(0) * 10 + digit;
+            tcod.count = Some(cmp::min(count, MAX_COUNT));
+            return PlayerAction::DidNotTakeTurn;
+        }
+    }
+    if tcod.key.code == Escape && tcod.count.take().is_some() {
+        // Escape only cancels the count.
+        return PlayerAction::DidNotTakeTurn;
+    }
+    let player_action = handle_command(tcod, game, objects);
+    if tcod.key.code != NoKey {
+        // A count is only good for the command right after it.
+        tcod.count = None;
+    }
+    player_action
+}
+
+pub fn handle_command(tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) -> PlayerAction {
+    use crate::tcod_types::input::KeyCode::*;
+
+    let player_alive = objects[PLAYER].alive;
+    let key = tcod.key;
+    if key.code == Enter && key.alt {
+        // Alt+Enter: toggle fullscreen
+        let fullscreen = tcod.ui.is_fullscreen();
+        tcod.ui.set_fullscreen(!fullscreen);
+        return PlayerAction::DidNotTakeTurn;
+    }
+    if let Some(slot) = macro_slot(key) {
+        // Play a macro; with Shift, start or stop recording one.
+        if key.shift {
+            toggle_recording(tcod, game, slot);
+        } else if player_alive {
+            play_macro(tcod, game, objects, slot);
+        }
+        return PlayerAction::DidNotTakeTurn;
+    }
+
+    if tcod.mouse.lbutton_pressed && player_alive {
+        // A click on somewhere the player knows sets off walking there.
+        let explored = |(x, y): (i32, i32)| game.map[x as usize][y as usize].explored;
+        if let Some(tile) = mouse_tile(tcod.mouse, tcod).filter(|&tile| explored(tile)) {
+            let actions = std::iter::once(Action::Travel(tile.0, tile.1)).collect();
+            tcod.queue = Some(ActionQueue::new(actions, &tcod.fov, game, objects));
+        }
+        return PlayerAction::DidNotTakeTurn;
+    }
+
+    match (keybindings().command(key), player_alive) {
+        (Some(Command::Move(dx, dy)), true) => {
+            perform_action(Action::Move(dx, dy), tcod, game, objects)
+        }
+        (Some(Command::Wait), true) => perform_action(Action::Wait, tcod, game, objects),
+        (Some(Command::Rest), true) => {
+            start_resting(tcod, game, objects);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::Search), true) => perform_action(Action::Search, tcod, game, objects),
+        (Some(Command::Shout), true) => perform_action(Action::Shout, tcod, game, objects),
+        (Some(Command::PickUp), true) => {
+            // Pick up an item. 
+            perform_action(Action::PickUp, tcod, game, objects)
+        }
+        (Some(Command::Descend), true) => {
+            // Go down the stairs, if the player is on them.
+            perform_action(Action::Descend, tcod, game, objects)
+        }
+        (Some(Command::OpenDoor), true) => match choose_door(tcod, game, objects, true) {
+            Some((dx, dy)) => perform_action(Action::OpenDoor(dx, dy), tcod, game, objects),
+            None => PlayerAction::DidNotTakeTurn,
+        },
+        (Some(Command::CloseDoor), true) => match choose_door(tcod, game, objects, false) {
+            Some((dx, dy)) => perform_action(Action::CloseDoor(dx, dy), tcod, game, objects),
+            None => PlayerAction::DidNotTakeTurn,
+        },
+        (Some(Command::Help), _) => {
+            show_help(tcod);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::Look), _) => {
+            look_around(tcod, game, objects);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::Inventory), true) => {
+            // Show the inventory. 
+            let inventory_index = inventory_menu(
+                &game.inventory, 
+                &game.identification,
+                &tr!("inventory-header"),
+                &mut *tcod.ui
+            );
+            match inventory_index {
+                Some(inventory_index) => {
+                    perform_action(Action::UseItem(inventory_index), tcod, game, objects)
+                }
+                None => PlayerAction::DidNotTakeTurn,
+            }
+        }
+        (Some(Command::Throw), true) => {
+            let header = tr!("throw.header");
+            let index = match inventory_menu(
+                &game.inventory,
+                &game.identification,
+                &header,
+                &mut *tcod.ui,
+            ) {
+                Some(index) => index,
+                None => return PlayerAction::DidNotTakeTurn,
+            };
+            // Aim at the closest monster to begin with, if there is one in range.
+            let start = Object::closest_monster(tcod, objects, THROW_RANGE)
+                .map_or(objects[PLAYER].pos(), |id| objects[id].pos());
+            match pick_tile(tcod, game, objects, &tr!("throw.target"), start) {
+                Some((x, y)) if (x, y) != objects[PLAYER].pos() => {
+                    perform_action(Action::Throw(index, x, y), tcod, game, objects)
+                }
+                _ => PlayerAction::DidNotTakeTurn,
+            }
+        }
+        (Some(Command::Fire), true) => {
+            let launcher = match ready_to_fire(game) {
+                Some((launcher, _)) => launcher,
+                None => return PlayerAction::DidNotTakeTurn,
+            };
+            let start = Object::closest_monster(tcod, objects, launcher.range)
+                .map_or(objects[PLAYER].pos(), |id| objects[id].pos());
+            match pick_tile(tcod, game, objects, &tr!("fire.target"), start) {
+                Some((x, y)) if (x, y) != objects[PLAYER].pos() => {
+                    perform_action(Action::Fire(x, y), tcod, game, objects)
+                }
+                _ => PlayerAction::DidNotTakeTurn,
+            }
+        }
+        (Some(Command::Context), true) => {
+            // Do whatever the spot calls for: take the stairs, or pick up what lies here.
+            let on_stairs = objects
+                .iter()
+                .any(|object| object.pos() == objects[PLAYER].pos() && is_stairs(object));
+            let action = if on_stairs { Action::Descend } else { Action::PickUp };
+            perform_action(action, tcod, game, objects)
+        }
+        (Some(Command::Bestiary), _) => {
+            show_bestiary(&game.bestiary, &mut *tcod.ui);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::QuestLog), _) => {
+            show_quest_log(game, &mut *tcod.ui);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::MessageHistory), _) => {
+            show_message_history(tcod, &game.messages);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::Journal), _) => {
+            show_journal(tcod, &game.journal);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::ScrollBack), _) => {
+            // Scroll the message panel back...
+            tcod.message_scroll += MSG_SCROLL_STEP;
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::ScrollForward), _) => {
+            // ...and forward again.
+            tcod.message_scroll = tcod.message_scroll.saturating_sub(MSG_SCROLL_STEP);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::Options), _) => {
+            options_menu(tcod);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::Save), true) if game.mode.can_save() => {
+            if game.mode.is_casual(&tcod.settings) {
+                // Save the game and keep playing.
+                match save_game(game, objects) {
+                    Ok(()) => game.messages.add(tr!("savegame.saved"), MessageCategory::System),
+                    Err(e) => game.messages.add(tr!("savegame.failed", e), MessageCategory::Warning),
+                }
+                PlayerAction::DidNotTakeTurn
+            } else {
+                // Otherwise saving quits, since a save is for picking the run up
+                // later, not for going back to. Leaving saves the run.
+                PlayerAction::Exit
+            }
+        }
+        (Some(Command::ExportMap), _) => {
+            export_map(tcod, game, objects);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::Screenshot), _) => {
+            // With Shift, the screen is also dumped as text.
+            take_screenshot(tcod, game, key.shift);
+            PlayerAction::DidNotTakeTurn
+        }
+        (Some(Command::Quit), _) => PlayerAction::Exit, // exit game
+        _ => PlayerAction::DidNotTakeTurn,
+    }
+}
+
+/// The offset of the door next to the player to open (or shut). With more
+/// than one to choose from, the player picks it with the cursor.
+pub fn choose_door(
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &[Object],
+    open: bool,
+) -> Option<(i32, i32)> {
+    let (x, y) = objects[PLAYER].pos();
+    let is_door = |dx: i32, dy: i32| {
+        if open {
+            is_closed_door(x + dx, y + dy, &game.map)
+        } else {
+            is_open_door(x + dx, y + dy, &game.map)
+        }
+    };
+    let doors: Vec<(i32, i32)> = (-1..2)
+        .flat_map(|dx| (-1..2).map(move |dy| (dx, dy)))
+        .filter(|&(dx, dy)| is_door(dx, dy))
+        .collect();
+    match doors.len() {
+        0 => {
+            let message = if open { tr!("door.none-closed") } else { tr!("door.none-open") };
+            game.messages.add(message, MessageCategory::Warning);
+            None
+        }
+        1 => Some(doors[0]),
+        _ => {
+            let (door_x, door_y) = pick_tile(tcod, game, objects, &tr!("door.which"), (x, y))?;
+            Some((door_x - x, door_y - y)).filter(|offset| doors.contains(offset))
+        }
+    }
+}
@@ -0,0 +1,814 @@
+//! Items and what happens when they are used.
+
+use std::cmp;
+
+use rand::{Rng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{Sound, SoundEffect};
+use crate::combat::weapon_dice;
+use crate::config::config;
+use crate::equipment::{Equipment, Slot, cast_remove_curse, toggle_equipment};
+use crate::game::{Game, Glimpse};
+use crate::identify::{Identification, POTIONS, SCROLLS};
+use crate::light::{
+    LANTERN_BONUS, LANTERN_FUEL, OIL_FUEL, TORCH_FUEL, is_light, refill_lantern, toggle_light,
+};
+use crate::locale::display_name;
+use crate::locks::use_key;
+use crate::map::random_free_tile;
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+use crate::quest::ARTIFACT_NAME;
+use crate::ranged::{
+    BOW_DAMAGE, BOW_DICE, BOW_RANGE, SLING_DAMAGE, SLING_DICE, SLING_RANGE, ready_ammo,
+};
+use crate::render::Animation;
+use crate::status::{StatusEffect, StatusKind};
+use crate::tcod_types::colors::*;
+use crate::throw::{DAGGER_DAMAGE, THROW_RANGE};
+use crate::ui::{INVENTORY_WIDTH, Tcod, menu, pick_tile, target_monster};
+use crate::wands::{DIG_RANGE, SLOW_RANGE, SLOW_TURNS, WandKind, zap_wand};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Item {
+    Heal,
+    Lightning,
+    Confuse,
+    Artifact,
+    Teleport,
+    Telepathy,
+    Clairvoyance,
+    Fire,
+    Regeneration,
+    /// Only good for throwing.
+    Dagger,
+    Identify,
+    Ration,
+    Wand(WandKind),
+    Sword,
+    Shield,
+    RemoveCurse,
+    /// Opens the locked doors of a vault.
+    Key,
+    /// Digs through rock when walked into, held in the hand.
+    Pickaxe,
+    /// Lights the way until it burns out.
+    Torch,
+    /// Lights the way further than a torch, and can be refilled.
+    Lantern,
+    /// Refills a lantern.
+    Oil,
+    /// Shoots arrows, held in the hand.
+    Bow,
+    /// Shoots stones, held in the hand.
+    Sling,
+    Arrow,
+    SlingStone,
+}
+
+/// Slots in the inventory; each holds one item, or a stack of alike ones.
+pub const INVENTORY_SLOTS: usize = 26;
+
+impl Item {
+    /// Whether several of the item share one inventory slot. Wands each
+    /// keep count of their own charges, and equipment may each be cursed or
+    /// not, so they never do.
+    pub fn stackable(self) -> bool {
+        match self {
+            Item::Artifact | Item::Wand(_) | Item::Sword | Item::Shield | Item::Pickaxe => false,
+            Item::Bow | Item::Sling => false,
+            // Each light burns down on its own.
+            Item::Torch | Item::Lantern => false,
+            _ => true,
+        }
+    }
+
+    pub fn category(self) -> ItemCategory {
+        match self {
+            Item::Sword | Item::Dagger | Item::Pickaxe => ItemCategory::Weapon,
+            Item::Bow | Item::Sling | Item::Arrow | Item::SlingStone => ItemCategory::Weapon,
+            Item::Shield => ItemCategory::Armor,
+            item if POTIONS.contains(&item) => ItemCategory::Potion,
+            item if SCROLLS.contains(&item) => ItemCategory::Scroll,
+            _ => ItemCategory::Misc,
+        }
+    }
+
+    /// What the item is and does, in numbers where it has them.
+    pub fn description(self) -> String {
+        let gameplay = &config().gameplay;
+        match self {
+            Item::Heal => tr!("description.heal", gameplay.heal_amount),
+            Item::Lightning => tr!(
+                "description.lightning",
+                gameplay.lightning_damage,
+                gameplay.lightning_range
+            ),
+            Item::Confuse => tr!(
+                "description.confuse",
+                gameplay.confuse_range,
+                gameplay.confuse_num_turns
+            ),
+            Item::Artifact => tr!("description.artifact"),
+            Item::Teleport => tr!("description.teleport"),
+            Item::Telepathy => tr!("description.telepathy", gameplay.telepathy_turns),
+            Item::Clairvoyance => tr!("description.clairvoyance", gameplay.clairvoyance_radius),
+            Item::Fire => tr!(
+                "description.fire",
+                gameplay.fire_range,
+                gameplay.fire_damage,
+                gameplay.fire_turns
+            ),
+            Item::Regeneration => tr!(
+                "description.regeneration",
+                gameplay.regeneration_amount,
+                gameplay.regeneration_turns
+            ),
+            Item::Dagger => tr!("description.dagger", DAGGER_DAMAGE, THROW_RANGE),
+            Item::Identify => tr!("description.identify"),
+            Item::Ration => tr!("description.ration", gameplay.ration_nutrition),
+            Item::Wand(WandKind::Lightning) => tr!(
+                "description.wand-lightning",
+                gameplay.lightning_damage,
+                gameplay.lightning_range
+            ),
+            Item::Wand(WandKind::Digging) => tr!("description.wand-digging", DIG_RANGE),
+            Item::Wand(WandKind::Slow) => tr!("description.wand-slow", SLOW_RANGE, SLOW_TURNS),
+            Item::Sword => tr!("description.sword"),
+            Item::Shield => tr!("description.shield"),
+            Item::RemoveCurse => tr!("description.remove-curse"),
+            Item::Key => tr!("description.key"),
+            Item::Pickaxe => tr!("description.pickaxe"),
+            Item::Torch => tr!("description.torch", config().fov.torch_radius),
+            Item::Lantern => tr!("description.lantern", config().fov.torch_radius + LANTERN_BONUS),
+            Item::Oil => tr!("description.oil", OIL_FUEL),
+            Item::Bow => tr!("description.bow", BOW_RANGE, BOW_DAMAGE, BOW_DICE.spread()),
+            Item::Sling => {
+                tr!("description.sling", SLING_RANGE, SLING_DAMAGE, SLING_DICE.spread())
+            }
+            Item::Arrow => tr!("description.arrow"),
+            Item::SlingStone => tr!("description.sling-stone"),
+        }
+    }
+}
+
+/// The groups the inventory is shown in, in the order they are shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ItemCategory {
+    Weapon,
+    Armor,
+    Potion,
+    Scroll,
+    Misc,
+}
+
+impl ItemCategory {
+    pub fn name(self) -> String {
+        match self {
+            ItemCategory::Weapon => tr!("category.weapons"),
+            ItemCategory::Armor => tr!("category.armor"),
+            ItemCategory::Potion => tr!("category.potions"),
+            ItemCategory::Scroll => tr!("category.scrolls"),
+            ItemCategory::Misc => tr!("category.misc"),
+        }
+    }
+}
+
+pub enum UseResult {
+    UsedUp,
+    /// Used, but there is more left in it, like a wand with charges.
+    UsedAndKept,
+    Cancelled,
+}
+
+pub fn cast_heal(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    // Heal the player.
+    if let Some(fighter) = objects[PLAYER].fighter {
+        if fighter.hp == fighter.max_hp {
+            game.messages.add(tr!("full-health"), MessageCategory::Warning);
+            return UseResult::Cancelled
+        }
+        game.messages
+            .add(tr!("heal"), MessageCategory::Item);
+        objects[PLAYER].heal(config().gameplay.heal_amount);
+        return UseResult::UsedUp
+    }
+    UseResult::Cancelled
+}
+
+pub fn cast_lightning(
+    _inventory_id: usize,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult 
+{
+    // Let the player pick an enemy inside the maximum range, and damage it.
+    let range = config().gameplay.lightning_range;
+    if Object::closest_monster(tcod, objects, range).is_none() {
+        // NO enemy found within maximum range. 
+        game.messages
+            .add(tr!("no-enemy-in-range"), MessageCategory::Warning);
+        return UseResult::Cancelled;
+    }
+    let monster_id = match target_monster(tcod, game, objects, &tr!("lightning.target"), range) {
+        Some(monster_id) => monster_id,
+        None => return UseResult::Cancelled,
+    };
+    // Zap it! 
+    game.messages.add(
+        tr!("lightning", display_name(&objects[monster_id].name), config().gameplay.lightning_damage),
+        MessageCategory::Combat
+    );
+    game.sounds.push(Sound {
+        effect: SoundEffect::Thunder,
+        x: objects[monster_id].x,
+        y: objects[monster_id].y,
+    });
+    game.animations.push(Animation::Projectile {
+        from: objects[PLAYER].pos(),
+        to: objects[monster_id].pos(),
+        char: '*',
+        color: LIGHT_BLUE,
+    });
+    game.animations.push(Animation::Explosion {
+        x: objects[monster_id].x,
+        y: objects[monster_id].y,
+        radius: 1,
+        color: LIGHT_BLUE,
+    });
+    objects[monster_id].take_damage(config().gameplay.lightning_damage, game);
+    UseResult::UsedUp
+}
+
+pub fn cast_confuse(
+    _inventory_id: usize,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult 
+{
+    // Let the player pick an enemy in range, and confuse it. 
+    let range = config().gameplay.confuse_range;
+    if Object::closest_monster(tcod, objects, range).is_none() {
+        // No enemy found within maximum range. 
+        game.messages
+            .add(tr!("no-enemy-in-range"), MessageCategory::Warning);
+        return UseResult::Cancelled;
+    }
+    let monster_id = match target_monster(tcod, game, objects, &tr!("confuse.target"), range) {
+        Some(monster_id) => monster_id,
+        None => return UseResult::Cancelled,
+    };
+    objects[monster_id].wake_up();
+    objects[monster_id].add_effect(StatusEffect {
+        kind: StatusKind::Confusion,
+        turns: config().gameplay.confuse_num_turns,
+        strength: 0,
+    });
+    game.messages.add(
+        tr!("confuse", display_name(&objects[monster_id].name)),
+        MessageCategory::Item
+    );
+    UseResult::UsedUp
+}
+
+pub fn cast_fire(
+    _inventory_id: usize,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    // Let the player pick an enemy in range, and set it alight.
+    let range = config().gameplay.fire_range;
+    if Object::closest_monster(tcod, objects, range).is_none() {
+        game.messages
+            .add(tr!("no-enemy-in-range"), MessageCategory::Warning);
+        return UseResult::Cancelled;
+    }
+    let monster_id = match target_monster(tcod, game, objects, &tr!("fire.target"), range) {
+        Some(monster_id) => monster_id,
+        None => return UseResult::Cancelled,
+    };
+    game.animations.push(Animation::Projectile {
+        from: objects[PLAYER].pos(),
+        to: objects[monster_id].pos(),
+        char: '*',
+        color: ORANGE,
+    });
+    game.messages.add(
+        tr!("fire", display_name(&objects[monster_id].name)),
+        MessageCategory::Combat
+    );
+    objects[monster_id].add_effect(StatusEffect {
+        kind: StatusKind::Burning,
+        turns: config().gameplay.fire_turns,
+        strength: config().gameplay.fire_damage,
+    });
+    UseResult::UsedUp
+}
+
+pub fn cast_regeneration(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    // Heal a little every turn for a while.
+    objects[PLAYER].add_effect(StatusEffect {
+        kind: StatusKind::Regeneration,
+        turns: config().gameplay.regeneration_turns,
+        strength: config().gameplay.regeneration_amount,
+    });
+    game.messages.add(tr!("regeneration"), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+pub fn cast_teleport(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    // Send the player to a random spot on the level.
+    let (x, y) = random_free_tile(&game.map, objects, &mut game.rng);
+    objects[PLAYER].set_pos(x, y);
+    game.messages.add(tr!("teleport"), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+pub fn cast_telepathy(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    _objects: &mut [Object],
+) -> UseResult
+{
+    // Sense every monster on the level for a while.
+    game.telepathy = config().gameplay.telepathy_turns;
+    game.messages.add(tr!("telepathy"), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+pub fn cast_clairvoyance(
+    _inventory_id: usize,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    // Look in on a faraway spot: its layout, and whatever is there right now.
+    let start = objects[PLAYER].pos();
+    let (tx, ty) = match pick_tile(tcod, game, objects, &tr!("clairvoyance.target"), start) {
+        Some(tile) => tile,
+        None => return UseResult::Cancelled,
+    };
+    let radius = config().gameplay.clairvoyance_radius;
+    let in_range = |x: i32, y: i32| (x - tx).pow(2) + (y - ty).pow(2) <= radius.pow(2);
+    for x in cmp::max(tx - radius, 0)..cmp::min(tx + radius + 1, config().map.width) {
+        for y in cmp::max(ty - radius, 0)..cmp::min(ty + radius + 1, config().map.height) {
+            if in_range(x, y) {
+                // Nothing is hidden from it, not even secret doors.
+                let tile = &mut game.map[x as usize][y as usize];
+                tile.explored = true;
+                tile.secret = false;
+            }
+        }
+    }
+    for object in objects.iter().skip(PLAYER + 1).filter(|o| in_range(o.x, o.y)) {
+        game.glimpses.push(Glimpse::of(object));
+    }
+    game.messages.add(tr!("clairvoyance"), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+pub fn admire_artifact(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    _objects: &mut [Object],
+) -> UseResult
+{
+    // The artifact is the goal of the game, not a consumable.
+    game.messages.add(tr!("artifact-use"), MessageCategory::Lore);
+    UseResult::Cancelled
+}
+
+pub fn cast_identify(
+    inventory_id: usize,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    _objects: &mut [Object],
+) -> UseResult
+{
+    // Learn what one of the other unknown items in the pack is.
+    let unknown: Vec<(usize, Item)> = game
+        .inventory
+        .iter()
+        .enumerate()
+        .filter_map(|(id, object)| object.item.map(|item| (id, item)))
+        .filter(|&(id, item)| id != inventory_id && !game.identification.is_known(item))
+        .collect();
+    if unknown.is_empty() {
+        game.messages.add(tr!("identify.nothing"), MessageCategory::Warning);
+        return UseResult::Cancelled;
+    }
+    let names: Vec<String> = unknown
+        .iter()
+        .map(|&(id, _)| stack_name(&game.inventory[id], &game.identification))
+        .collect();
+    let (id, item) = match menu(&tr!("identify.header"), &names, INVENTORY_WIDTH, &mut *tcod.ui) {
+        Some(index) => unknown[index],
+        None => return UseResult::Cancelled,
+    };
+    let look = game.identification.name_of(&game.inventory[id]);
+    game.identification.learn(item);
+    let name = display_name(&game.inventory[id].name);
+    game.messages.add(tr!("identify.learned", look, name), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+pub fn eat_ration(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    _objects: &mut [Object],
+) -> UseResult
+{
+    let max = config().gameplay.max_nutrition;
+    if game.nutrition >= max {
+        game.messages.add(tr!("hunger.full"), MessageCategory::Warning);
+        return UseResult::Cancelled;
+    }
+    game.nutrition = cmp::min(game.nutrition + config().gameplay.ration_nutrition, max);
+    game.messages.add(tr!("hunger.eat"), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+pub fn ready_dagger(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    _objects: &mut [Object],
+) -> UseResult
+{
+    // Daggers are thrown, not used.
+    game.messages.add(tr!("dagger-use"), MessageCategory::Item);
+    UseResult::Cancelled
+}
+
+pub fn use_item(inventory_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
+    use Item::*;
+    // Just call the "use_function" if it is defined. 
+    let price = game.inventory[inventory_id].price;
+    if let Some(item) = game.inventory[inventory_id].item {
+        let on_use = match item {
+            Heal => cast_heal,
+            Lightning => cast_lightning,
+            Confuse => cast_confuse,
+            Artifact => admire_artifact,
+            Teleport => cast_teleport,
+            Telepathy => cast_telepathy,
+            Clairvoyance => cast_clairvoyance,
+            Fire => cast_fire,
+            Regeneration => cast_regeneration,
+            Dagger => ready_dagger,
+            Identify => cast_identify,
+            Ration => eat_ration,
+            Wand(_) => zap_wand,
+            Sword | Shield | Pickaxe | Bow | Sling => toggle_equipment,
+            RemoveCurse => cast_remove_curse,
+            Key => use_key,
+            Torch | Lantern => toggle_light,
+            Oil => refill_lantern,
+            Arrow | SlingStone => ready_ammo,
+        };
+        let look = game.identification.name_of(&game.inventory[inventory_id]);
+        match on_use(inventory_id, tcod, game, objects) {
+            UseResult::UsedUp => {
+                // Destroy after use, unless it was cancelled for some reason. 
+                take_from_inventory(&mut game.inventory, inventory_id);
+            }
+            UseResult::UsedAndKept => {
+                // Whatever is left of it is the player's now.
+                game.inventory[inventory_id].price = None;
+            }
+            UseResult::Cancelled => {
+                game.messages.add(tr!("cancelled"), MessageCategory::Warning);
+                return;
+            }
+        }
+        // Using an item shows what it was.
+        if game.identification.learn(item) {
+            let name = display_name(&create_item(item, 0, 0).name);
+            game.messages.add(tr!("identify.learned", look, name), MessageCategory::Item);
+        }
+        // You use it, you bought it.
+        if let (Some(price), Some(shop)) = (price, game.shop.as_mut()) {
+            shop.debt += price;
+            game.messages.add(tr!("shop.used-unpaid", price), MessageCategory::Item);
+        }
+    }
+}
+
+/// The name an item goes by, with how many there are if it is a stack.
+pub fn stack_name(item: &Object, identification: &Identification) -> String {
+    let mut name = identification.name_of(item);
+    if let Some(equipment) = item.equipment {
+        if equipment.cursed && equipment.curse_known {
+            name = tr!("equipment.cursed-tag", name);
+        }
+        if equipment.equipped {
+            name = match equipment.slot {
+                Slot::Hand => tr!("equipment.wielded-tag", name),
+                Slot::OffHand => tr!("equipment.worn-tag", name),
+                Slot::Light => tr!("equipment.lit-tag", name),
+            };
+        }
+    }
+    if let Some(Item::Wand(_)) = item.item {
+        tr!("wand.charges", name, item.charges)
+    } else if item.item.map_or(false, is_light) {
+        tr!("light.fuel", name, item.charges)
+    } else if item.count > 1 {
+        tr!("item.stack", name, item.count)
+    } else {
+        name
+    }
+}
+
+/// The slot an item would join in the inventory: a stack of the same kind
+/// and price, if there is one.
+fn stack_for(inventory: &[Object], item: &Object) -> Option<usize> {
+    if !item.item.map_or(false, Item::stackable) {
+        return None;
+    }
+    inventory
+        .iter()
+        .position(|other| other.item == item.item && other.price == item.price)
+}
+
+/// Whether there is room in the inventory for the item.
+pub fn has_room_for(inventory: &[Object], item: &Object) -> bool {
+    stack_for(inventory, item).is_some() || inventory.len() < INVENTORY_SLOTS
+}
+
+/// Put an item in the inventory, on a stack of alike ones if there is one.
+/// The item is handed back, boxed, if there is no room for it.
+pub fn add_to_inventory(inventory: &mut Vec<Object>, item: Object) -> Result<(), Box<Object>> {
+    match stack_for(inventory, &item) {
+        Some(index) => {
+            inventory[index].count += item.count;
+            Ok(())
+        }
+        None if inventory.len() < INVENTORY_SLOTS => {
+            inventory.push(item);
+            Ok(())
+        }
+        None => Err(Box::new(item)),
+    }
+}
+
+/// Take a single item out of the given slot, which is emptied once its
+/// stack runs out.
+pub fn take_from_inventory(inventory: &mut Vec<Object>, index: usize) -> Object {
+    match inventory[index].item {
+        Some(item) if inventory[index].count > 1 => {
+            let stack = &mut inventory[index];
+            stack.count -= 1;
+            let mut single = create_item(item, stack.x, stack.y);
+            single.price = stack.price;
+            single
+        }
+        _ => inventory.remove(index),
+    }
+}
+
+/// Merge stacks that have become alike, as when their goods are paid for.
+pub fn restack(inventory: &mut Vec<Object>) {
+    for item in std::mem::take(inventory) {
+        // Each stack had a slot before, so there is room for it now.
+        let _ = add_to_inventory(inventory, item);
+    }
+}
+
+/// Pick a kind of item from the spawn table.
+pub fn random_item(unlocked_items: &[Item], rng: &mut StdRng) -> Item {
+    let dice = rng.next_f32();
+    if dice < 0.23 {
+        // Create a healing potion. (23% chance)
+        Item::Heal
+    } else if dice < 0.23 + 0.02 {
+        // Create a pickaxe (2% chance)
+        Item::Pickaxe
+    } else if dice < 0.25 + 0.02 {
+        // Create a torch (2% chance)
+        Item::Torch
+    } else if dice < 0.27 + 0.01 {
+        // Create a lantern (1% chance)
+        Item::Lantern
+    } else if dice < 0.28 + 0.02 {
+        // Create a flask of oil (2% chance)
+        Item::Oil
+    } else if dice < 0.3 + 0.01 {
+        // Create a bow (1% chance)
+        Item::Bow
+    } else if dice < 0.31 + 0.01 {
+        // Create a sling (1% chance)
+        Item::Sling
+    } else if dice < 0.32 + 0.01 {
+        // Create a bundle of arrows (1% chance)
+        Item::Arrow
+    } else if dice < 0.33 + 0.01 {
+        // Create a bundle of sling stones (1% chance)
+        Item::SlingStone
+    } else if dice < 0.34 + 0.02 {
+        // Create a sword (2% chance)
+        Item::Sword
+    } else if dice < 0.36 + 0.02 {
+        // Create a shield (2% chance)
+        Item::Shield
+    } else if dice < 0.38 + 0.01 {
+        // Create a scroll of remove curse (1% chance)
+        Item::RemoveCurse
+    } else if dice < 0.39 + 0.06 {
+        // Create a food ration (6% chance)
+        Item::Ration
+    } else if dice < 0.45 + 0.05 {
+        // Create a wand of any kind (5% chance)
+        Item::Wand(*rng.choose(&WandKind::ALL).unwrap())
+    } else if dice < 0.5 + 0.05 {
+        // Create an identify scroll (5% chance)
+        Item::Identify
+    } else if dice < 0.55 + 0.1 {
+        // Create a lightning bolt scroll (10% chance)
+        Item::Lightning
+    } else if dice < 0.55 + 0.1 + 0.05 {
+        // Create a telepathy potion (5% chance)
+        Item::Telepathy
+    } else if dice < 0.55 + 0.1 + 0.05 + 0.05 {
+        // Create a clairvoyance scroll (5% chance)
+        Item::Clairvoyance
+    } else if dice < 0.55 + 0.1 + 0.05 + 0.05 + 0.05 {
+        // Create a fire scroll (5% chance)
+        Item::Fire
+    } else if dice < 0.55 + 0.1 + 0.05 + 0.05 + 0.05 + 0.05 {
+        // Create a regeneration potion (5% chance)
+        Item::Regeneration
+    } else if dice < 0.55 + 0.1 + 0.05 + 0.05 + 0.05 + 0.05 + 0.05 {
+        // Create a dagger, for throwing (5% chance)
+        Item::Dagger
+    } else if dice < 0.55 + 0.1 + 0.05 + 0.05 + 0.05 + 0.05 + 0.05 + 0.05
+        && unlocked_items.contains(&Item::Teleport)
+    {
+        // Create a teleportation scroll, once it is unlocked (5% chance)
+        Item::Teleport
+    } else {
+        // Create a confuse scroll (10% chance)
+        Item::Confuse
+    }
+}
+
+/// What a shop charges for an item.
+pub fn item_price(item: Item) -> u32 {
+    match item {
+        Item::Heal => 20,
+        Item::Lightning => 60,
+        Item::Confuse => 40,
+        Item::Artifact => 1000,
+        Item::Teleport => 50,
+        Item::Telepathy => 40,
+        Item::Clairvoyance => 50,
+        Item::Fire => 50,
+        Item::Regeneration => 40,
+        Item::Dagger => 15,
+        Item::Identify => 30,
+        Item::Ration => 10,
+        Item::Wand(WandKind::Lightning) => 90,
+        Item::Wand(WandKind::Digging) => 60,
+        Item::Wand(WandKind::Slow) => 50,
+        Item::Sword => 80,
+        Item::Shield => 60,
+        Item::RemoveCurse => 50,
+        Item::Key => 30,
+        Item::Pickaxe => 70,
+        Item::Torch => 10,
+        Item::Lantern => 60,
+        Item::Oil => 15,
+        Item::Bow => 70,
+        Item::Sling => 30,
+        Item::Arrow => 3,
+        Item::SlingStone => 1,
+    }
+}
+
+/// Everything the player knows about an item: what it is and does, unless
+/// they have yet to identify it, and how it stands.
+pub fn describe_item(item: &Object, identification: &Identification) -> String {
+    let mut lines = vec![stack_name(item, identification), String::new()];
+    match item.item {
+        Some(kind) if identification.is_known(kind) => lines.push(kind.description()),
+        Some(_) => lines.push(tr!("description.unknown")),
+        None => {}
+    }
+    if let Some(Item::Wand(_)) = item.item {
+        lines.push(tr!("description.charges", item.charges));
+    }
+    if item.item.map_or(false, is_light) {
+        lines.push(tr!("description.fuel", item.charges));
+    }
+    if let Some(equipment) = item.equipment.filter(|e| e.slot != Slot::Light) {
+        if !equipment.curse_known {
+            lines.push(tr!("description.untried"));
+        } else {
+            let power = format!("{:+}", equipment.power_bonus);
+            let defense = format!("{:+}", equipment.defense_bonus);
+            lines.push(tr!("description.bonuses", power, defense));
+            if equipment.cursed {
+                lines.push(tr!("description.cursed"));
+            }
+        }
+    }
+    if let Some(dice) = item.item.and_then(weapon_dice) {
+        lines.push(tr!("description.dice", dice, dice.spread()));
+    }
+    lines.join("\n")
+}
+
+/// Create the map object for the given kind of item.
+pub fn create_item(item: Item, x: i32, y: i32) -> Object {
+    let mut object = match item {
+        Item::Heal => Object::new(x, y, '!', "healing potion", VIOLET, false),
+        Item::Lightning => Object::new(x, y, '#', "scroll of lightning bolt", LIGHT_YELLOW, false),
+        Item::Confuse => Object::new(x, y, '#', "scroll of confusion", LIGHT_YELLOW, false),
+        Item::Artifact => Object::new(x, y, '*', ARTIFACT_NAME, GOLD, false),
+        Item::Teleport => Object::new(x, y, '#', "scroll of teleportation", LIGHT_VIOLET, false),
+        Item::Telepathy => Object::new(x, y, '!', "potion of telepathy", LIGHT_AZURE, false),
+        Item::Clairvoyance => Object::new(x, y, '#', "scroll of clairvoyance", LIGHT_CYAN, false),
+        Item::Fire => Object::new(x, y, '#', "scroll of fire", ORANGE, false),
+        Item::Regeneration => Object::new(x, y, '!', "potion of regeneration", LIGHT_GREEN, false),
+        Item::Dagger => Object::new(x, y, ')', "dagger", LIGHT_GREY, false),
+        Item::Identify => Object::new(x, y, '#', "scroll of identify", WHITE, false),
+        Item::Ration => Object::new(x, y, '%', "food ration", LIGHT_SEPIA, false),
+        Item::Wand(kind) => {
+            let mut wand = Object::new(x, y, '/', kind.name(), kind.color(), false);
+            wand.charges = kind.charges();
+            wand
+        }
+        Item::Sword => {
+            let mut sword = Object::new(x, y, ')', "sword", SKY, false);
+            sword.equipment = Some(Equipment::new(Slot::Hand, 3, 0));
+            sword
+        }
+        Item::Shield => {
+            let mut shield = Object::new(x, y, '[', "shield", DARKER_ORANGE, false);
+            shield.equipment = Some(Equipment::new(Slot::OffHand, 0, 1));
+            shield
+        }
+        Item::RemoveCurse => Object::new(x, y, '#', "scroll of remove curse", LIGHT_VIOLET, false),
+        Item::Key => Object::new(x, y, '-', "vault key", GOLD, false),
+        Item::Pickaxe => {
+            let mut pickaxe = Object::new(x, y, '(', "pickaxe", LIGHT_SEPIA, false);
+            pickaxe.equipment = Some(Equipment::new(Slot::Hand, 1, 0));
+            pickaxe
+        }
+        Item::Torch => {
+            let mut torch = Object::new(x, y, '(', "torch", ORANGE, false);
+            torch.equipment = Some(Equipment::new(Slot::Light, 0, 0));
+            torch.charges = TORCH_FUEL;
+            torch
+        }
+        Item::Lantern => {
+            let mut lantern = Object::new(x, y, '(', "lantern", YELLOW, false);
+            lantern.equipment = Some(Equipment::new(Slot::Light, 0, 0));
+            lantern.charges = LANTERN_FUEL;
+            lantern
+        }
+        Item::Oil => Object::new(x, y, '!', "flask of oil", DARK_AMBER, false),
+        Item::Bow => {
+            let mut bow = Object::new(x, y, '}', "bow", SEPIA, false);
+            bow.equipment = Some(Equipment::new(Slot::Hand, 0, 0));
+            bow
+        }
+        Item::Sling => {
+            let mut sling = Object::new(x, y, '}', "sling", DESATURATED_ORANGE, false);
+            sling.equipment = Some(Equipment::new(Slot::Hand, 0, 0));
+            sling
+        }
+        Item::Arrow => Object::new(x, y, '|', "arrow", LIGHT_SEPIA, false),
+        Item::SlingStone => Object::new(x, y, '`', "sling stone", GREY, false),
+    };
+    object.item = Some(item);
+    object
+}
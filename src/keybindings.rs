@@ -0,0 +1,316 @@
+//! Key bindings read from `keybindings.toml`, which the player edits by hand.
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::save::LOAD_ERRORS;
+use crate::tcod_types::input::{Key, KeyCode};
+
+pub const KEYBINDINGS_FILE: &str = "keybindings.toml";
+
+/// Something the player can bind keys to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Move(i32, i32),
+    /// Let a turn go by without doing anything.
+    Wait,
+    /// Wait until healed, or until something comes up.
+    Rest,
+    /// Search the tiles around for anything hidden.
+    Search,
+    /// Make a noise that carries far.
+    Shout,
+    PickUp,
+    Descend,
+    OpenDoor,
+    CloseDoor,
+    /// Move a cursor about to see what is where.
+    Look,
+    Inventory,
+    Throw,
+    /// Shoot the ranged weapon held.
+    Fire,
+    /// Do whatever the spot calls for: take the stairs, or pick up what lies there.
+    Context,
+    Bestiary,
+    QuestLog,
+    MessageHistory,
+    Journal,
+    Options,
+    Save,
+    ExportMap,
+    ScrollBack,
+    ScrollForward,
+    /// With Shift, the screen is also dumped as text.
+    Screenshot,
+    Help,
+    Quit,
+}
+
+impl Command {
+    /// What the command does, as listed on the help screen.
+    pub fn description(self) -> String {
+        match self {
+            Command::Move(0, -1) => tr!("help.move-n"),
+            Command::Move(0, 1) => tr!("help.move-s"),
+            Command::Move(-1, 0) => tr!("help.move-w"),
+            Command::Move(1, 0) => tr!("help.move-e"),
+            Command::Move(-1, -1) => tr!("help.move-nw"),
+            Command::Move(1, -1) => tr!("help.move-ne"),
+            Command::Move(-1, 1) => tr!("help.move-sw"),
+            Command::Move(..) => tr!("help.move-se"),
+            Command::Wait => tr!("help.wait"),
+            Command::Rest => tr!("help.rest"),
+            Command::Search => tr!("help.search"),
+            Command::Shout => tr!("help.shout"),
+            Command::PickUp => tr!("help.pickup"),
+            Command::Descend => tr!("help.descend"),
+            Command::OpenDoor => tr!("help.open-door"),
+            Command::CloseDoor => tr!("help.close-door"),
+            Command::Look => tr!("help.look"),
+            Command::Inventory => tr!("help.inventory"),
+            Command::Throw => tr!("help.throw"),
+            Command::Fire => tr!("help.fire"),
+            Command::Context => tr!("help.context"),
+            Command::Bestiary => tr!("help.bestiary"),
+            Command::QuestLog => tr!("help.quest-log"),
+            Command::MessageHistory => tr!("help.message-history"),
+            Command::Journal => tr!("help.journal"),
+            Command::Options => tr!("help.options"),
+            Command::Save => tr!("help.save"),
+            Command::ExportMap => tr!("help.export-map"),
+            Command::ScrollBack => tr!("help.scroll-back"),
+            Command::ScrollForward => tr!("help.scroll-forward"),
+            Command::Screenshot => tr!("help.screenshot"),
+            Command::Help => tr!("help.help"),
+            Command::Quit => tr!("help.quit"),
+        }
+    }
+}
+
+/// The keys bound to each command. A key is either a single character, such
+/// as `"g"` or `">"`, or the name of a special key, such as `"Up"`,
+/// `"NumPad8"`, `"Enter"` or `"F12"`. The file is written with the defaults
+/// on first run, and any command left out of it keeps its default keys.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub move_n: Vec<String>,
+    pub move_s: Vec<String>,
+    pub move_w: Vec<String>,
+    pub move_e: Vec<String>,
+    pub move_nw: Vec<String>,
+    pub move_ne: Vec<String>,
+    pub move_sw: Vec<String>,
+    pub move_se: Vec<String>,
+    pub wait: Vec<String>,
+    pub rest: Vec<String>,
+    pub search: Vec<String>,
+    pub shout: Vec<String>,
+    pub pickup: Vec<String>,
+    pub descend: Vec<String>,
+    pub open_door: Vec<String>,
+    pub close_door: Vec<String>,
+    pub look: Vec<String>,
+    pub inventory: Vec<String>,
+    pub throw: Vec<String>,
+    pub fire: Vec<String>,
+    pub context: Vec<String>,
+    pub bestiary: Vec<String>,
+    pub quest_log: Vec<String>,
+    pub message_history: Vec<String>,
+    pub journal: Vec<String>,
+    pub options: Vec<String>,
+    pub save: Vec<String>,
+    pub export_map: Vec<String>,
+    pub scroll_back: Vec<String>,
+    pub scroll_forward: Vec<String>,
+    pub screenshot: Vec<String>,
+    pub help: Vec<String>,
+    pub quit: Vec<String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = |names: &[&str]| names.iter().map(|&name| name.to_string()).collect();
+        KeyBindings {
+            move_n: keys(&["Up", "k", "NumPad8"]),
+            move_s: keys(&["Down", "j", "NumPad2"]),
+            move_w: keys(&["Left", "h", "NumPad4"]),
+            move_e: keys(&["Right", "l", "NumPad6"]),
+            move_nw: keys(&["y", "NumPad7"]),
+            move_ne: keys(&["u", "NumPad9"]),
+            move_sw: keys(&["b", "NumPad1"]),
+            move_se: keys(&["n", "NumPad3"]),
+            wait: keys(&[".", "NumPad5"]),
+            rest: keys(&["R"]),
+            search: keys(&["s"]),
+            shout: keys(&["!"]),
+            pickup: keys(&["g"]),
+            descend: keys(&[">"]),
+            open_door: keys(&["o"]),
+            close_door: keys(&["c"]),
+            look: keys(&[";", "x"]),
+            inventory: keys(&["i", "Tab"]),
+            throw: keys(&["t"]),
+            fire: keys(&["f"]),
+            context: keys(&["Enter"]),
+            bestiary: keys(&["B"]),
+            quest_log: keys(&["Q"]),
+            message_history: keys(&["m"]),
+            journal: keys(&["J"]),
+            options: keys(&["O"]),
+            save: keys(&["S"]),
+            export_map: keys(&["X"]),
+            scroll_back: keys(&["PageUp"]),
+            scroll_forward: keys(&["PageDown"]),
+            screenshot: keys(&["F12"]),
+            help: keys(&["?"]),
+            quit: keys(&["Escape"]),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Read `keybindings.toml`, creating it if it is missing. A file that
+    /// cannot be parsed is reported, and the defaults are used instead. Key
+    /// names that are not known are reported too.
+    pub fn load() -> Self {
+        let bindings = match fs::read_to_string(KEYBINDINGS_FILE) {
+            Ok(text) => toml::from_str(&text).unwrap_or_else(|e| {
+                let error = tr!("config.invalid", KEYBINDINGS_FILE, e);
+                LOAD_ERRORS.with(|errors| errors.borrow_mut().push(error));
+                KeyBindings::default()
+            }),
+            Err(_) => {
+                let bindings = KeyBindings::default();
+                if let Ok(text) = toml::to_string(&bindings) {
+                    let _ = fs::write(KEYBINDINGS_FILE, text);
+                }
+                bindings
+            }
+        };
+        let unknown: Vec<&String> = bindings
+            .table()
+            .iter()
+            .flat_map(|&(_, names)| names.iter())
+            .filter(|name| key_code(name).is_none() && name.chars().count() != 1)
+            .collect();
+        if !unknown.is_empty() {
+            let names: Vec<&str> = unknown.iter().map(|name| name.as_str()).collect();
+            let error = tr!("keybindings.unknown", KEYBINDINGS_FILE, names.join(", "));
+            LOAD_ERRORS.with(|errors| errors.borrow_mut().push(error));
+        }
+        bindings
+    }
+
+    /// Every command, with the keys bound to it.
+    pub fn table(&self) -> [(Command, &[String]); 33] {
+        [
+            (Command::Move(0, -1), &self.move_n),
+            (Command::Move(0, 1), &self.move_s),
+            (Command::Move(-1, 0), &self.move_w),
+            (Command::Move(1, 0), &self.move_e),
+            (Command::Move(-1, -1), &self.move_nw),
+            (Command::Move(1, -1), &self.move_ne),
+            (Command::Move(-1, 1), &self.move_sw),
+            (Command::Move(1, 1), &self.move_se),
+            (Command::Wait, &self.wait),
+            (Command::Rest, &self.rest),
+            (Command::Search, &self.search),
+            (Command::Shout, &self.shout),
+            (Command::PickUp, &self.pickup),
+            (Command::Descend, &self.descend),
+            (Command::OpenDoor, &self.open_door),
+            (Command::CloseDoor, &self.close_door),
+            (Command::Look, &self.look),
+            (Command::Inventory, &self.inventory),
+            (Command::Throw, &self.throw),
+            (Command::Fire, &self.fire),
+            (Command::Context, &self.context),
+            (Command::Bestiary, &self.bestiary),
+            (Command::QuestLog, &self.quest_log),
+            (Command::MessageHistory, &self.message_history),
+            (Command::Journal, &self.journal),
+            (Command::Options, &self.options),
+            (Command::Save, &self.save),
+            (Command::ExportMap, &self.export_map),
+            (Command::ScrollBack, &self.scroll_back),
+            (Command::ScrollForward, &self.scroll_forward),
+            (Command::Screenshot, &self.screenshot),
+            (Command::Help, &self.help),
+            (Command::Quit, &self.quit),
+        ]
+    }
+
+    /// The command bound to a key press, if any.
+    pub fn command(&self, key: Key) -> Option<Command> {
+        self.table()
+            .iter()
+            .find(|&&(_, names)| names.iter().any(|name| is_bound(name, key)))
+            .map(|&(command, _)| command)
+    }
+}
+
+/// Whether the key press is the key with the given name. Characters are
+/// matched against the character a press types, so `"S"` means Shift+S.
+pub fn is_bound(name: &str, key: Key) -> bool {
+    match key_code(name) {
+        Some(code) => key.code == code,
+        None => key.code == KeyCode::Char && name.chars().eq(Some(key.printable)),
+    }
+}
+
+/// The special key with the given name, as in `keybindings.toml`.
+pub fn key_code(name: &str) -> Option<KeyCode> {
+    use crate::tcod_types::input::KeyCode::*;
+    let code = match name {
+        "Escape" => Escape,
+        "Backspace" => Backspace,
+        "Tab" => Tab,
+        "Enter" => Enter,
+        "Spacebar" => Spacebar,
+        "PageUp" => PageUp,
+        "PageDown" => PageDown,
+        "End" => End,
+        "Home" => Home,
+        "Up" => Up,
+        "Down" => Down,
+        "Left" => Left,
+        "Right" => Right,
+        "Insert" => Insert,
+        "Delete" => Delete,
+        "NumPad0" => NumPad0,
+        "NumPad1" => NumPad1,
+        "NumPad2" => NumPad2,
+        "NumPad3" => NumPad3,
+        "NumPad4" => NumPad4,
+        "NumPad5" => NumPad5,
+        "NumPad6" => NumPad6,
+        "NumPad7" => NumPad7,
+        "NumPad8" => NumPad8,
+        "NumPad9" => NumPad9,
+        "F1" => F1,
+        "F2" => F2,
+        "F3" => F3,
+        "F4" => F4,
+        "F5" => F5,
+        "F6" => F6,
+        "F7" => F7,
+        "F8" => F8,
+        "F9" => F9,
+        "F10" => F10,
+        "F11" => F11,
+        "F12" => F12,
+        _ => return None,
+    };
+    Some(code)
+}
+
+pub static KEYBINDINGS: std::sync::OnceLock<KeyBindings> = std::sync::OnceLock::new();
+
+/// The key bindings from `keybindings.toml`, loaded the first time they are needed.
+pub fn keybindings() -> &'static KeyBindings {
+    KEYBINDINGS.get_or_init(KeyBindings::load)
+}
@@ -0,0 +1,57 @@
+//! Lava: molten rock in some rooms, mostly in the caves. It
+//! badly burns whoever stands in it, and glows bright enough to be seen from
+//! anywhere it is in sight, however dark it is.
+
+use rand::{Rng, StdRng};
+
+use crate::branch::Branch;
+use crate::fov::Fov;
+use crate::game::Game;
+use crate::locale::display_name;
+use crate::map::{Map, Rect, Terrain};
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+
+pub const LAVA_DAMAGE: i32 = 10; // Hit points lost for each turn spent in lava
+
+/// Sometimes fill a patch of the room with lava, though never its middle,
+/// where the player may start or the stairs may go.
+pub fn place_lava(branch: Branch, room: Rect, map: &mut Map, rng: &mut StdRng) {
+    if rng.next_f32() >= branch.lava_chance() {
+        return;
+    }
+    let (cx, cy) = (rng.gen_range(room.x1 + 1, room.x2), rng.gen_range(room.y1 + 1, room.y2));
+    let (mx, my) = room.center();
+    for x in cx - 1..cx + 2 {
+        for y in cy - 1..cy + 2 {
+            let near_middle = (x - mx).abs() <= 1 && (y - my).abs() <= 1;
+            if !room.contains(x, y) || near_middle {
+                continue;
+            }
+            let tile = &mut map[x as usize][y as usize];
+            if !tile.blocked {
+                tile.terrain = Terrain::Lava;
+            }
+        }
+    }
+}
+
+/// Burn everyone standing in lava.
+pub fn tick_lava(fov: &Fov, game: &mut Game, objects: &mut [Object]) {
+    for (id, object) in objects.iter_mut().enumerate() {
+        let burning = object.alive
+            && object.fighter.is_some()
+            && game.map[object.x as usize][object.y as usize].terrain == Terrain::Lava;
+        if !burning {
+            continue;
+        }
+        if id == PLAYER {
+            game.messages.add(tr!("lava.player-burns", LAVA_DAMAGE), MessageCategory::Warning);
+            game.last_attacker = Some(tr!("lava.name"));
+        } else if fov.is_in_fov(object.x, object.y) {
+            let name = display_name(&object.name);
+            game.messages.add(tr!("lava.burns", name), MessageCategory::Combat);
+        }
+        object.take_dungeon_damage(LAVA_DAMAGE, game);
+    }
+}
@@ -0,0 +1,82 @@
+//! The game itself: map generation, combat, monsters, items and the screens
+//! around them. The binary only opens a window and runs the main menu, so
+//! tests, benchmarks and tools can call into any of this directly.
+
+/// Translate the text with the given key, filling in its placeholders with
+/// the remaining arguments.
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::locale::translate($key, &[])
+    };
+    ($key:expr, $($arg:expr),+ $(,)*) => {
+        $crate::locale::translate($key, &[$(&$arg),+])
+    };
+}
+
+pub mod action;
+pub mod ai;
+pub mod arena;
+pub mod audio;
+pub mod backend;
+pub mod bestiary;
+pub mod bones;
+pub mod boss;
+pub mod bot;
+pub mod branch;
+pub mod combat;
+pub mod config;
+pub mod daily;
+pub mod digging;
+pub mod editor;
+pub mod equipment;
+pub mod faction;
+pub mod fov;
+pub mod game;
+pub mod gold;
+pub mod headless;
+pub mod hunger;
+pub mod identify;
+pub mod input;
+pub mod items;
+pub mod keybindings;
+pub mod lava;
+#[cfg(feature = "tcod")]
+pub mod libtcod;
+pub mod light;
+pub mod lighting;
+pub mod locale;
+pub mod locks;
+pub mod map;
+pub mod messages;
+pub mod noise;
+pub mod morgue;
+pub mod npc;
+pub mod object;
+pub mod observer;
+pub mod online;
+pub mod pack;
+pub mod pathfinding;
+pub mod profile;
+pub mod quest;
+pub mod ranged;
+pub mod render;
+pub mod respawn;
+pub mod save;
+pub mod scavenge;
+pub mod scores;
+pub mod settings;
+pub mod shop;
+pub mod sleep;
+pub mod status;
+pub mod summon;
+pub mod tcod_types;
+#[cfg(feature = "terminal")]
+pub mod terminal;
+pub mod throw;
+pub mod traps;
+pub mod tutorial;
+pub mod ui;
+pub mod vault;
+pub mod wands;
+pub mod water;
@@ -0,0 +1,304 @@
+//! A libtcod window, the backend the game is played in unless told
+//! otherwise. Only built with the `tcod` feature, which is on by default.
+
+use tcod::colors::*;
+use tcod::console::*;
+use tcod::input::{self, Event, EventFlags, Key, KeyCode};
+
+use crate::backend::{InputSource, Layer, Renderer};
+use crate::config::config;
+
+/// A libtcod window, with an off-screen console for each layer.
+pub struct TcodBackend {
+    root: Root,
+    map: Offscreen,
+    panel: Offscreen,
+    window: Offscreen,
+    backdrop: Offscreen,
+}
+
+/// Run the code with `$console` bound to the layer's console. Root and
+/// Offscreen are different types, so the code is repeated for each.
+macro_rules! on_layer {
+    ($backend:expr, $layer:expr, |$console:ident| $body:expr) => {
+        match $layer {
+            Layer::Screen => {
+                let $console = &mut $backend.root;
+                $body
+            }
+            Layer::Map => {
+                let $console = &mut $backend.map;
+                $body
+            }
+            Layer::Panel => {
+                let $console = &mut $backend.panel;
+                $body
+            }
+            Layer::Window => {
+                let $console = &mut $backend.window;
+                $body
+            }
+            Layer::Backdrop => {
+                let $console = &mut $backend.backdrop;
+                $body
+            }
+        }
+    };
+}
+
+/// `on_layer!`, for reading.
+macro_rules! on_layer_ref {
+    ($backend:expr, $layer:expr, |$console:ident| $body:expr) => {
+        match $layer {
+            Layer::Screen => {
+                let $console = &$backend.root;
+                $body
+            }
+            Layer::Map => {
+                let $console = &$backend.map;
+                $body
+            }
+            Layer::Panel => {
+                let $console = &$backend.panel;
+                $body
+            }
+            Layer::Window => {
+                let $console = &$backend.window;
+                $body
+            }
+            Layer::Backdrop => {
+                let $console = &$backend.backdrop;
+                $body
+            }
+        }
+    };
+}
+
+impl TcodBackend {
+    /// Open a window of the given size, in cells. The other layers start
+    /// out tiny, until they are resized.
+    pub fn new(width: i32, height: i32) -> Self {
+        tcod::system::set_fps(config().screen.fps);
+        TcodBackend {
+            root: init_root(width, height),
+            map: Offscreen::new(1, 1),
+            panel: Offscreen::new(1, 1),
+            window: Offscreen::new(1, 1),
+            backdrop: Offscreen::new(1, 1),
+        }
+    }
+}
+
+fn init_root(width: i32, height: i32) -> Root {
+    Root::initializer()
+        .font("arial10x10.png", FontLayout::Tcod)
+        .font_type(FontType::Greyscale)
+        .size(width, height)
+        .title("Rust/libtcod tutorial")
+        .init()
+}
+
+impl Renderer for TcodBackend {
+    fn size(&self, layer: Layer) -> (i32, i32) {
+        on_layer_ref!(self, layer, |console| (console.width(), console.height()))
+    }
+
+    fn resize(&mut self, layer: Layer, width: i32, height: i32) {
+        match layer {
+            // libtcod cannot resize its window in place, so the root console is created anew.
+            Layer::Screen => self.root = init_root(width, height),
+            Layer::Map => self.map = Offscreen::new(width, height),
+            Layer::Panel => self.panel = Offscreen::new(width, height),
+            Layer::Window => self.window = Offscreen::new(width, height),
+            Layer::Backdrop => self.backdrop = Offscreen::new(width, height),
+        }
+    }
+
+    fn set_default_foreground(&mut self, layer: Layer, color: Color) {
+        on_layer!(self, layer, |console| console.set_default_foreground(color))
+    }
+
+    fn set_default_background(&mut self, layer: Layer, color: Color) {
+        on_layer!(self, layer, |console| console.set_default_background(color))
+    }
+
+    fn clear(&mut self, layer: Layer) {
+        on_layer!(self, layer, |console| console.clear())
+    }
+
+    fn put_char(&mut self, layer: Layer, x: i32, y: i32, glyph: char, flag: BackgroundFlag) {
+        on_layer!(self, layer, |console| console.put_char(x, y, glyph, flag))
+    }
+
+    fn set_char_background(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        color: Color,
+        flag: BackgroundFlag,
+    ) {
+        on_layer!(self, layer, |console| console.set_char_background(x, y, color, flag))
+    }
+
+    fn rect(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        flag: BackgroundFlag,
+    ) {
+        on_layer!(self, layer, |console| console.rect(x, y, width, height, false, flag))
+    }
+
+    fn print_ex(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    ) {
+        on_layer!(self, layer, |console| console.print_ex(x, y, flag, alignment, text))
+    }
+
+    fn print_rect(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+    ) -> i32 {
+        on_layer!(self, layer, |console| console.print_rect(x, y, width, height, text))
+    }
+
+    fn get_height_rect(
+        &self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+    ) -> i32 {
+        on_layer_ref!(self, layer, |console| {
+            console.get_height_rect(x, y, width, height, text)
+        })
+    }
+
+    fn cell(&self, layer: Layer, x: i32, y: i32) -> (char, Color, Color) {
+        on_layer_ref!(self, layer, |console| (
+            console.get_char(x, y),
+            console.get_char_foreground(x, y),
+            console.get_char_background(x, y)
+        ))
+    }
+
+    fn blit(
+        &mut self,
+        from: Layer,
+        position: (i32, i32),
+        size: (i32, i32),
+        to: (i32, i32),
+        foreground_alpha: f32,
+        background_alpha: f32,
+    ) {
+        let root = &mut self.root;
+        let source = match from {
+            // Already there.
+            Layer::Screen => return,
+            Layer::Map => &self.map,
+            Layer::Panel => &self.panel,
+            Layer::Window => &self.window,
+            Layer::Backdrop => &self.backdrop,
+        };
+        blit(source, position, size, root, to, foreground_alpha, background_alpha);
+    }
+
+    fn copy_screen(&mut self, to: Layer) {
+        let size = (self.root.width(), self.root.height());
+        let target = match to {
+            Layer::Screen => return,
+            Layer::Map => &mut self.map,
+            Layer::Panel => &mut self.panel,
+            Layer::Window => &mut self.window,
+            Layer::Backdrop => &mut self.backdrop,
+        };
+        *target = Offscreen::new(size.0, size.1);
+        blit(&self.root, (0, 0), size, target, (0, 0), 1.0, 1.0);
+    }
+
+    fn flush(&mut self) {
+        self.root.flush();
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        self.root.is_fullscreen()
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.root.set_fullscreen(fullscreen);
+    }
+
+    fn window_closed(&self) -> bool {
+        self.root.window_closed()
+    }
+
+    fn save_image(&self, layer: Layer, path: &str) {
+        match layer {
+            Layer::Screen => tcod::system::save_screenshot(path),
+            layer => on_layer_ref!(self, layer, |console| {
+                tcod::image::Image::from_console(console).save(path)
+            }),
+        }
+    }
+}
+
+impl InputSource for TcodBackend {
+    fn check_for_event(&mut self, mask: EventFlags) -> Option<Event> {
+        loop {
+            let (_, event) = input::check_for_event(mask)?;
+            if let Some(event) = typed(event) {
+                return Some(event);
+            }
+        }
+    }
+
+    fn wait_for_event(&mut self, mask: EventFlags) -> Option<Event> {
+        let mut flush = true;
+        loop {
+            let (_, event) = input::wait_for_event(mask, flush)?;
+            flush = false;
+            if let Some(event) = typed(event) {
+                return Some(event);
+            }
+        }
+    }
+
+    fn wait_for_keypress(&mut self) -> Key {
+        match self.wait_for_event(input::KEY_PRESS) {
+            Some(Event::Key(key)) => key,
+            _ => Key::default(),
+        }
+    }
+}
+
+/// libtcod sends a letter twice: as a character press, which says which key
+/// it was, then as text, which says what it typed. Only the text is kept,
+/// passed on as a character press.
+fn typed(event: Event) -> Option<Event> {
+    match event {
+        Event::Key(key) if key.code == KeyCode::Char && key.pressed => None,
+        Event::Key(mut key) if key.code == KeyCode::Text => {
+            key.code = KeyCode::Char;
+            key.printable = key.text().chars().next().unwrap_or(key.printable);
+            Some(Event::Key(key))
+        }
+        event => Some(event),
+    }
+}
@@ -0,0 +1,134 @@
+//! Light: the torch or lantern the player carries sets how far they can see,
+//! and burns down a little every turn it is lit. A torch is thrown away once
+//! it burns out; a lantern can be refilled with a flask of oil.
+
+use crate::config::config;
+use crate::equipment::toggle_equipment;
+use crate::game::Game;
+use crate::items::{Item, UseResult, take_from_inventory};
+use crate::locale::display_name;
+use crate::messages::MessageCategory;
+use crate::object::Object;
+use crate::status::StatusKind;
+use crate::ui::Tcod;
+
+pub const DARK_RADIUS: i32 = 1; // How far the player sees with no light at all
+pub const BLIND_RADIUS: i32 = 1; // How far a blinded player can still make things out
+pub const LANTERN_BONUS: i32 = 2; // How much further a lantern shines than a torch
+pub const TORCH_FUEL: u32 = 800;
+pub const LANTERN_FUEL: u32 = 1500; // A full lantern
+pub const OIL_FUEL: u32 = 750; // Poured into a lantern by one flask of oil
+pub const LOW_FUEL: u32 = 50; // The player is warned with this many turns left
+
+/// Whether the item gives light, when lit.
+pub fn is_light(item: Item) -> bool {
+    matches!(item, Item::Torch | Item::Lantern)
+}
+
+/// The inventory slot of the lit light the player carries, if any.
+fn lit_light(game: &Game) -> Option<usize> {
+    game.inventory.iter().position(|item| {
+        item.item.map_or(false, is_light) && item.equipment.map_or(false, |e| e.equipped)
+    })
+}
+
+/// How far the player can see, by the light they carry.
+pub fn light_radius(game: &Game) -> i32 {
+    let torch = config().fov.torch_radius;
+    match lit_light(game).and_then(|index| game.inventory[index].item) {
+        Some(Item::Torch) => torch,
+        Some(Item::Lantern) => torch + LANTERN_BONUS,
+        _ => DARK_RADIUS,
+    }
+}
+
+/// How far the player can see: by the light they carry, unless blinded.
+pub fn sight_radius(game: &Game, player: &Object) -> i32 {
+    if player.has_effect(StatusKind::Blindness) {
+        BLIND_RADIUS
+    } else {
+        light_radius(game)
+    }
+}
+
+/// Light the torch or lantern, or put it out if it is lit. There is no
+/// lighting one with nothing left to burn.
+pub fn toggle_light(
+    inventory_id: usize,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    let light = &game.inventory[inventory_id];
+    let lit = light.equipment.map_or(false, |e| e.equipped);
+    if !lit && light.charges == 0 {
+        let name = display_name(&light.name);
+        game.messages.add(tr!("light.empty", name), MessageCategory::Warning);
+        return UseResult::Cancelled;
+    }
+    tcod.fov_stale = true;
+    toggle_equipment(inventory_id, tcod, game, objects)
+}
+
+/// Pour a flask of oil into the lantern, lit or not.
+pub fn refill_lantern(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    _objects: &mut [Object],
+) -> UseResult
+{
+    // The lit lantern first, if there is one; otherwise the emptiest.
+    let lantern = game
+        .inventory
+        .iter()
+        .enumerate()
+        .filter(|&(_, item)| item.item == Some(Item::Lantern))
+        .min_by_key(|&(_, item)| (!item.equipment.map_or(false, |e| e.equipped), item.charges))
+        .map(|(index, _)| index);
+    let lantern = match lantern {
+        Some(index) => &mut game.inventory[index],
+        None => {
+            game.messages.add(tr!("light.no-lantern"), MessageCategory::Warning);
+            return UseResult::Cancelled;
+        }
+    };
+    if lantern.charges >= LANTERN_FUEL {
+        game.messages.add(tr!("light.full"), MessageCategory::Warning);
+        return UseResult::Cancelled;
+    }
+    lantern.charges = (lantern.charges + OIL_FUEL).min(LANTERN_FUEL);
+    game.messages.add(tr!("light.refilled"), MessageCategory::Item);
+    UseResult::UsedUp
+}
+
+/// Burn a turn's worth of the lit light. A torch that burns out is gone; a
+/// lantern that runs dry only goes out.
+pub fn tick_light(tcod: &mut Tcod, game: &mut Game) {
+    // The lights around the level may have moved with the turn, so what
+    // they show is worked out afresh.
+    tcod.fov_stale = true;
+    let index = match lit_light(game) {
+        Some(index) => index,
+        None => return,
+    };
+    let light = &mut game.inventory[index];
+    light.charges = light.charges.saturating_sub(1);
+    let name = display_name(&light.name);
+    if light.charges == LOW_FUEL {
+        game.messages.add(tr!("light.low", name), MessageCategory::Warning);
+    }
+    if light.charges > 0 {
+        return;
+    }
+    if light.item == Some(Item::Torch) {
+        take_from_inventory(&mut game.inventory, index);
+        game.messages.add(tr!("light.burnt-out", name), MessageCategory::Warning);
+    } else {
+        if let Some(ref mut equipment) = light.equipment {
+            equipment.equipped = false;
+        }
+        game.messages.add(tr!("light.out", name), MessageCategory::Warning);
+    }
+}
@@ -0,0 +1,154 @@
+//! Lighting: braziers, lava and burning creatures light up the tiles around
+//! them, whatever light the player carries. Lit tiles can be seen from as
+//! far away as they are in sight, and take on the color of their light.
+
+use rand::{Rng, StdRng};
+
+use crate::branch::Branch;
+use crate::config::config;
+use crate::map::{Map, Rect, Terrain};
+use crate::object::Object;
+use crate::render::COLOR_LIGHT_LAVA;
+use crate::status::StatusKind;
+use crate::tcod_types::colors::*;
+use crate::throw::trajectory;
+
+pub const BRAZIER_RADIUS: i32 = 4;
+pub const LAVA_GLOW_RADIUS: i32 = 1;
+pub const BURNING_GLOW_RADIUS: i32 = 2;
+pub const LIGHT_TINT: f32 = 0.3; // How strongly the brightest light colors a tile
+
+/// Something that gives off light.
+struct LightSource {
+    x: i32,
+    y: i32,
+    radius: i32,
+    color: Color,
+}
+
+/// How brightly, and in what color, each tile of the map is lit by the
+/// lights around it.
+pub struct Lighting {
+    width: i32,
+    height: i32,
+    tiles: Vec<Option<(f32, Color)>>,
+}
+
+impl Lighting {
+    /// Work out the light falling on every tile, from all the lights on the
+    /// level. Light reaches the walls it falls on, but not past them.
+    pub fn of(map: &Map, objects: &[Object]) -> Self {
+        let (width, height) = (config().map.width, config().map.height);
+        let mut lighting = Lighting {
+            width: width,
+            height: height,
+            tiles: vec![None; (width * height) as usize],
+        };
+        for source in light_sources(map, objects) {
+            let r = source.radius;
+            for y in (source.y - r).max(0)..(source.y + r + 1).min(height) {
+                for x in (source.x - r).max(0)..(source.x + r + 1).min(width) {
+                    let distance = (((x - source.x).pow(2) + (y - source.y).pow(2)) as f32).sqrt();
+                    if distance > r as f32 + 0.5 {
+                        continue;
+                    }
+                    let reached = trajectory((source.x, source.y), (x, y))
+                        .into_iter()
+                        .take_while(|&tile| tile != (x, y))
+                        .all(|(x, y)| !map[x as usize][y as usize].block_sight);
+                    if !reached {
+                        continue;
+                    }
+                    let brightness = 1.0 - distance / (r + 1) as f32;
+                    let tile = &mut lighting.tiles[(y * width + x) as usize];
+                    // The brightest light on a tile is the one it shows.
+                    if tile.map_or(true, |(brighter, _)| brightness > brighter) {
+                        *tile = Some((brightness, source.color));
+                    }
+                }
+            }
+        }
+        lighting
+    }
+
+    /// The light on the tile, if any: how bright it is, from 0 to 1, and
+    /// its color.
+    pub fn at(&self, x: i32, y: i32) -> Option<(f32, Color)> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        self.tiles[(y * self.width + x) as usize]
+    }
+
+    pub fn is_lit(&self, x: i32, y: i32) -> bool {
+        self.at(x, y).is_some()
+    }
+}
+
+/// Everything on the level that gives off light: lava, objects with a glow
+/// of their own, and whatever is on fire.
+fn light_sources(map: &Map, objects: &[Object]) -> Vec<LightSource> {
+    let mut sources = vec![];
+    for (x, column) in map.iter().enumerate() {
+        for (y, tile) in column.iter().enumerate() {
+            if tile.terrain == Terrain::Lava {
+                sources.push(LightSource {
+                    x: x as i32,
+                    y: y as i32,
+                    radius: LAVA_GLOW_RADIUS,
+                    color: COLOR_LIGHT_LAVA,
+                });
+            }
+        }
+    }
+    for object in objects {
+        if object.glow > 0 {
+            sources.push(LightSource {
+                x: object.x,
+                y: object.y,
+                radius: object.glow,
+                color: object.color,
+            });
+        }
+        let burning = object.alive
+            && object.effects.iter().any(|effect| effect.kind == StatusKind::Burning);
+        if burning {
+            sources.push(LightSource {
+                x: object.x,
+                y: object.y,
+                radius: BURNING_GLOW_RADIUS,
+                color: FLAME,
+            });
+        }
+    }
+    sources
+}
+
+/// Sometimes set a brazier in the room, away from its walls, so it never
+/// stands in anyone's way, and from its middle.
+pub fn place_brazier(
+    branch: Branch,
+    room: Rect,
+    map: &Map,
+    objects: &mut Vec<Object>,
+    rng: &mut StdRng,
+) {
+    if room.x2 - room.x1 < 5 || room.y2 - room.y1 < 5 {
+        return;
+    }
+    if rng.next_f32() >= branch.brazier_chance() {
+        return;
+    }
+    let x = rng.gen_range(room.x1 + 2, room.x2 - 1);
+    let y = rng.gen_range(room.y1 + 2, room.y2 - 1);
+    let tile = &map[x as usize][y as usize];
+    if (x, y) == room.center() || tile.blocked || tile.terrain != Terrain::Floor {
+        return;
+    }
+    if objects.iter().any(|object| object.pos() == (x, y)) {
+        return;
+    }
+    let mut brazier = Object::new(x, y, '&', "brazier", FLAME, true);
+    brazier.glow = BRAZIER_RADIUS;
+    objects.push(brazier);
+}
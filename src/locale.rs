@@ -0,0 +1,125 @@
+//! Translations of the game's text, read from `locales/`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::Read;
+
+pub const LOCALE_DIR: &str = "locales";
+
+/// The strings of one language, read from a `locales/<language>.txt` file of
+/// `key = text` lines.
+pub struct Locale {
+    pub strings: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn parse(text: &str) -> Self {
+        let strings = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, '=');
+                let key = parts.next()?.trim();
+                let text = parts.next()?.trim();
+                Some((key.to_string(), text.replace("\\n", "\n")))
+            })
+            .collect();
+        Locale { strings: strings }
+    }
+
+    pub fn load(language: &str) -> Result<Self, Box<dyn Error>> {
+        let mut text = String::new();
+        File::open(format!("{}/{}.txt", LOCALE_DIR, language))?.read_to_string(&mut text)?;
+        Ok(Locale::parse(&text))
+    }
+}
+
+thread_local! {
+    /// English is built into the game, so there is always some text to show.
+    pub static ENGLISH: Locale = Locale::parse(include_str!("../locales/en.txt"));
+    /// The language the player picked, if not English.
+    pub static LOCALE: RefCell<Option<Locale>> = const { RefCell::new(None) };
+}
+
+/// Switch all game text to the given language.
+pub fn set_language(language: &str) -> Result<(), Box<dyn Error>> {
+    let locale = if language == "en" {
+        None
+    } else {
+        Some(Locale::load(language)?)
+    };
+    LOCALE.with(|current| *current.borrow_mut() = locale);
+    Ok(())
+}
+
+/// All languages available, as `(code, name)` pairs.
+pub fn available_languages() -> Vec<(String, String)> {
+    let mut languages = vec![("en".to_string(), "English".to_string())];
+    if let Ok(entries) = fs::read_dir(LOCALE_DIR) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let code = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(code) if code != "en" && path.extension().map_or(false, |e| e == "txt") => code,
+                _ => continue,
+            };
+            if let Ok(locale) = Locale::load(code) {
+                let name = locale.strings.get("language-name").cloned().unwrap_or_else(|| code.into());
+                languages.push((code.into(), name));
+            }
+        }
+    }
+    languages.sort();
+    languages
+}
+
+/// Find the text with the given key in the current language, falling back to English.
+pub fn lookup(key: &str) -> Option<String> {
+    LOCALE
+        .with(|current| {
+            current
+                .borrow()
+                .as_ref()
+                .and_then(|locale| locale.strings.get(key).cloned())
+        })
+        .or_else(|| ENGLISH.with(|english| english.strings.get(key).cloned()))
+}
+
+/// Translate the text with the given key. Use the `tr!` macro instead of calling this directly.
+pub fn translate(key: &str, args: &[&dyn Display]) -> String {
+    let text = lookup(key).unwrap_or_else(|| key.to_string());
+
+    // Replace `{}` with the next argument, and `{n}` with the n-th one.
+    let mut result = String::new();
+    let mut rest = text.as_str();
+    let mut next_arg = 0;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        result.push_str(&rest[..start]);
+        let index = &rest[start + 1..end];
+        let arg = if index.is_empty() {
+            next_arg += 1;
+            args.get(next_arg - 1)
+        } else {
+            index.parse().ok().and_then(|index: usize| args.get(index))
+        };
+        match arg {
+            Some(arg) => result.push_str(&arg.to_string()),
+            None => result.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The name of an object as it should be shown to the player.
+pub fn display_name(name: &str) -> String {
+    lookup(&format!("name.{}", name)).unwrap_or_else(|| name.to_string())
+}
@@ -0,0 +1,95 @@
+//! Locked doors, which keep some vaults and their treasure shut away until
+//! the player finds the key hidden elsewhere on the level, or picks the lock.
+
+use rand::{Rng, StdRng};
+
+use crate::game::Game;
+use crate::items::{Item, UseResult, create_item, take_from_inventory};
+use crate::map::{Map, is_locked_door, random_free_tile, set_door};
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+use crate::ui::Tcod;
+
+pub const LOCKED_VAULT_CHANCE: f32 = 0.5; // Chance of a vault's doors being locked
+pub const LOCKPICK_CHANCE: f32 = 0.1; // Chance of picking a lock at the first level
+pub const LOCKPICK_PER_LEVEL: f32 = 0.05; // Better odds for each level the player gained since
+
+/// Sometimes lock the doors of a vault, and leave their key somewhere on the
+/// level outside it.
+pub fn lock_vault<F>(
+    doors: &[(i32, i32)],
+    inside: F,
+    map: &mut Map,
+    objects: &mut Vec<Object>,
+    rng: &mut StdRng,
+) where
+    F: Fn(i32, i32) -> bool,
+{
+    if doors.is_empty() || rng.next_f32() >= LOCKED_VAULT_CHANCE {
+        return;
+    }
+    for &(x, y) in doors {
+        map[x as usize][y as usize].locked = true;
+    }
+    let (x, y) = loop {
+        let (x, y) = random_free_tile(map, objects, rng);
+        if !inside(x, y) {
+            break (x, y);
+        }
+    };
+    objects.push(create_item(Item::Key, x, y));
+}
+
+/// Unlock and open the door, and every other door locked with it.
+fn unlock(x: i32, y: i32, map: &mut Map) {
+    for column in map.iter_mut() {
+        for tile in column.iter_mut() {
+            tile.locked = false;
+        }
+    }
+    set_door(x, y, true, map);
+}
+
+/// Try the lock of the door at the given spot: with the key, if the player
+/// has it, or else by picking the lock, which gets easier with experience.
+pub fn try_lock(x: i32, y: i32, game: &mut Game, objects: &[Object]) {
+    if let Some(index) = game.inventory.iter().position(|item| item.item == Some(Item::Key)) {
+        take_from_inventory(&mut game.inventory, index);
+        unlock(x, y, &mut game.map);
+        game.messages.add(tr!("lock.unlocked"), MessageCategory::Lore);
+        return;
+    }
+    let chance = LOCKPICK_CHANCE + LOCKPICK_PER_LEVEL * (objects[PLAYER].level - 1) as f32;
+    if game.rng.next_f32() < chance {
+        game.map[x as usize][y as usize].locked = false;
+        set_door(x, y, true, &mut game.map);
+        game.messages.add(tr!("lock.picked"), MessageCategory::Lore);
+    } else {
+        game.messages.add(tr!("lock.failed"), MessageCategory::Warning);
+    }
+}
+
+/// Unlock a locked door next to the player.
+pub fn use_key(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    let (px, py) = objects[PLAYER].pos();
+    let door = (-1..2)
+        .flat_map(|dx| (-1..2).map(move |dy| (px + dx, py + dy)))
+        .find(|&(x, y)| is_locked_door(x, y, &game.map));
+    match door {
+        Some((x, y)) => {
+            unlock(x, y, &mut game.map);
+            game.messages.add(tr!("lock.unlocked"), MessageCategory::Lore);
+            UseResult::UsedUp
+        }
+        None => {
+            game.messages.add(tr!("lock.none"), MessageCategory::Warning);
+            UseResult::Cancelled
+        }
+    }
+}
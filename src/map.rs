@@ -0,0 +1,672 @@
+//! Dungeon tiles, and the generation of dungeon levels.
+
+use std::cmp;
+
+use rand::{Rng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::branch::Branch;
+use crate::config::{Generator, config};
+use crate::equipment::maybe_curse;
+use crate::gold::place_gold;
+use crate::items::{Item, create_item, random_item};
+use crate::lava::place_lava;
+use crate::lighting::place_brazier;
+use crate::object::{Object, PLAYER, random_monster};
+use crate::pack::place_pack;
+use crate::pathfinding;
+use crate::ranged::bundle_ammo;
+use crate::render::{
+    COLOR_DARK_DEEP_WATER, COLOR_DARK_LAVA, COLOR_DARK_SHALLOW_WATER, COLOR_LIGHT_DEEP_WATER,
+    COLOR_LIGHT_LAVA, COLOR_LIGHT_SHALLOW_WATER,
+};
+use crate::sleep::maybe_put_to_sleep;
+use crate::tcod_types::colors::*;
+use crate::traps::{Trap, place_trap};
+use crate::vault::place_vault;
+use crate::water::place_pool;
+
+pub const FOUNTAIN_CHANCE: f32 = 0.1; // Chance of a fountain in each room
+pub const DOOR_CHANCE: f32 = 0.6; // Chance of a door in each way into a room
+pub const SECRET_DOOR_CHANCE: f32 = 0.1; // Chance of a door being a secret one
+pub const BSP_MIN_LEAF: i32 = 10; // Pieces of a partitioned map are never cut smaller than this
+pub const CAVE_FILL: f32 = 0.45; // Share of a cave level that starts out as rock
+pub const CAVE_SMOOTHING: u32 = 4; // Passes of the cellular automaton
+pub const CAVE_MIN_POCKET: usize = 12; // Smaller pockets are filled in rather than joined up
+pub const CAVE_AREA_PER_SPOT: usize = 80; // Floor tiles for each spot with monsters and items
+
+/// A tile of the map and its properties
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Tile {
+    pub blocked: bool,
+    pub explored: bool,
+    pub block_sight: bool,
+    /// A door, which is shut while it blocks.
+    #[serde(default)]
+    pub door: bool,
+    /// A door that passes for wall, until someone finds it.
+    #[serde(default)]
+    pub secret: bool,
+    /// A door that will not open without its key, or a picked lock.
+    #[serde(default)]
+    pub locked: bool,
+    /// Rock too hard to dig through, like the walls of vaults.
+    #[serde(default)]
+    pub undiggable: bool,
+    #[serde(default)]
+    pub terrain: Terrain,
+    #[serde(default)]
+    pub trap: Option<Trap>,
+}
+
+/// What covers the floor of a tile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Terrain {
+    #[default]
+    Floor,
+    /// Wades through like floor.
+    ShallowWater,
+    /// Slow to swim through, and those who cannot swim drown in it.
+    DeepWater,
+    /// Burns whoever stands in it, and glows in the dark.
+    Lava,
+}
+
+impl Terrain {
+    pub fn name(self) -> Option<String> {
+        match self {
+            Terrain::Floor => None,
+            Terrain::ShallowWater => Some(tr!("look.shallow-water")),
+            Terrain::DeepWater => Some(tr!("look.deep-water")),
+            Terrain::Lava => Some(tr!("look.lava")),
+        }
+    }
+
+    /// What is drawn over its ground, if anything.
+    pub fn glyph(self) -> Option<(char, Color)> {
+        match self {
+            Terrain::DeepWater => Some(('~', LIGHT_BLUE)),
+            Terrain::Lava => Some(('~', YELLOW)),
+            _ => None,
+        }
+    }
+
+    /// Whether monsters would not last long there, and so are never put there.
+    pub fn deadly(self) -> bool {
+        matches!(self, Terrain::DeepWater | Terrain::Lava)
+    }
+
+    /// The color of its ground, in view and out of it, unless it is plain floor.
+    pub fn colors(self) -> Option<(Color, Color)> {
+        match self {
+            Terrain::Floor => None,
+            Terrain::ShallowWater => Some((COLOR_DARK_SHALLOW_WATER, COLOR_LIGHT_SHALLOW_WATER)),
+            Terrain::DeepWater => Some((COLOR_DARK_DEEP_WATER, COLOR_LIGHT_DEEP_WATER)),
+            Terrain::Lava => Some((COLOR_DARK_LAVA, COLOR_LIGHT_LAVA)),
+        }
+    }
+}
+
+impl Tile {
+    pub fn empty() -> Self {
+        Tile {
+            blocked: false,
+            explored: false,
+            block_sight: false,
+            door: false,
+            secret: false,
+            locked: false,
+            undiggable: false,
+            terrain: Terrain::Floor,
+            trap: None,
+        }
+    }
+
+    pub fn wall() -> Self {
+        Tile {
+            blocked: true,
+            explored: false,
+            block_sight: true,
+            door: false,
+            secret: false,
+            locked: false,
+            undiggable: false,
+            terrain: Terrain::Floor,
+            trap: None,
+        }
+    }
+
+    /// A closed door.
+    pub fn door() -> Self {
+        Tile {
+            blocked: true,
+            explored: false,
+            block_sight: true,
+            door: true,
+            secret: false,
+            locked: false,
+            undiggable: false,
+            terrain: Terrain::Floor,
+            trap: None,
+        }
+    }
+}
+
+pub type Map = Vec<Vec<Tile>>;
+
+// Paths go around other creatures when they can, rather than wait behind them.
+pub const OCCUPIED_STEP_COST: u32 = 5;
+// Opening a door on the way takes a turn.
+pub const DOOR_STEP_COST: u32 = 2;
+// Known traps are only walked over when there is no other way.
+pub const TRAP_STEP_COST: u32 = 20;
+// Swimming is slow, so deep water is best gone around.
+pub const DEEP_WATER_STEP_COST: u32 = 3;
+
+/// Whether a tile is on the map at all, and so can be looked up in it.
+pub fn on_map(x: i32, y: i32) -> bool {
+    x >= 0 && y >= 0 && x < config().map.width && y < config().map.height
+}
+
+/// Whether a tile is off the map or cannot be walked on.
+pub fn map_blocked(x: i32, y: i32, map: &Map) -> bool {
+    !on_map(x, y) || map[x as usize][y as usize].blocked
+}
+
+/// Whether a tile is a door that is shut. Secret doors are not, as far as
+/// anyone can tell, until they are found.
+pub fn is_closed_door(x: i32, y: i32, map: &Map) -> bool {
+    on_map(x, y) && {
+        let tile = &map[x as usize][y as usize];
+        tile.door && tile.blocked && !tile.secret
+    }
+}
+
+/// Whether a tile is a door that is shut and locked.
+pub fn is_locked_door(x: i32, y: i32, map: &Map) -> bool {
+    is_closed_door(x, y, map) && map[x as usize][y as usize].locked
+}
+
+/// Whether a tile is a door that stands open.
+pub fn is_open_door(x: i32, y: i32, map: &Map) -> bool {
+    on_map(x, y) && map[x as usize][y as usize].door && !map[x as usize][y as usize].blocked
+}
+
+/// Open or shut the door on a tile.
+pub fn set_door(x: i32, y: i32, open: bool, map: &mut Map) {
+    let tile = &mut map[x as usize][y as usize];
+    tile.blocked = !open;
+    tile.block_sight = !open;
+}
+
+/// The cost of stepping onto a tile when finding a path, or `None` if it
+/// cannot be walked on or is off the map.
+pub fn travel_cost(x: i32, y: i32, map: &Map, objects: &[Object]) -> Option<u32> {
+    if !on_map(x, y) || is_locked_door(x, y, map) || map[x as usize][y as usize].terrain == Terrain::Lava {
+        None
+    } else if is_closed_door(x, y, map) {
+        Some(DOOR_STEP_COST)
+    } else if map_blocked(x, y, map) {
+        None
+    } else if objects.iter().any(|object| object.blocks && object.pos() == (x, y)) {
+        Some(OCCUPIED_STEP_COST)
+    } else if map[x as usize][y as usize].trap.map_or(false, |trap| !trap.hidden) {
+        Some(TRAP_STEP_COST)
+    } else if map[x as usize][y as usize].terrain == Terrain::DeepWater {
+        Some(DEEP_WATER_STEP_COST)
+    } else {
+        Some(1)
+    }
+}
+
+/// A rectangle on the map, used to characterize a room.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Rect {
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        Rect {
+            x1: x,
+            y1: y,
+            x2: x + w,
+            y2: y + h,
+        }
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        let center_x = (self.x1 + self.x2) / 2;
+        let center_y = (self.y1 + self.y2) / 2;
+        (center_x, center_y)
+    }
+
+    /// Whether the point is on the room's floor, rather than in or beyond its walls.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x > self.x1 && x < self.x2 && y > self.y1 && y < self.y2
+    }
+
+    pub fn intersects_with(&self, other: &Rect) -> bool {
+        // Returns true if this rectangle intersects with another one
+        (self.x1 <= other.x2)
+            && (self.x2 >= other.x1)
+            && (self.y1 <= other.y2)
+            && (self.y2 >= other.y1)
+    }
+}
+
+pub fn create_room(room: Rect, map: &mut Map) {
+    // Go through the tiles in the rectangle and make them passable
+    for x in (room.x1 + 1)..room.x2 {
+        for y in (room.y1 + 1)..room.y2 {
+            map[x as usize][y as usize] = Tile::empty();
+        }
+    }
+}
+
+pub fn create_h_tunnel(x1: i32, x2: i32, y: i32, map: &mut Map) {
+    // horizontal tunnel `min()` and `max()` are used in case `x1 > x2`
+    for x in cmp::min(x1, x2)..(cmp::max(x1, x2) + 1) {
+        map[x as usize][y as usize] = Tile::empty();
+    }
+}
+
+pub fn create_v_tunnel(y1: i32, y2: i32, x: i32, map: &mut Map) {
+    // horizontal tunnel `min()` and `max()` are used in case `y1 > y2`
+    for y in cmp::min(y1, y2)..(cmp::max(y1, y2) + 1) {
+        map[x as usize][y as usize] = Tile::empty();
+    }
+}
+
+pub fn place_objects(
+    branch: Branch,
+    room: Rect,
+    map: &mut Map,
+    objects: &mut Vec<Object>,
+    unlocked_items: &[Item],
+    rng: &mut StdRng,
+) {
+    place_pool(branch, room, map, rng);
+    place_lava(branch, room, map, rng);
+    place_brazier(branch, room, map, objects, rng);
+
+    // Choose random number of monsters
+    let num_monsters = rng.gen_range(0, config().map.max_room_monsters + 1);
+
+    for _ in 0..num_monsters {
+        // Chose random spot for this monster
+        let x = rng.gen_range(room.x1 + 1, room.x2);
+        let y = rng.gen_range(room.y1 + 1, room.y2);
+
+        // Only place monster if tile is not blocked, nor deadly
+        let deadly = map[x as usize][y as usize].terrain.deadly();
+        if !Object::is_blocked(x, y, map, objects) && !deadly {
+            let mut monster = random_monster(x, y, branch, rng);
+            maybe_put_to_sleep(&mut monster, rng);
+            objects.push(monster);
+            place_pack(objects.len() - 1, map, objects, rng);
+        }
+    }
+
+    // Choose random number of items. 
+    let num_items = rng.gen_range(0, config().map.max_room_items + 1);
+
+    for _ in 0..num_items {
+        // Choose random spot for this item. 
+        let x = rng.gen_range(room.x1 + 1, room.x2);
+        let y = rng.gen_range(room.y1 + 1, room.y2);
+
+        // Only place item if the tile is not blocked. 
+        if !Object::is_blocked(x, y, map, objects) {
+            let item = random_item(unlocked_items, rng);
+            let mut object = create_item(item, x, y);
+            maybe_curse(&mut object, rng);
+            bundle_ammo(&mut object, rng);
+            objects.push(object);
+        }
+    }
+
+    // Now and then, a fountain to gurgle in the background.
+    if rng.next_f32() < FOUNTAIN_CHANCE {
+        let (x, y) = room.center();
+        if !Object::is_blocked(x, y, map, objects) {
+            objects.push(Object::new(x, y, '{', "fountain", LIGHT_BLUE, false));
+        }
+    }
+
+    place_gold(room, map, objects, rng);
+    place_trap(room, map, objects, rng);
+}
+
+/// Generate a new map for the given branch and depth, laid out the way the
+/// branch (or, in the main dungeon, `config.toml`) asks. All randomness
+/// comes from `rng`, so the same seed always produces the same dungeon.
+/// Items in `unlocked_items` join the spawn pool.
+pub fn make_map(
+    branch: Branch,
+    depth: u32,
+    objects: &mut Vec<Object>,
+    unlocked_items: &[Item],
+    rng: &mut StdRng,
+) -> (Map, Vec<Rect>) {
+    let (mut map, rooms) = match branch.generator(depth) {
+        Generator::Rooms => make_rooms_map(branch, objects, unlocked_items, rng),
+        Generator::Bsp => make_bsp_map(branch, objects, unlocked_items, rng),
+        Generator::Caves => make_cave_map(branch, objects, unlocked_items, rng),
+    };
+
+    place_vault(branch, &rooms, &mut map, objects, unlocked_items, rng);
+    place_doors(&rooms, &mut map, rng);
+
+    // Create stairs at the center of the last room. Caves have no rooms, and see to their own.
+    if let Some(last_room) = rooms.last() {
+        let (last_room_x, last_room_y) = last_room.center();
+        let stairs = Object::new(last_room_x, last_room_y, '>', "stairs", WHITE, false);
+        objects.push(stairs);
+    }
+
+    (map, rooms)
+}
+
+/// Generate a level of rooms scattered at random, each joined to the last by a corridor.
+pub fn make_rooms_map(
+    branch: Branch,
+    objects: &mut Vec<Object>,
+    unlocked_items: &[Item],
+    rng: &mut StdRng,
+) -> (Map, Vec<Rect>) {
+    // fill map with "blocked" tiles
+    let mut map = vec![vec![Tile::wall(); config().map.height as usize]; config().map.width as usize];
+
+    // Create rooms
+    let mut rooms = vec![];
+
+    for _ in 0..config().map.max_rooms {
+        // Random width and height
+        let w = rng.gen_range(config().map.room_min_size, config().map.room_max_size + 1);
+        let h = rng.gen_range(config().map.room_min_size, config().map.room_max_size + 1);
+        // Random position without going out of the boundaries of the map
+        let x = rng.gen_range(0, config().map.width - w);
+        let y = rng.gen_range(0, config().map.height - h);
+
+        let new_room = Rect::new(x, y, w, h);
+
+        // Run through the other rooms and see if they intersect with this one
+        let failed = rooms
+            .iter()
+            .any(|other_room| new_room.intersects_with(other_room));
+
+        if !failed {
+            // The room is valid if there are no intersections
+
+            // "Paint" it to the map's tiles
+            create_room(new_room, &mut map);
+
+            // Add some content to this room, such as monsters
+            place_objects(branch, new_room, &mut map, objects, unlocked_items, rng);
+
+            // Center coordinates of the new room
+            let (new_x, new_y) = new_room.center();
+
+            if rooms.is_empty() {
+                // Start player at first created room
+                objects[PLAYER].set_pos(new_x, new_y);
+            } else {
+                // Connect new room to previous room with a tunnel
+
+                // Get center coordinates of the previous room
+                let (prev_x, prev_y) = rooms[rooms.len() - 1].center();
+
+                // Toss a coin (random bool value -- either true or false)
+                if rng.gen() {
+                    // First move horizontally, then vertically
+                    create_h_tunnel(prev_x, new_x, prev_y, &mut map);
+                    create_v_tunnel(prev_y, new_y, new_x, &mut map);
+                } else {
+                    // First move vertically, then horizontally
+                    create_v_tunnel(prev_y, new_y, prev_x, &mut map);
+                    create_h_tunnel(prev_x, new_x, new_y, &mut map);
+                }
+            }
+
+            // Finally, append the new room to `rooms`
+            rooms.push(new_room);
+        }
+    }
+
+    (map, rooms)
+}
+
+/// Generate a level by binary space partitioning: the map is cut in two,
+/// and each half again, until the pieces are small enough to hold a room
+/// each. Rooms are joined along the cuts, so the layout is tidier than
+/// rooms dropped anywhere.
+pub fn make_bsp_map(
+    branch: Branch,
+    objects: &mut Vec<Object>,
+    unlocked_items: &[Item],
+    rng: &mut StdRng,
+) -> (Map, Vec<Rect>) {
+    let mut map = vec![vec![Tile::wall(); config().map.height as usize]; config().map.width as usize];
+    let whole = Rect {
+        x1: 0,
+        y1: 0,
+        x2: config().map.width - 1,
+        y2: config().map.height - 1,
+    };
+    let rooms = bsp_rooms(whole, &mut map, rng);
+
+    for &room in &rooms {
+        place_objects(branch, room, &mut map, objects, unlocked_items, rng);
+    }
+    if let Some(first_room) = rooms.first() {
+        let (x, y) = first_room.center();
+        objects[PLAYER].set_pos(x, y);
+    }
+    (map, rooms)
+}
+
+/// Generate a level of winding caverns: rock scattered at random, smoothed
+/// out by a cellular automaton, with any pockets left cut off tunnelled back
+/// to the main cave. The stairs go as far from the start as can be walked.
+pub fn make_cave_map(
+    branch: Branch,
+    objects: &mut Vec<Object>,
+    unlocked_items: &[Item],
+    rng: &mut StdRng,
+) -> (Map, Vec<Rect>) {
+    let (width, height) = (config().map.width, config().map.height);
+    let edge = |x: i32, y: i32| x == 0 || y == 0 || x == width - 1 || y == height - 1;
+    let mut map = vec![vec![Tile::wall(); height as usize]; width as usize];
+    for x in 0..width {
+        for y in 0..height {
+            if !edge(x, y) && rng.next_f32() >= CAVE_FILL {
+                map[x as usize][y as usize] = Tile::empty();
+            }
+        }
+    }
+
+    // Rock where most of the neighbourhood is rock, floor where most is floor.
+    for _ in 0..CAVE_SMOOTHING {
+        let previous = map.clone();
+        for x in 1..width - 1 {
+            for y in 1..height - 1 {
+                let rock = (-1..2)
+                    .flat_map(|dx| (-1..2).map(move |dy| (dx, dy)))
+                    .filter(|&(dx, dy)| previous[(x + dx) as usize][(y + dy) as usize].blocked)
+                    .count();
+                map[x as usize][y as usize] = if rock >= 5 { Tile::wall() } else { Tile::empty() };
+            }
+        }
+    }
+
+    // Join every pocket of floor to the biggest one, or fill it in if it is tiny.
+    let mut pockets = floor_regions(&map);
+    pockets.sort_by_key(|pocket| cmp::Reverse(pocket.len()));
+    if pockets.is_empty() {
+        // All rock; open up the middle so there is somewhere to stand.
+        let (x, y) = (width / 2, height / 2);
+        map[x as usize][y as usize] = Tile::empty();
+        pockets.push(vec![(x, y)]);
+    }
+    for pocket in &pockets[1..] {
+        if pocket.len() < CAVE_MIN_POCKET {
+            for &(x, y) in pocket {
+                map[x as usize][y as usize] = Tile::wall();
+            }
+            continue;
+        }
+        let (x1, y1) = pocket[0];
+        let distance = |&&(x, y): &&(i32, i32)| (x - x1).pow(2) + (y - y1).pow(2);
+        if let Some(&(x2, y2)) = pockets[0].iter().min_by_key(distance) {
+            create_h_tunnel(x1, x2, y1, &mut map);
+            create_v_tunnel(y1, y2, x2, &mut map);
+        }
+    }
+
+    let main = &pockets[0];
+    let start = main[rng.gen_range(0, main.len())];
+    objects[PLAYER].set_pos(start.0, start.1);
+
+    // Scatter monsters and items about, as if each spot were a small room.
+    let spots = cmp::max(1, main.len() / CAVE_AREA_PER_SPOT);
+    for _ in 0..spots {
+        let (x, y) = main[rng.gen_range(0, main.len())];
+        let spot = Rect::new(x - 3, y - 3, 6, 6);
+        let inside = spot.x1 >= 0 && spot.y1 >= 0 && spot.x2 < width && spot.y2 < height;
+        if inside && !spot.contains(start.0, start.1) {
+            place_objects(branch, spot, &mut map, objects, unlocked_items, rng);
+        }
+    }
+
+    let distances = pathfinding::distance_map(start, |x, y| {
+        if map_blocked(x, y, &map) { None } else { Some(1) }
+    });
+    if let Some((&(x, y), _)) = distances.iter().max_by_key(|&(&pos, &cost)| (cost, pos)) {
+        objects.push(Object::new(x, y, '>', "stairs", WHITE, false));
+    }
+    (map, vec![])
+}
+
+/// Every separate stretch of floor on the map, as the tiles in it.
+fn floor_regions(map: &Map) -> Vec<Vec<(i32, i32)>> {
+    let (width, height) = (config().map.width, config().map.height);
+    let mut seen = vec![vec![false; height as usize]; width as usize];
+    let mut regions = vec![];
+    for x in 0..width {
+        for y in 0..height {
+            if seen[x as usize][y as usize] || map[x as usize][y as usize].blocked {
+                continue;
+            }
+            let mut region = vec![];
+            let mut frontier = vec![(x, y)];
+            seen[x as usize][y as usize] = true;
+            while let Some((x, y)) = frontier.pop() {
+                region.push((x, y));
+                for &(dx, dy) in &[(0, -1), (0, 1), (-1, 0), (1, 0)] {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if !map_blocked(nx, ny, map) && !seen[nx as usize][ny as usize] {
+                        seen[nx as usize][ny as usize] = true;
+                        frontier.push((nx, ny));
+                    }
+                }
+            }
+            regions.push(region);
+        }
+    }
+    regions
+}
+
+/// Carve the rooms of one piece of a partitioned map, and join them up.
+fn bsp_rooms(leaf: Rect, map: &mut Map, rng: &mut StdRng) -> Vec<Rect> {
+    let width = leaf.x2 - leaf.x1;
+    let height = leaf.y2 - leaf.y1;
+    let split_x = width >= 2 * BSP_MIN_LEAF;
+    let split_y = height >= 2 * BSP_MIN_LEAF;
+
+    if !split_x && !split_y {
+        // Small enough: put a room somewhere in it.
+        let size = |most: i32, rng: &mut StdRng| {
+            let min = cmp::min(config().map.room_min_size, most);
+            let max = cmp::min(config().map.room_max_size, most);
+            rng.gen_range(min, max + 1)
+        };
+        let w = size(width, rng);
+        let h = size(height, rng);
+        let x = rng.gen_range(leaf.x1, leaf.x2 - w + 1);
+        let y = rng.gen_range(leaf.y1, leaf.y2 - h + 1);
+        let room = Rect::new(x, y, w, h);
+        create_room(room, map);
+        return vec![room];
+    }
+
+    // Cut across the longer side, so the pieces stay roughly square.
+    let (first, second) = if split_x && (!split_y || width >= height) {
+        let cut = rng.gen_range(leaf.x1 + BSP_MIN_LEAF, leaf.x2 - BSP_MIN_LEAF + 1);
+        (Rect { x2: cut, ..leaf }, Rect { x1: cut, ..leaf })
+    } else {
+        let cut = rng.gen_range(leaf.y1 + BSP_MIN_LEAF, leaf.y2 - BSP_MIN_LEAF + 1);
+        (Rect { y2: cut, ..leaf }, Rect { y1: cut, ..leaf })
+    };
+    let mut rooms = bsp_rooms(first, map, rng);
+    let others = bsp_rooms(second, map, rng);
+
+    // Join the two halves where they are closest to each other.
+    let (prev_x, prev_y) = rooms[rooms.len() - 1].center();
+    let (new_x, new_y) = others[0].center();
+    if rng.gen() {
+        create_h_tunnel(prev_x, new_x, prev_y, map);
+        create_v_tunnel(prev_y, new_y, new_x, map);
+    } else {
+        create_v_tunnel(prev_y, new_y, prev_x, map);
+        create_h_tunnel(prev_x, new_x, new_y, map);
+    }
+    rooms.extend(others);
+    rooms
+}
+
+/// Hang doors in some of the gaps that corridors broke through room walls,
+/// a few of them secret.
+pub fn place_doors(rooms: &[Rect], map: &mut Map, rng: &mut StdRng) {
+    let floor = |x: i32, y: i32, map: &Map| !map[x as usize][y as usize].blocked;
+    for room in rooms {
+        let mut gaps = vec![];
+        for x in (room.x1 + 1)..room.x2 {
+            for &y in &[room.y1, room.y2] {
+                // A gap in the top or bottom wall, with wall to either side.
+                if floor(x, y, map) && !floor(x - 1, y, map) && !floor(x + 1, y, map) {
+                    gaps.push((x, y));
+                }
+            }
+        }
+        for y in (room.y1 + 1)..room.y2 {
+            for &x in &[room.x1, room.x2] {
+                if floor(x, y, map) && !floor(x, y - 1, map) && !floor(x, y + 1, map) {
+                    gaps.push((x, y));
+                }
+            }
+        }
+        for (x, y) in gaps {
+            if rng.next_f32() < DOOR_CHANCE {
+                let mut door = Tile::door();
+                door.secret = rng.next_f32() < SECRET_DOOR_CHANCE;
+                map[x as usize][y as usize] = door;
+            }
+        }
+    }
+}
+
+/// Pick a random tile that is neither a wall nor occupied by a blocking object.
+pub fn random_free_tile(map: &Map, objects: &[Object], rng: &mut StdRng) -> (i32, i32) {
+    loop {
+        let x = rng.gen_range(0, config().map.width);
+        let y = rng.gen_range(0, config().map.height);
+        if !Object::is_blocked(x, y, map, objects) {
+            return (x, y);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! The message log.
+
+use serde::{Deserialize, Serialize};
+
+use crate::tcod_types::colors::*;
+
+/// What a message is about. The player's settings decide each category's
+/// color, and whether it is shown at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum MessageCategory {
+    Combat,
+    /// Critical hits, by the player or on them.
+    Critical,
+    Item,
+    Warning,
+    Lore,
+    /// About the game rather than the dungeon: saving, screenshots, macros.
+    System,
+}
+
+impl MessageCategory {
+    pub const ALL: [MessageCategory; 6] = [
+        MessageCategory::Combat,
+        MessageCategory::Critical,
+        MessageCategory::Item,
+        MessageCategory::Warning,
+        MessageCategory::Lore,
+        MessageCategory::System,
+    ];
+
+    pub fn name(self) -> String {
+        use MessageCategory::*;
+        match self {
+            Combat => tr!("category.combat"),
+            Critical => tr!("category.critical"),
+            Item => tr!("category.item"),
+            Warning => tr!("category.warning"),
+            Lore => tr!("category.lore"),
+            System => tr!("category.system"),
+        }
+    }
+
+    pub fn default_style(self) -> MessageStyle {
+        use MessageCategory::*;
+        let color = match self {
+            Combat => WHITE,
+            Critical => ORANGE,
+            Item => LIGHT_GREEN,
+            Warning => RED,
+            Lore => LIGHT_YELLOW,
+            System => LIGHT_GREY,
+        };
+        MessageStyle {
+            color: color,
+            muted: false,
+        }
+    }
+}
+
+/// How the messages of one category are shown.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct MessageStyle {
+    pub color: Color,
+    /// Muted messages are left out of the panel, but kept in the history.
+    pub muted: bool,
+}
+
+/// Colors the player can pick for a message category.
+pub const MESSAGE_PALETTE: [(&str, Color); 9] = [
+    ("color.white", WHITE),
+    ("color.grey", LIGHT_GREY),
+    ("color.red", RED),
+    ("color.orange", ORANGE),
+    ("color.yellow", LIGHT_YELLOW),
+    ("color.green", LIGHT_GREEN),
+    ("color.cyan", LIGHT_CYAN),
+    ("color.blue", LIGHT_BLUE),
+    ("color.violet", LIGHT_VIOLET),
+];
+
+pub fn color_name(color: Color) -> String {
+    MESSAGE_PALETTE
+        .iter()
+        .find(|&&(_, palette_color)| palette_color == color)
+        .map_or_else(|| tr!("color.custom"), |&(key, _)| tr!(key))
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Messages {
+    pub messages: Vec<(String, MessageCategory)>,
+    /// The text of the newest message as it was added, before any count of
+    /// its repeats was put after it.
+    #[serde(default)]
+    last: String,
+    /// How many times in a row the newest message was added.
+    #[serde(default)]
+    repeats: u32,
+}
+
+impl Messages {
+    pub fn new() -> Self {
+        Self {
+            messages: vec![],
+            last: String::new(),
+            repeats: 0,
+        }
+    }
+
+    /// Add the new message as a tuple, with the text and the category. The
+    /// same message added again right away is not repeated, but counted on
+    /// the line already there.
+    pub fn add<T: Into<String>>(&mut self, message: T, category: MessageCategory) {
+        let message = message.into();
+        if let Some(newest) = self.messages.last_mut() {
+            if self.repeats > 0 && newest.1 == category && message == self.last {
+                self.repeats += 1;
+                newest.0 = tr!("message.repeated", message, self.repeats);
+                return;
+            }
+        }
+        self.messages.push((message.clone(), category));
+        self.last = message;
+        self.repeats = 1;
+    }
+
+    /// Create a `DoubleEndedIterator` over the messages. 
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(String, MessageCategory)> {
+        self.messages.iter()
+    }
+}
@@ -0,0 +1,79 @@
+//! The morgue: a plain text account of each character whose run is over,
+//! written when they die or win, with how far they got, what they carried,
+//! what they killed and the last things that happened to them.
+
+use std::fs;
+use std::io;
+
+use crate::game::Game;
+use crate::items::stack_name;
+use crate::locale::display_name;
+use crate::object::{Object, PLAYER};
+
+pub const MORGUE_DIR: &str = "morgue";
+pub const MORGUE_MESSAGES: usize = 50; // Newest messages kept in a morgue file
+
+/// Write the morgue file of a character who just died or won. Returns its path.
+pub fn write_morgue(game: &Game, objects: &[Object], name: &str) -> io::Result<String> {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    // The name is the player's own, and may not make a good file name.
+    let safe_name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let path = format!("{}/{}-{}.txt", MORGUE_DIR, safe_name, stamp);
+    fs::create_dir_all(MORGUE_DIR)?;
+    fs::write(&path, morgue_text(game, objects, name))?;
+    Ok(path)
+}
+
+pub fn morgue_text(game: &Game, objects: &[Object], name: &str) -> String {
+    let player = &objects[PLAYER];
+    let mut lines = vec![];
+
+    if game.won {
+        lines.push(tr!("morgue.title-won", name, game.dungeon_level, game.branch.name()));
+    } else {
+        lines.push(tr!("morgue.title", name, game.dungeon_level, game.branch.name()));
+        if let Some(ref cause) = game.last_attacker {
+            lines.push(tr!("morgue.killed-by", display_name(cause)));
+        }
+    }
+    lines.push(tr!("morgue.turns", game.turn, game.seed));
+    lines.push(String::new());
+
+    if let Some(fighter) = player.fighter {
+        lines.push(tr!("morgue.level", player.level, fighter.xp));
+        lines.push(tr!("morgue.hp", fighter.hp, fighter.max_hp));
+        lines.push(tr!("morgue.attack", fighter.power, fighter.defense));
+    }
+    lines.push(tr!("morgue.gold", game.gold));
+    lines.push(String::new());
+
+    lines.push(tr!("morgue.inventory"));
+    if game.inventory.is_empty() {
+        lines.push(format!("  {}", tr!("inventory-empty")));
+    }
+    for item in &game.inventory {
+        lines.push(format!("  {}", stack_name(item, &game.identification)));
+    }
+    lines.push(String::new());
+
+    lines.push(tr!("morgue.kills", game.kills));
+    let mut kills: Vec<_> = game.kill_counts.iter().collect();
+    // The most killed first, and alike counts in alphabetical order.
+    kills.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    for (monster, count) in kills {
+        lines.push(format!("  {:>4} {}", count, display_name(monster)));
+    }
+    lines.push(String::new());
+
+    lines.push(tr!("morgue.messages"));
+    let messages = &game.messages.messages;
+    let first = messages.len().saturating_sub(MORGUE_MESSAGES);
+    for (message, _) in &messages[first..] {
+        lines.push(format!("  {}", message));
+    }
+
+    lines.join("\n") + "\n"
+}
@@ -0,0 +1,64 @@
+//! Noise: blows, breaking glass, alarms and shouts are heard some way off,
+//! round corners but not through walls, and muffled by closed doors. Every
+//! monster in earshot wakes, and the hostile ones come to see what it was.
+
+use crate::config::config;
+use crate::game::Game;
+use crate::object::{Object, PLAYER};
+use crate::pathfinding;
+
+pub const COMBAT_NOISE: u32 = 6; // How far the sound of a blow carries
+pub const SHATTER_NOISE: u32 = 10; // A potion breaking
+pub const LANDING_NOISE: u32 = 5; // A thrown item clattering to the ground
+pub const SHOUT_NOISE: u32 = 20;
+pub const DOOR_MUFFLING: u32 = 4; // A closed door counts as this many tiles more
+
+/// A noise made somewhere on the map, heard up to `loudness` tiles away.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Noise {
+    pub x: i32,
+    pub y: i32,
+    pub loudness: u32,
+}
+
+/// Make a noise at the given spot, to be heard before the monsters next act.
+pub fn make_noise(game: &mut Game, x: i32, y: i32, loudness: u32) {
+    game.noises.push(Noise {
+        x: x,
+        y: y,
+        loudness: loudness,
+    });
+}
+
+/// Let every monster within earshot of the noises made since they last
+/// acted hear them.
+pub fn spread_noises(game: &mut Game, objects: &mut [Object]) {
+    let noises: Vec<Noise> = game.noises.drain(..).collect();
+    for noise in noises {
+        let map = &game.map;
+        let reach = noise.loudness as i32;
+        let distances = pathfinding::distance_map((noise.x, noise.y), |x, y| {
+            let on_map = x >= 0 && y >= 0 && x < config().map.width && y < config().map.height;
+            let in_reach = (x - noise.x).abs() <= reach && (y - noise.y).abs() <= reach;
+            if !on_map || !in_reach {
+                return None;
+            }
+            let tile = &map[x as usize][y as usize];
+            if !tile.blocked {
+                Some(1)
+            } else if tile.door {
+                Some(1 + DOOR_MUFFLING)
+            } else {
+                None
+            }
+        });
+        for (id, object) in objects.iter_mut().enumerate() {
+            let heard = distances
+                .get(&object.pos())
+                .map_or(false, |&distance| distance <= noise.loudness);
+            if id != PLAYER && object.alive && heard {
+                object.alert(noise.x, noise.y);
+            }
+        }
+    }
+}
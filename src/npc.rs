@@ -0,0 +1,191 @@
+//! Non-player characters and their dialogue, read from `dialogue/`.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::Ai;
+use crate::game::Game;
+use crate::items::{Item, create_item};
+use crate::locale::{display_name, translate};
+use crate::object::{Object, PLAYER};
+use crate::quest::{Quest, offer_quest, start_quest};
+use crate::save::read_json;
+use crate::shop::{open_shop, serve_customer, turn_hostile};
+use crate::ui::{Tcod, menu};
+
+pub const DIALOGUE_DIR: &str = "dialogue";
+
+/// Someone the player can talk to instead of fight.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Npc {
+    /// A quest this NPC offers, until the player accepts it.
+    pub quest: Option<Quest>,
+    /// Index into `Game::quests` of the quest the player accepted from this NPC.
+    pub given: Option<usize>,
+    /// Items this NPC is willing to trade.
+    pub wares: Vec<Item>,
+    /// Whether this NPC keeps the shop on the current level.
+    pub shopkeeper: bool,
+}
+
+/// Talk to an NPC, following its dialogue if it has one.
+pub fn talk_to(npc_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    if objects[npc_id].ai == Some(Ai::Follow) {
+        // Trade places so a companion can't wall the player into a corridor.
+        let player_pos = objects[PLAYER].pos();
+        let npc_pos = objects[npc_id].pos();
+        objects[PLAYER].set_pos(npc_pos.0, npc_pos.1);
+        objects[npc_id].set_pos(player_pos.0, player_pos.1);
+        return;
+    }
+
+    if objects[npc_id].npc.as_ref().map_or(false, |npc| npc.shopkeeper) {
+        serve_customer(npc_id, tcod, game, objects);
+        return;
+    }
+
+    match Dialogue::load(&objects[npc_id].name) {
+        Some(dialogue) => run_dialogue(&dialogue, npc_id, tcod, game, objects),
+        None => offer_quest(npc_id, tcod, game, objects),
+    }
+}
+
+/// A conversation, as a graph of nodes loaded from a data file.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Dialogue {
+    pub start: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueNode {
+    /// Locale key of what the NPC says.
+    pub text: String,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueChoice {
+    /// Locale key of what the player says.
+    pub text: String,
+    /// The choice is only offered if all of these hold.
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    /// The node to go to next. The conversation ends if there is none.
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    HasItem(String),
+    QuestState(QuestState),
+}
+
+/// Where the player stands with the quest an NPC offers.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuestState {
+    None,
+    Offered,
+    Active,
+    Completed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    StartQuest,
+    OpenShop,
+    TurnHostile,
+}
+
+impl Dialogue {
+    pub fn load(npc: &str) -> Option<Self> {
+        read_json(&format!("{}/{}.json", DIALOGUE_DIR, npc))
+    }
+}
+
+impl Condition {
+    pub fn holds(&self, npc_id: usize, game: &Game, objects: &[Object]) -> bool {
+        match *self {
+            Condition::HasItem(ref item) => game.inventory.iter().any(|object| object.name == *item),
+            Condition::QuestState(state) => quest_state(npc_id, game, objects) == state,
+        }
+    }
+}
+
+pub fn quest_state(npc_id: usize, game: &Game, objects: &[Object]) -> QuestState {
+    match objects[npc_id].npc {
+        Some(Npc { quest: Some(_), .. }) => QuestState::Offered,
+        Some(Npc { given: Some(index), .. }) if game.quests[index].completed => QuestState::Completed,
+        Some(Npc { given: Some(_), .. }) => QuestState::Active,
+        _ => QuestState::None,
+    }
+}
+
+/// The placeholders dialogue text can use: the NPC's name, and the
+/// description and reward of its quest.
+pub fn dialogue_args(npc_id: usize, game: &Game, objects: &[Object]) -> (String, String, String) {
+    let name = display_name(&objects[npc_id].name);
+    let quest = match objects[npc_id].npc {
+        Some(Npc { quest: Some(ref quest), .. }) => Some(quest),
+        Some(Npc { given: Some(index), .. }) => game.quests.get(index),
+        _ => None,
+    };
+    let description = quest.map_or_else(String::new, |quest| quest.description());
+    let reward = quest
+        .and_then(|quest| quest.reward)
+        .map_or_else(String::new, |reward| display_name(&create_item(reward, 0, 0).name));
+    (name, description, reward)
+}
+
+pub fn run_dialogue(
+    dialogue: &Dialogue,
+    npc_id: usize,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) {
+    let mut node_id = dialogue.start.clone();
+    while let Some(node) = dialogue.nodes.get(&node_id) {
+        let (name, description, reward) = dialogue_args(npc_id, game, objects);
+        let args: [&dyn Display; 3] = [&name, &description, &reward];
+        let text = translate(&node.text, &args);
+
+        let choices: Vec<&DialogueChoice> = node
+            .choices
+            .iter()
+            .filter(|choice| choice.conditions.iter().all(|c| c.holds(npc_id, game, objects)))
+            .collect();
+        let options: Vec<String> = choices
+            .iter()
+            .map(|choice| translate(&choice.text, &args))
+            .collect();
+        let choice = match menu(&text, &options, 50, &mut *tcod.ui) {
+            Some(index) => choices[index],
+            None => return,
+        };
+
+        for effect in &choice.effects {
+            match *effect {
+                Effect::StartQuest => start_quest(npc_id, game, objects),
+                Effect::OpenShop => open_shop(npc_id, tcod, game, objects),
+                Effect::TurnHostile => {
+                    turn_hostile(npc_id, game, objects);
+                    return;
+                }
+            }
+        }
+        match choice.next {
+            Some(ref next) => node_id = next.clone(),
+            None => return,
+        }
+    }
+}
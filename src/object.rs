@@ -0,0 +1,779 @@
+//! Objects: the player, monsters, items, the stairs and everything else
+//! drawn on the map, with the fighting that goes on between them.
+
+use std::cmp;
+
+use rand::{Rng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::Ai;
+use crate::audio::{Sound, SoundEffect};
+use crate::backend::{Layer, Renderer};
+use crate::bones::{BONES_CHANCE, Bones};
+use crate::combat::{dice_of, roll_blow};
+use crate::digging::{dig, is_diggable, wields_pickaxe};
+use crate::equipment::Equipment;
+use crate::branch::Branch;
+use crate::faction::{Faction, Standing, standing};
+use crate::fov::Fov;
+use crate::game::{Game, GameEvent, GameMode};
+use crate::items::{Item, add_to_inventory, has_room_for, stack_name};
+use crate::locale::display_name;
+use crate::locks::try_lock;
+use crate::map::{
+    Map, Terrain, is_closed_door, is_locked_door, map_blocked, on_map, set_door, travel_cost,
+};
+use crate::messages::MessageCategory;
+use crate::noise::{COMBAT_NOISE, make_noise};
+use crate::npc::{Npc, talk_to};
+use crate::pathfinding;
+use crate::render::Animation;
+use crate::scavenge::spill_inventory;
+use crate::sleep::SNEAK_ATTACK_MULTIPLIER;
+use crate::status::{CONFUSED_STUMBLE_CHANCE, StatusEffect, StatusKind, random_direction};
+use crate::tcod_types::colors::*;
+use crate::tcod_types::console::*;
+use crate::traps::spring_trap;
+use crate::ui::{Danger, Tcod, confirm, menu};
+use crate::water::flounders;
+
+pub const PLAYER: usize = 0; // Player will always be the first object
+
+// Experience needed to go up from level N is LEVEL_UP_BASE + N * LEVEL_UP_FACTOR.
+pub const LEVEL_UP_BASE: i32 = 200;
+pub const LEVEL_UP_FACTOR: i32 = 150;
+pub const LEVEL_SCREEN_WIDTH: i32 = 40;
+pub const LEVEL_UP_HP: i32 = 10;
+// How long a giant spider's bite keeps poisoning.
+pub const SPIDER_VENOM_TURNS: i32 = 5;
+// How far a skeleton archer can shoot.
+pub const ARCHER_RANGE: i32 = 6;
+
+/// This is a generic object: the player, a monster, an item, the stairs...
+/// It is always represented by a character on screen.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Object {
+    pub x: i32,
+    pub y: i32,
+    pub char: char,
+    pub color: Color,
+    pub name: String,
+    pub blocks: bool,
+    pub alive: bool,
+    pub fighter: Option<Fighter>,
+    pub ai: Option<Ai>,
+    pub item: Option<Item>,
+    pub npc: Option<Npc>,
+    pub faction: Option<Faction>,
+    /// Unpaid shop goods carry their price.
+    pub price: Option<u32>,
+    /// Character level; only the player gains levels.
+    pub level: i32,
+    pub effects: Vec<StatusEffect>,
+    /// How many of an item make up the stack; 1 for anything else.
+    #[serde(default = "single")]
+    pub count: u32,
+    /// Charges left in a wand, or turns of fuel left in a light.
+    #[serde(default)]
+    pub charges: u32,
+    #[serde(default)]
+    pub equipment: Option<Equipment>,
+    /// Crosses deep water safely, and at full speed.
+    #[serde(default)]
+    pub swims: bool,
+    /// How far the object's own light reaches, if it gives any.
+    #[serde(default)]
+    pub glow: i32,
+    /// Has the wits to pick up what it finds, and to use it.
+    #[serde(default)]
+    pub intelligent: bool,
+    /// What a monster has picked up, to be dropped where it dies.
+    #[serde(default)]
+    pub inventory: Vec<Object>,
+}
+
+fn single() -> u32 {
+    1
+}
+
+impl Object {
+    pub fn new(x: i32, y: i32, char: char, name: &str, color: Color, blocks: bool) -> Self {
+        Object {
+            x: x,
+            y: y,
+            char: char,
+            color: color,
+            name: name.into(),
+            blocks: blocks,
+            alive: false,
+            fighter: None,
+            ai: None,
+            item: None,
+            npc: None,
+            faction: None,
+            price: None,
+            level: 1,
+            effects: vec![],
+            count: 1,
+            charges: 0,
+            equipment: None,
+            swims: false,
+            glow: 0,
+            intelligent: false,
+            inventory: vec![],
+        }
+    }
+
+    pub fn pos(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
+    pub fn set_pos(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    /// set the color and then draw the character that represents this object at its position,
+    /// on the map layer
+    pub fn draw(&self, ui: &mut dyn Renderer) {
+        ui.set_default_foreground(Layer::Map, self.color);
+        ui.put_char(Layer::Map, self.x, self.y, self.char, BackgroundFlag::None);
+    }
+
+    pub fn take_damage(&mut self, damage: i32, game: &mut Game) {
+        // Nobody sleeps through being hurt.
+        self.wake_up();
+        // Apply damage if possible
+        if let Some(fighter) = self.fighter.as_mut() {
+            fighter.hp -= damage;
+        }
+        // Check for death and call the on_death callback.
+        if let Some(fighter) = self.fighter {
+            if fighter.hp <= 0 {
+                self.alive = false;
+                fighter.on_death.callback(self, game);
+            }
+        }
+    }
+
+    /// Take damage from the dungeon itself: deep water, lava or a trap.
+    /// Whatever dies of it is nobody's kill, least of all the player's.
+    pub fn take_dungeon_damage(&mut self, damage: i32, game: &mut Game) {
+        game.infighting = true;
+        self.take_damage(damage, game);
+        game.infighting = false;
+    }
+
+    pub fn attack(&mut self, target: &mut Object, game: &mut Game) {
+        self.strike(target, game, "attack-hit", "attack-no-effect");
+    }
+
+    /// Attack from a distance, with an arrow or whatever else comes to hand.
+    pub fn shoot(&mut self, target: &mut Object, game: &mut Game) {
+        game.animations.push(Animation::Projectile {
+            from: self.pos(),
+            to: target.pos(),
+            char: '*',
+            color: LIGHTEST_SEPIA,
+        });
+        self.strike(target, game, "shoot-hit", "shoot-no-effect");
+    }
+
+    /// Deal damage to the target, telling what happened with the given messages.
+    fn strike(&mut self, target: &mut Object, game: &mut Game, hit: &str, no_effect: &str) {
+        let is_player = |object: &Object| {
+            object.fighter.map_or(false, |f| f.on_death == DeathCallback::Player)
+        };
+        if is_player(target) {
+            game.last_attacker = Some(self.name.clone());
+        }
+        let infighting = !is_player(self) && !is_player(target);
+        let blow = roll_blow(
+            self.fighter.map_or(0, |f| f.power),
+            dice_of(self, game),
+            target.fighter.map_or(0, |f| f.defense),
+            &mut game.rng,
+        );
+        let mut damage = blow.damage;
+        // A sleeping target is caught unawares.
+        let unawares = target.is_asleep();
+        target.wake_up();
+        make_noise(game, target.x, target.y, COMBAT_NOISE);
+        if unawares {
+            damage *= SNEAK_ATTACK_MULTIPLIER;
+        }
+        if damage > 0 {
+            // Make target take some damage
+            let message = if unawares {
+                "sneak-attack-hit"
+            } else if blow.critical {
+                "critical-hit"
+            } else {
+                hit
+            };
+            let category = if blow.critical {
+                MessageCategory::Critical
+            } else {
+                MessageCategory::Combat
+            };
+            game.messages.add(
+                tr!(
+                    message,
+                    display_name(&self.name),
+                    display_name(&target.name),
+                    damage
+                ),
+                category
+            );
+            game.sounds.push(Sound {
+                effect: SoundEffect::Hit,
+                x: target.x,
+                y: target.y,
+            });
+            game.infighting = infighting;
+            target.take_damage(damage, game);
+            game.infighting = false;
+            if let Some(venom) = self.fighter.and_then(|f| f.venom) {
+                if target.alive {
+                    target.add_effect(venom);
+                }
+            }
+        } else {
+            game.messages.add(
+                tr!(
+                    no_effect,
+                    display_name(&self.name),
+                    display_name(&target.name)
+                ),
+                MessageCategory::Combat,
+            );
+        }
+    }
+
+    /// Heal by the give amount, withoug going over the maximum. 
+    pub fn heal(&mut self, amount: i32) {
+        if let Some(ref mut fighter) = self.fighter {
+            fighter.hp += amount;
+            if fighter.hp > fighter.max_hp {
+                fighter.hp = fighter.max_hp;
+            }
+        }
+    }
+
+    // move by the given amount, if the destination is not blocked; closed doors get opened
+    pub fn move_by(
+        id: usize,
+        dx: i32,
+        dy: i32,
+        fov: &Fov,
+        game: &mut Game,
+        objects: &mut [Object],
+    ) {
+        let (x, y) = objects[id].pos();
+        if flounders(id, game, objects) {
+            // Struggling in the water, and getting nowhere.
+        } else if is_locked_door(x + dx, y + dy, &game.map) {
+            // Nobody walks through a locked door; the player has to try its lock.
+        } else if is_closed_door(x + dx, y + dy, &game.map) {
+            set_door(x + dx, y + dy, true, &mut game.map);
+        } else if !Object::is_blocked(x + dx, y + dy, &game.map, objects) {
+            objects[id].set_pos(x + dx, y + dy);
+            spring_trap(id, fov, game, objects);
+        }
+    }
+
+    /// Step to whichever neighbouring tile is furthest from the given spot.
+    /// Returns false if there is nowhere further away to go.
+    pub fn move_away(
+        id: usize,
+        from_x: i32,
+        from_y: i32,
+        fov: &Fov,
+        game: &mut Game,
+        objects: &mut [Object],
+    ) -> bool {
+        let (x, y) = objects[id].pos();
+        let distance = |x: i32, y: i32| (x - from_x).pow(2) + (y - from_y).pow(2);
+        let step = (-1..2)
+            .flat_map(|dx| (-1..2).map(move |dy| (dx, dy)))
+            .filter(|&(dx, dy)| !Object::is_blocked(x + dx, y + dy, &game.map, objects))
+            .max_by_key(|&(dx, dy)| distance(x + dx, y + dy))
+            .filter(|&(dx, dy)| distance(x + dx, y + dy) > distance(x, y));
+        match step {
+            Some((dx, dy)) => {
+                Object::move_by(id, dx, dy, fov, game, objects);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_blocked(x: i32, y: i32, map: &Map, objects: &[Object]) -> bool {
+        // First test map tile, off the map being as good as a wall
+        if map_blocked(x, y, map) {
+            return true;
+        }
+
+        // Now check for any blocking objects
+        objects
+            .iter()
+            .any(|object| object.blocks && object.pos() == (x, y))
+    }
+
+    /// Returns false if the player thought better of it, and did nothing.
+    pub fn player_move_or_attack(
+        dx: i32,
+        dy: i32,
+        tcod: &mut Tcod,
+        game: &mut Game,
+        objects: &mut Vec<Object>,
+    ) -> bool {
+        // A confused player often goes some other way than they meant to.
+        let confused = objects[PLAYER].has_effect(StatusKind::Confusion);
+        let (dx, dy) = if confused && game.rng.next_f32() < CONFUSED_STUMBLE_CHANCE {
+            game.messages.add(tr!("status.player-stumbles"), MessageCategory::Warning);
+            random_direction(&mut game.rng)
+        } else {
+            (dx, dy)
+        };
+
+        // Coordinates the player is moving to or attacking
+        let x = objects[PLAYER].x + dx;
+        let y = objects[PLAYER].y + dy;
+
+        // Try to find an attackable object there
+        let target_id = objects
+            .iter()
+            .position(|object| object.fighter.is_some() && object.pos() == (x, y));
+        // ...or someone to talk to
+        let npc_id = objects
+            .iter()
+            .position(|object| object.npc.is_some() && object.pos() == (x, y));
+
+        // Friends make way instead of being attacked
+        let friend = target_id.map_or(false, |id| {
+            objects[id].ai == Some(Ai::Idle)
+                && objects[id].faction.map(|f| standing(game, f)) == Some(Standing::Friendly)
+        });
+
+        // Attack if target found, talk to NPCs, move otherwise
+        match (target_id, npc_id) {
+            (Some(target_id), _) if friend => {
+                let (player_x, player_y) = objects[PLAYER].pos();
+                objects[target_id].set_pos(player_x, player_y);
+                objects[PLAYER].set_pos(x, y);
+            },
+            (Some(target_id), _) => {
+                let peaceful = matches!(
+                    objects[target_id].ai.as_ref().map(Ai::awake),
+                    Some(&Ai::Idle) | Some(&Ai::Follow)
+                );
+                if peaceful {
+                    let question = tr!("confirm.attack-peaceful", display_name(&objects[target_id].name));
+                    if !confirm(tcod, game, Danger::AttackPeaceful, &question) {
+                        return false;
+                    }
+                    // Provoked!
+                    objects[target_id].ai = Some(Ai::Basic);
+                }
+                let (monster, player) = mut_two(target_id, PLAYER, objects);
+                player.attack(monster, game);
+            },
+            (None, Some(npc_id)) => {
+                talk_to(npc_id, tcod, game, objects);
+            },
+            (None, None) if is_locked_door(x, y, &game.map) => {
+                try_lock(x, y, game, objects);
+            },
+            (None, None) if wields_pickaxe(game) && is_diggable(x, y, &game.map) => {
+                dig(x, y, tcod, &mut game.map);
+                game.messages.add(tr!("dig.pickaxe"), MessageCategory::Item);
+            },
+            (None, None) => {
+                // Off the edge of the map there is nothing to look up, and
+                // nowhere to go.
+                let (px, py) = objects[PLAYER].pos();
+                let into_lava = on_map(x, y)
+                    && game.map[x as usize][y as usize].terrain == Terrain::Lava
+                    && game.map[px as usize][py as usize].terrain != Terrain::Lava;
+                if into_lava && !confirm(tcod, game, Danger::EnterLava, &tr!("confirm.lava")) {
+                    return false;
+                }
+                let known_trap = if on_map(x, y) {
+                    game.map[x as usize][y as usize].trap.filter(|trap| !trap.hidden)
+                } else {
+                    None
+                };
+                if let Some(trap) = known_trap {
+                    let question = tr!("confirm.trap", trap.kind.name());
+                    if !confirm(tcod, game, Danger::EnterTrap, &question) {
+                        return false;
+                    }
+                }
+                if is_closed_door(x, y, &game.map) {
+                    game.messages.add(tr!("door.opened"), MessageCategory::Lore);
+                }
+                Object::move_by(PLAYER, dx, dy, &tcod.fov, game, objects);
+            }
+        }
+        true
+    }
+
+    /// Take a step along the cheapest path to the target, or straight at it
+    /// if there is no path.
+    pub fn move_towards(
+        id: usize,
+        target_x: i32,
+        target_y: i32,
+        fov: &Fov,
+        game: &mut Game,
+        objects: &mut [Object],
+    ) {
+        let from = objects[id].pos();
+        let target = (target_x, target_y);
+        let swims = objects[id].swims;
+        let path = pathfinding::astar(from, target, |x, y| {
+            // Those who cannot swim stay out of deep water.
+            if !swims && on_map(x, y) && game.map[x as usize][y as usize].terrain == Terrain::DeepWater {
+                return None;
+            }
+            // The target itself is usually occupied, by whoever is being chased.
+            travel_cost(x, y, &game.map, objects).or(if (x, y) == target { Some(1) } else { None })
+        });
+        if let Some(&(x, y)) = path.as_ref().and_then(|path| path.first()) {
+            Object::move_by(id, x - from.0, y - from.1, fov, game, objects);
+            return;
+        }
+
+        // Get vector from this object's tile to the target tile and total distance
+        let dx = target_x - objects[id].x;
+        let dy = target_y - objects[id].y;
+        let distance = ((dx.pow(2) + dy.pow(2)) as f32).sqrt();
+
+        // Normalize vector, then round it and convert it to `i32` to get grid movement
+        let dx = (dx as f32 / distance).round() as i32; 
+        let dy = (dy as f32 / distance).round() as i32;
+        Object::move_by(id, dx, dy, fov, game, objects);
+    }
+
+    /// Return the distance to another object
+    pub fn distance_to(&self, other: &Object) -> f32 {
+        let dx = other.x - self.x;
+        let dy = other.y - self.y;
+        ((dx.pow(2) + dy.pow(2)) as f32).sqrt()
+    }
+
+    /// Add to the player's inventory and remove from the map. 
+    pub fn pick_item_up(object_id: usize, game: &mut Game, objects: &mut Vec<Object>) {
+        if !has_room_for(&game.inventory, &objects[object_id]) {
+            game.messages.add(
+                tr!("inventory-full", stack_name(&objects[object_id], &game.identification)),
+                MessageCategory::Warning,
+            );
+        } else {
+            let item = objects.swap_remove(object_id);
+            let name = stack_name(&item, &game.identification);
+            game.messages.add(tr!("picked-up", name), MessageCategory::Item);
+            game.events.push(GameEvent::ItemPickedUp {
+                name: item.name.clone(),
+            });
+            game.sounds.push(Sound {
+                effect: SoundEffect::PickUp,
+                x: item.x,
+                y: item.y,
+            });
+            if let Some(price) = item.price {
+                game.messages.add(tr!("shop.for-sale", price), MessageCategory::Item);
+            }
+            let _ = add_to_inventory(&mut game.inventory, item);
+        }
+    }
+
+    /// Find closest enemy, up to a maximum range, and in the player's FOV. 
+    pub fn closest_monster(tcod: &Tcod, objects: &[Object], max_range: i32) -> Option<usize> {
+        let mut closest_enemy = None;
+        let mut closest_dist = (max_range + 1) as f32; // Start with (slightly more than) maximum range. 
+
+        for (id, object) in objects.iter().enumerate() {
+            if (id != PLAYER)
+                && object.fighter.is_some()
+                && object.ai.is_some()
+                && tcod.fov.is_in_fov(object.x, object.y)
+            {
+                // Calculate distance between this object and the player. 
+                let dist = objects[PLAYER].distance_to(object);
+                if dist < closest_dist {
+                    // It is closer, so remember it. 
+                    closest_enemy = Some(id);
+                    closest_dist = dist;
+                }
+            }
+        }
+        closest_enemy
+    }
+}
+
+/// Combat-related component
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fighter {
+    pub max_hp: i32,
+    pub hp: i32,
+    pub defense: i32,
+    pub power: i32,
+    /// Experience the player has gained, or that a monster is worth when killed.
+    pub xp: i32,
+    /// What a hit from this fighter leaves behind, such as poison.
+    pub venom: Option<StatusEffect>,
+    pub on_death: DeathCallback,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DeathCallback {
+    Player,
+    Monster,
+}
+
+impl DeathCallback {
+    pub fn callback(self, object: &mut Object, game: &mut Game) {
+        use DeathCallback::*;
+        let callback: fn(&mut Object, game: &mut Game) = match self {
+            Player => player_death,
+            Monster => monster_death,
+        };
+        callback(object, game);
+    }
+}
+
+/// Experience the player needs to go up from the given level.
+pub fn level_up_xp(level: i32) -> i32 {
+    LEVEL_UP_BASE + level * LEVEL_UP_FACTOR
+}
+
+/// Once the player has enough experience, raise their level and let them
+/// pick which stat improves. The bot picks for itself in demos.
+pub fn level_up(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
+    let player = &mut objects[PLAYER];
+    let mut fighter = match player.fighter {
+        Some(fighter) => fighter,
+        None => return,
+    };
+    while fighter.xp >= level_up_xp(player.level) {
+        fighter.xp -= level_up_xp(player.level);
+        player.level += 1;
+        game.messages.add(tr!("level-up", player.level), MessageCategory::Lore);
+        game.write_journal(tr!("journal.level", player.level));
+
+        let choice = if game.mode == GameMode::Demo {
+            player.level as usize % 3
+        } else {
+            let choices = &[
+                tr!("level-up.constitution", LEVEL_UP_HP, fighter.max_hp),
+                tr!("level-up.strength", fighter.power),
+                tr!("level-up.agility", fighter.defense),
+            ];
+            // The choice can't be put off, unless the window is gone (or a
+            // headless script ran out), in which case constitution it is.
+            loop {
+                if let Some(choice) = menu(&tr!("level-up.choose"), choices, LEVEL_SCREEN_WIDTH, &mut *tcod.ui) {
+                    break choice;
+                }
+                if tcod.ui.window_closed() {
+                    break 0;
+                }
+            }
+        };
+        match choice {
+            0 => {
+                fighter.max_hp += LEVEL_UP_HP;
+                fighter.hp += LEVEL_UP_HP;
+            }
+            1 => fighter.power += 1,
+            _ => fighter.defense += 1,
+        }
+    }
+    player.fighter = Some(fighter);
+}
+
+pub fn player_death(player: &mut Object, game: &mut Game) {
+    // The game ended!
+    game.messages.add(tr!("player-died"), MessageCategory::Warning);
+
+    // Sometimes leave bones for a future run to find. Daily challenges never do,
+    // since every player must face the same dungeon.
+    if game.mode == GameMode::Normal && game.rng.next_f32() < BONES_CHANCE {
+        let bones = Bones::new(player, game);
+        // Losing the bones only means a later run will not meet this ghost.
+        let _ = bones.save();
+    }
+
+    // For added effect, transform the player into a corpse!
+    player.char = '%';
+    player.color = DARKER_RED;
+}
+
+pub fn monster_death(monster: &mut Object, game: &mut Game) {
+    // Transform it into a nasty corpse! It does not block,
+    // it cannot be attacked, and it does not move. 
+    game.messages.add(tr!("monster-died", display_name(&monster.name)), MessageCategory::Combat);
+    spill_inventory(monster, game);
+    let by_player = !game.infighting;
+    if by_player {
+        game.kills += 1;
+        *game.kill_counts.entry(monster.name.clone()).or_insert(0) += 1;
+        game.bestiary.record_kill(&monster.name);
+    }
+    game.events.push(GameEvent::MonsterKilled {
+        name: monster.name.clone(),
+        faction: monster.faction,
+        xp: monster.fighter.map_or(0, |f| f.xp),
+        pos: monster.pos(),
+        by_player: by_player,
+    });
+    game.sounds.push(Sound {
+        effect: SoundEffect::Death,
+        x: monster.x,
+        y: monster.y,
+    });
+    game.animations.push(Animation::Flash {
+        x: monster.x,
+        y: monster.y,
+        color: LIGHT_RED,
+    });
+    monster.char = '%';
+    monster.color = DARKER_RED;
+    monster.blocks = false;
+    monster.fighter = None;
+    monster.ai = None;
+    monster.name = tr!("remains-of", display_name(&monster.name));
+}
+
+/// Create a monster picked from the branch's spawn table.
+pub fn random_monster(x: i32, y: i32, branch: Branch, rng: &mut StdRng) -> Object {
+    let table = branch.monsters();
+    let mut dice = rng.next_f32();
+    // Anything the chances do not add up to goes to the last monster.
+    let mut name = table[table.len() - 1].0;
+    for &(monster, chance) in table {
+        if dice < chance {
+            name = monster;
+            break;
+        }
+        dice -= chance;
+    }
+    create_monster(name, x, y)
+}
+
+/// Create a common monster of the given kind.
+pub fn create_monster(name: &str, x: i32, y: i32) -> Object {
+    let mut monster = if name == "orc" {
+        // Create an orc
+        let mut orc = Object::new(x, y, 'o', "orc", DESATURATED_GREEN, true);
+        orc.fighter = Some(Fighter {
+            max_hp: 10,
+            hp: 10,
+            defense: 0,
+            power: 3,
+            xp: 35,
+            venom: None,
+            on_death: DeathCallback::Monster,
+        });
+        orc.ai = Some(Ai::Basic);
+        orc.faction = Some(Faction::Orcs);
+        orc.intelligent = true;
+        orc
+    } else if name == "giant spider" {
+        // Weak, but its bite is poisonous
+        let mut spider = Object::new(x, y, 's', "giant spider", DARK_AMBER, true);
+        spider.fighter = Some(Fighter {
+            max_hp: 6,
+            hp: 6,
+            defense: 0,
+            power: 2,
+            xp: 40,
+            venom: Some(StatusEffect {
+                kind: StatusKind::Poison,
+                turns: SPIDER_VENOM_TURNS,
+                strength: 1,
+            }),
+            on_death: DeathCallback::Monster,
+        });
+        spider.ai = Some(Ai::Basic);
+        spider
+    } else if name == "orc shaman" {
+        // Frail, but never fights alone for long
+        let mut shaman = Object::new(x, y, 'o', "orc shaman", LIGHT_VIOLET, true);
+        shaman.fighter = Some(Fighter {
+            max_hp: 8,
+            hp: 8,
+            defense: 0,
+            power: 2,
+            xp: 60,
+            venom: None,
+            on_death: DeathCallback::Monster,
+        });
+        shaman.ai = Some(Ai::Summoner { cooldown: 0 });
+        shaman.faction = Some(Faction::Orcs);
+        shaman.intelligent = true;
+        shaman
+    } else if name == "jackal" {
+        // Feeble alone, but never alone
+        let mut jackal = Object::new(x, y, 'j', "jackal", SEPIA, true);
+        jackal.fighter = Some(Fighter {
+            max_hp: 4,
+            hp: 4,
+            defense: 0,
+            power: 2,
+            xp: 15,
+            venom: None,
+            on_death: DeathCallback::Monster,
+        });
+        jackal.ai = Some(Ai::Pack);
+        jackal
+    } else if name == "skeleton archer" {
+        // Frail, but shoots from afar
+        let mut archer = Object::new(x, y, 'k', "skeleton archer", LIGHTEST_SEPIA, true);
+        archer.fighter = Some(Fighter {
+            max_hp: 8,
+            hp: 8,
+            defense: 0,
+            power: 3,
+            xp: 50,
+            venom: None,
+            on_death: DeathCallback::Monster,
+        });
+        archer.ai = Some(Ai::Ranged { range: ARCHER_RANGE });
+        archer
+    } else {
+        let mut troll = Object::new(x, y, 'T', "troll", DARKER_GREEN, true);
+        troll.fighter = Some(Fighter {
+            max_hp: 16,
+            hp: 16,
+            defense: 1,
+            power: 4,
+            xp: 100,
+            venom: None,
+            on_death: DeathCallback::Monster,
+        });
+        troll.ai = Some(Ai::Basic);
+        troll.faction = Some(Faction::Trolls);
+        troll.swims = true;
+        troll
+    };
+
+    monster.alive = true;
+    monster
+}
+
+/// Mutably borrow two *separate* elements from the given slice.
+/// Panics when the indices are equal or out of bounds. 
+pub fn mut_two<T>(first_index: usize, second_index: usize, items: &mut [T]) -> (&mut T, &mut T) {
+    assert_ne!(first_index, second_index);
+    let split_at_index = cmp::max(first_index, second_index);
+    let (first_slice, second_slice) = items.split_at_mut(split_at_index);
+    if first_index < second_index {
+        (&mut first_slice[first_index], &mut second_slice[0])
+    } else {
+        (&mut second_slice[0], &mut first_slice[second_index])
+    }
+}
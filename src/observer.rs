@@ -0,0 +1,136 @@
+//! A local HTTP endpoint serving the visible game state as JSON, when built
+//! with the `observer` feature. Headless runs print the same state.
+
+use serde::Serialize;
+
+use crate::fov::Fov;
+use crate::game::Game;
+use crate::items::stack_name;
+use crate::object::{Object, PLAYER};
+use crate::render::explored_map_text;
+
+/// Where the observer API listens. It is only reachable from this machine.
+#[cfg(feature = "observer")]
+pub const OBSERVER_ADDRESS: &str = "127.0.0.1:8787";
+/// Newest messages included in the observed state.
+pub const OBSERVED_MESSAGES: usize = 20;
+
+/// What the player can currently see and knows of the game, as served by the
+/// observer API and printed by headless runs.
+#[derive(Serialize)]
+pub struct Observation<'a> {
+    pub turn: u32,
+    pub depth: u32,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub gold: u32,
+    pub kills: u32,
+    /// The explored map, one string per row, with the items and stairs the
+    /// player has seen on it.
+    pub map: Vec<String>,
+    /// Everything in view, the player first.
+    pub entities: Vec<ObservedEntity>,
+    pub messages: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+pub struct ObservedEntity {
+    /// As the player knows it, so unidentified items go by their looks.
+    pub name: String,
+    pub char: char,
+    pub x: i32,
+    pub y: i32,
+    pub hp: Option<i32>,
+}
+
+impl<'a> Observation<'a> {
+    pub fn of(game: &'a Game, objects: &'a [Object], fov: &Fov) -> Self {
+        let fighter = objects[PLAYER].fighter;
+        Observation {
+            turn: game.turn,
+            depth: game.dungeon_level,
+            hp: fighter.map_or(0, |f| f.hp),
+            max_hp: fighter.map_or(0, |f| f.max_hp),
+            gold: game.gold,
+            kills: game.kills,
+            map: explored_map_text(game, objects).lines().map(String::from).collect(),
+            entities: objects
+                .iter()
+                .filter(|object| fov.is_in_fov(object.x, object.y))
+                .map(|object| ObservedEntity {
+                    name: stack_name(object, &game.identification),
+                    char: object.char,
+                    x: object.x,
+                    y: object.y,
+                    hp: object.fighter.map(|f| f.hp),
+                })
+                .collect(),
+            messages: game
+                .messages
+                .iter()
+                .rev()
+                .take(OBSERVED_MESSAGES)
+                .map(|(msg, _)| msg.as_str())
+                .collect(),
+        }
+    }
+}
+
+/// A small read-only HTTP server for stream overlays, map viewers and
+/// outside bots. It answers every GET with the latest observed state as JSON,
+/// which the game updates every frame; it never touches the game itself.
+#[cfg(feature = "observer")]
+pub struct Observer {
+    pub state: std::sync::Arc<std::sync::Mutex<String>>,
+}
+
+#[cfg(feature = "observer")]
+impl Observer {
+    // Not `Default`, since it starts a server.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let state = std::sync::Arc::new(std::sync::Mutex::new("null".to_string()));
+        let served = state.clone();
+        // If the address is taken, the game just goes without the API.
+        if let Ok(server) = tiny_http::Server::http(OBSERVER_ADDRESS) {
+            std::thread::spawn(move || {
+                let json_type = "Content-Type: application/json".parse::<tiny_http::Header>().ok();
+                for request in server.incoming_requests() {
+                    let response = if *request.method() == tiny_http::Method::Get {
+                        let body = served.lock().map(|state| state.clone()).unwrap_or_default();
+                        let mut response = tiny_http::Response::from_string(body);
+                        if let Some(ref header) = json_type {
+                            response.add_header(header.clone());
+                        }
+                        response
+                    } else {
+                        tiny_http::Response::from_string("").with_status_code(405)
+                    };
+                    let _ = request.respond(response);
+                }
+            });
+        }
+        Observer { state: state }
+    }
+
+    pub fn publish(&mut self, game: &Game, objects: &[Object], fov: &Fov) {
+        let observation = Observation::of(game, objects, fov);
+        if let (Ok(json), Ok(mut state)) = (serde_json::to_string(&observation), self.state.lock()) {
+            *state = json;
+        }
+    }
+}
+
+/// Stands in for the observer API when the game is built without it.
+#[cfg(not(feature = "observer"))]
+#[derive(Default)]
+pub struct Observer;
+
+#[cfg(not(feature = "observer"))]
+impl Observer {
+    pub fn new() -> Self {
+        Observer
+    }
+
+    pub fn publish(&mut self, _game: &Game, _objects: &[Object], _fov: &Fov) {}
+}
@@ -0,0 +1,124 @@
+//! Submitting runs to the score server and fetching its leaderboard, when
+//! built with the `online` feature.
+
+use std::cmp;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::daily::today;
+use crate::game::{Game, GameMode};
+use crate::locale::display_name;
+use crate::object::{Object, PLAYER};
+use crate::scores::run_score;
+use crate::ui::{Tcod, msgbox};
+
+#[cfg(feature = "online")]
+pub const ONLINE_TIMEOUT_SECS: u64 = 5;
+
+/// What is sent to the score server at the end of a run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub name: String,
+    pub date: String,
+    /// `"normal"` or `"daily"`.
+    pub mode: String,
+    pub seed: usize,
+    pub score: u32,
+    pub depth: u32,
+    pub kills: u32,
+    /// Name of the monster that killed the player, if one did.
+    pub cause_of_death: Option<String>,
+}
+
+/// Send a summary of the run to the score server, if the player set one.
+/// Only normal runs and daily challenges are submitted.
+pub fn submit_run_summary(tcod: &mut Tcod, game: &Game, objects: &[Object]) {
+    let server = match tcod.settings.score_server {
+        Some(ref server) => server.clone(),
+        None => return,
+    };
+    let (mode, date) = match game.mode {
+        GameMode::Normal => ("normal", today()),
+        GameMode::Daily(ref date) => ("daily", date.clone()),
+        _ => return,
+    };
+    let summary = RunSummary {
+        name: tcod.settings.player_name.clone(),
+        date: date,
+        mode: mode.into(),
+        seed: game.seed,
+        score: run_score(game, objects),
+        depth: game.dungeon_level,
+        kills: game.kills,
+        cause_of_death: if objects[PLAYER].alive {
+            None
+        } else {
+            game.last_attacker.clone()
+        },
+    };
+    if let Err(e) = submit_run(&server, &summary) {
+        msgbox(&format!("\n{}\n", tr!("online.submit-failed", e)), 50, &mut *tcod.ui);
+    }
+}
+
+#[cfg(feature = "online")]
+pub fn submit_run(server: &str, summary: &RunSummary) -> Result<(), Box<dyn Error>> {
+    ureq::post(&format!("{}/runs", server.trim_end_matches('/')))
+        .timeout(std::time::Duration::from_secs(ONLINE_TIMEOUT_SECS))
+        .send_json(summary)?;
+    Ok(())
+}
+
+#[cfg(feature = "online")]
+pub fn fetch_daily_leaderboard(server: &str, date: &str) -> Result<Vec<RunSummary>, Box<dyn Error>> {
+    let runs = ureq::get(&format!("{}/daily/{}", server.trim_end_matches('/'), date))
+        .timeout(std::time::Duration::from_secs(ONLINE_TIMEOUT_SECS))
+        .call()?
+        .into_json()?;
+    Ok(runs)
+}
+
+#[cfg(not(feature = "online"))]
+pub fn submit_run(_server: &str, _summary: &RunSummary) -> Result<(), Box<dyn Error>> {
+    Err(tr!("online.unsupported").into())
+}
+
+#[cfg(not(feature = "online"))]
+pub fn fetch_daily_leaderboard(_server: &str, _date: &str) -> Result<Vec<RunSummary>, Box<dyn Error>> {
+    Err(tr!("online.unsupported").into())
+}
+
+/// Show today's best daily challenge runs from everyone, as reported by the score server.
+pub fn show_daily_leaderboard(tcod: &mut Tcod) {
+    let server = match tcod.settings.score_server {
+        Some(ref server) => server.clone(),
+        None => {
+            msgbox(&format!("\n{}\n", tr!("online.no-server")), 50, &mut *tcod.ui);
+            return;
+        }
+    };
+    let date = today();
+    let text = match fetch_daily_leaderboard(&server, &date) {
+        Ok(mut runs) => {
+            runs.sort_by_key(|run| cmp::Reverse(run.score));
+            let mut text = format!("{}\n\n", tr!("online.leaderboard-title", date));
+            if runs.is_empty() {
+                text.push_str(&format!("{}\n", tr!("daily.no-scores")));
+            }
+            for (rank, run) in runs.iter().take(20).enumerate() {
+                let fate = match run.cause_of_death {
+                    Some(ref killer) => tr!("online.killed-by", display_name(killer)),
+                    None => tr!("daily.survived"),
+                };
+                text.push_str(&format!(
+                    "{}\n",
+                    tr!("online.entry", rank + 1, run.name, run.score, run.depth, fate)
+                ));
+            }
+            text
+        }
+        Err(e) => format!("\n{}\n", tr!("online.fetch-failed", e)),
+    };
+    msgbox(&text, 60, &mut *tcod.ui);
+}
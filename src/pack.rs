@@ -0,0 +1,115 @@
+//! Packs: some beasts, like jackals, are never found alone. A pack sleeps
+//! and wakes together, every member runs to join in once one of them sees
+//! the player, and they spread out around their prey rather than queue up
+//! behind each other.
+
+use rand::{Rng, StdRng};
+
+use crate::ai::Ai;
+use crate::fov::Fov;
+use crate::game::Game;
+use crate::map::{Map, Terrain, map_blocked, travel_cost};
+use crate::object::{Object, PLAYER, create_monster};
+
+pub const PACK_MIN: i32 = 3; // Counting the one the pack is placed around
+pub const PACK_MAX: i32 = 5;
+pub const PACK_SPREAD: i32 = 2; // How far from the first the rest are placed
+pub const PACK_CALL_DISTANCE: f32 = 12.0; // How far a pack animal calls the others from
+
+/// Whether it hunts with a pack, awake or not.
+pub fn is_pack_animal(object: &Object) -> bool {
+    match object.ai.as_ref().map(Ai::awake) {
+        Some(&Ai::Pack) => true,
+        Some(Ai::Alerted { previous_ai, .. }) => **previous_ai == Ai::Pack,
+        _ => false,
+    }
+}
+
+/// If the monster just placed hunts with a pack, place the rest of its pack
+/// around it, asleep if it is.
+pub fn place_pack(leader_id: usize, map: &Map, objects: &mut Vec<Object>, rng: &mut StdRng) {
+    if !is_pack_animal(&objects[leader_id]) {
+        return;
+    }
+    let (leader_x, leader_y) = objects[leader_id].pos();
+    let name = objects[leader_id].name.clone();
+    let asleep = objects[leader_id].is_asleep();
+    let mut left = rng.gen_range(PACK_MIN, PACK_MAX + 1) - 1;
+    for dx in -PACK_SPREAD..PACK_SPREAD + 1 {
+        for dy in -PACK_SPREAD..PACK_SPREAD + 1 {
+            let (x, y) = (leader_x + dx, leader_y + dy);
+            if left == 0 || map_blocked(x, y, map) || Object::is_blocked(x, y, map, objects) {
+                continue;
+            }
+            if map[x as usize][y as usize].terrain.deadly() {
+                continue;
+            }
+            let mut member = create_monster(&name, x, y);
+            if asleep {
+                member.ai = Some(Ai::Sleeping {
+                    previous_ai: Box::new(Ai::Pack),
+                });
+            }
+            objects.push(member);
+            left -= 1;
+        }
+    }
+}
+
+/// The pack animal has seen the player: the rest of its pack, wherever it
+/// is, wakes and comes running.
+pub fn call_pack(caller_id: usize, fov: &Fov, objects: &mut [Object]) {
+    let (player_x, player_y) = objects[PLAYER].pos();
+    let (caller_x, caller_y) = objects[caller_id].pos();
+    let name = objects[caller_id].name.clone();
+    for (id, object) in objects.iter_mut().enumerate() {
+        let (dx, dy) = (object.x - caller_x, object.y - caller_y);
+        let distance = ((dx.pow(2) + dy.pow(2)) as f32).sqrt();
+        let packmate = id != caller_id
+            && object.alive
+            && object.name == name
+            && is_pack_animal(object)
+            && distance < PACK_CALL_DISTANCE;
+        if packmate && (object.is_asleep() || !fov.is_in_fov(object.x, object.y)) {
+            object.alert(player_x, player_y);
+        }
+    }
+}
+
+/// The free tile beside the player the pack animal should make for: the
+/// nearest to it that no other member of the pack is nearer to. That way
+/// they come at the player from all sides, each by its own way if there
+/// is one.
+pub fn flank_spot(monster_id: usize, game: &Game, objects: &[Object]) -> Option<(i32, i32)> {
+    let monster = &objects[monster_id];
+    let (player_x, player_y) = objects[PLAYER].pos();
+    let distance = |(x, y): (i32, i32), (to_x, to_y): (i32, i32)| {
+        (x - to_x).pow(2) + (y - to_y).pow(2)
+    };
+    let packmates: Vec<(i32, i32)> = objects
+        .iter()
+        .enumerate()
+        .filter(|&(id, object)| {
+            id != monster_id
+                && object.alive
+                && object.name == monster.name
+                && is_pack_animal(object)
+        })
+        .map(|(_, object)| object.pos())
+        .collect();
+    (-1..2)
+        .flat_map(|dx| (-1..2).map(move |dy| (player_x + dx, player_y + dy)))
+        .filter(|&(x, y)| {
+            if map_blocked(x, y, &game.map) || Object::is_blocked(x, y, &game.map, objects) {
+                return false;
+            }
+            let deep = game.map[x as usize][y as usize].terrain == Terrain::DeepWater;
+            (monster.swims || !deep) && travel_cost(x, y, &game.map, objects).is_some()
+        })
+        .filter(|&spot| {
+            packmates
+                .iter()
+                .all(|&packmate| distance(packmate, spot) >= distance(monster.pos(), spot))
+        })
+        .min_by_key(|&spot| distance(monster.pos(), spot))
+}
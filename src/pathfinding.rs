@@ -0,0 +1,267 @@
+//! Pathfinding over the dungeon, done by the game itself rather than libtcod.
+//!
+//! The searches know nothing about maps: they are given a function returning
+//! the cost of stepping onto a tile, or `None` for tiles that cannot be
+//! entered at all (walls, and anything off the map). That is where doors,
+//! water and other hazards make themselves felt. Moving diagonally costs the
+//! same as moving straight, as it does everywhere else in the game.
+
+use std::cmp::{self, Ordering};
+use std::collections::{BinaryHeap, HashMap};
+
+/// The eight steps that can be taken from a tile.
+const STEPS: [(i32, i32); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+/// A tile waiting to be searched, ordered so that `BinaryHeap` gives back
+/// the cheapest estimate first.
+#[derive(PartialEq, Eq)]
+struct Node {
+    estimate: u32,
+    cost: u32,
+    pos: (i32, i32),
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.cost.cmp(&other.cost))
+            .then_with(|| self.pos.cmp(&other.pos))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Find the cheapest path from `from` to `to` with A*. The path leaves out
+/// `from` and ends with `to`; it is `None` if `to` cannot be reached.
+pub fn astar<C>(from: (i32, i32), to: (i32, i32), cost: C) -> Option<Vec<(i32, i32)>>
+where
+    C: Fn(i32, i32) -> Option<u32>,
+{
+    // No step costs less than 1, so the number of steps never overestimates.
+    let heuristic = |(x, y): (i32, i32)| cmp::max((x - to.0).abs(), (y - to.1).abs()) as u32;
+    search(from, |x, y| (x, y) == to, heuristic, cost)
+}
+
+/// Find the cheapest path from `from` to the nearest tile that satisfies
+/// `is_goal`, as `astar` would if it knew which one that was.
+pub fn nearest<G, C>(from: (i32, i32), is_goal: G, cost: C) -> Option<Vec<(i32, i32)>>
+where
+    G: Fn(i32, i32) -> bool,
+    C: Fn(i32, i32) -> Option<u32>,
+{
+    search(from, is_goal, |_| 0, cost)
+}
+
+/// The cost of the cheapest path from `from` to every tile that can be
+/// reached from it, for creatures that care about how far they are from
+/// something rather than how to get there.
+pub fn distance_map<C>(from: (i32, i32), cost: C) -> HashMap<(i32, i32), u32>
+where
+    C: Fn(i32, i32) -> Option<u32>,
+{
+    let mut best = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+    best.insert(from, 0);
+    frontier.push(Node {
+        estimate: 0,
+        cost: 0,
+        pos: from,
+    });
+
+    while let Some(Node { cost: so_far, pos, .. }) = frontier.pop() {
+        if best.get(&pos).map_or(false, |&known| known < so_far) {
+            continue;
+        }
+        for &(dx, dy) in &STEPS {
+            let next = (pos.0 + dx, pos.1 + dy);
+            let step_cost = match cost(next.0, next.1) {
+                Some(step_cost) => step_cost,
+                None => continue,
+            };
+            let total = so_far + step_cost;
+            if best.get(&next).map_or(true, |&known| total < known) {
+                best.insert(next, total);
+                frontier.push(Node {
+                    estimate: total,
+                    cost: total,
+                    pos: next,
+                });
+            }
+        }
+    }
+    best
+}
+
+fn search<G, H, C>(from: (i32, i32), is_goal: G, heuristic: H, cost: C) -> Option<Vec<(i32, i32)>>
+where
+    G: Fn(i32, i32) -> bool,
+    H: Fn((i32, i32)) -> u32,
+    C: Fn(i32, i32) -> Option<u32>,
+{
+    let mut came_from = HashMap::new();
+    let mut best = HashMap::new();
+    let mut frontier = BinaryHeap::new();
+    best.insert(from, 0);
+    frontier.push(Node {
+        estimate: heuristic(from),
+        cost: 0,
+        pos: from,
+    });
+
+    while let Some(Node { cost: so_far, pos, .. }) = frontier.pop() {
+        if best.get(&pos).map_or(false, |&known| known < so_far) {
+            // A cheaper way here was found after this one was queued.
+            continue;
+        }
+        if pos != from && is_goal(pos.0, pos.1) {
+            let mut path = vec![pos];
+            let mut step = pos;
+            while let Some(&previous) = came_from.get(&step) {
+                if previous == from {
+                    break;
+                }
+                path.push(previous);
+                step = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &(dx, dy) in &STEPS {
+            let next = (pos.0 + dx, pos.1 + dy);
+            let step_cost = match cost(next.0, next.1) {
+                Some(step_cost) => step_cost,
+                None => continue,
+            };
+            let total = so_far + step_cost;
+            if best.get(&next).map_or(true, |&known| total < known) {
+                best.insert(next, total);
+                came_from.insert(next, pos);
+                frontier.push(Node {
+                    estimate: total + heuristic(next),
+                    cost: total,
+                    pos: next,
+                });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{DEEP_WATER_STEP_COST, DOOR_STEP_COST};
+
+    /// A map drawn as rows of text: `#` is wall, `+` a closed door, `~` deep
+    /// water and anything else floor. Costs are those `travel_cost` gives.
+    fn costs<'a>(rows: &'a [&'a str]) -> impl Fn(i32, i32) -> Option<u32> + 'a {
+        move |x, y| {
+            let glyph = rows.get(y as usize)?.as_bytes().get(x as usize)?;
+            match glyph {
+                b'#' => None,
+                b'+' => Some(DOOR_STEP_COST),
+                b'~' => Some(DEEP_WATER_STEP_COST),
+                _ => Some(1),
+            }
+        }
+    }
+
+    fn total_cost(path: &[(i32, i32)], cost: impl Fn(i32, i32) -> Option<u32>) -> u32 {
+        path.iter().map(|&(x, y)| cost(x, y).unwrap()).sum()
+    }
+
+    #[test]
+    fn straight_path_over_open_floor() {
+        let rows = [".....", ".....", "....."];
+        let path = astar((0, 1), (4, 1), costs(&rows)).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.last(), Some(&(4, 1)));
+        // Each step is to a neighbouring tile.
+        let steps = std::iter::once((0, 1)).chain(path.iter().cloned()).collect::<Vec<_>>();
+        assert!(steps.windows(2).all(|pair| {
+            (pair[0].0 - pair[1].0).abs() <= 1 && (pair[0].1 - pair[1].1).abs() <= 1
+        }));
+    }
+
+    #[test]
+    fn diagonal_steps_cost_the_same() {
+        let rows = ["....", "....", "....", "...."];
+        let path = astar((0, 0), (3, 3), costs(&rows)).unwrap();
+        assert_eq!(path.len(), 3);
+    }
+
+    #[test]
+    fn walled_off_tile_cannot_be_reached() {
+        let rows = ["..#..", "..#..", "..#.."];
+        assert_eq!(astar((0, 1), (4, 1), costs(&rows)), None);
+    }
+
+    #[test]
+    fn path_goes_around_walls() {
+        let rows = [".....", ".###.", "....."];
+        let path = astar((0, 1), (4, 1), costs(&rows)).unwrap();
+        assert_eq!(path.last(), Some(&(4, 1)));
+        assert!(path.iter().all(|&(x, y)| rows[y as usize].as_bytes()[x as usize] != b'#'));
+        assert_eq!(path.len(), 4);
+    }
+
+    #[test]
+    fn door_is_opened_when_that_is_cheapest() {
+        let rows = [".#.", ".+.", ".#."];
+        let path = astar((0, 1), (2, 1), costs(&rows)).unwrap();
+        assert_eq!(path, vec![(1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn door_is_walked_around_when_that_is_cheaper() {
+        // Through the door costs one more than the step beside it.
+        let rows = ["...", ".+.", "..."];
+        let path = astar((0, 1), (2, 1), costs(&rows)).unwrap();
+        assert!(!path.contains(&(1, 1)));
+        assert_eq!(total_cost(&path, costs(&rows)), 2);
+    }
+
+    #[test]
+    fn deep_water_is_avoided_for_a_short_detour() {
+        let rows = ["......", "..~~..", "......"];
+        let cost = costs(&rows);
+        let path = astar((1, 1), (4, 1), &cost).unwrap();
+        assert!(path.iter().all(|&(x, y)| rows[y as usize].as_bytes()[x as usize] != b'~'));
+        assert_eq!(total_cost(&path, &cost), 3);
+    }
+
+    #[test]
+    fn deep_water_is_crossed_when_there_is_no_other_way() {
+        let rows = ["#####", "..~..", "#####"];
+        let cost = costs(&rows);
+        let path = astar((0, 1), (4, 1), &cost).unwrap();
+        assert!(path.contains(&(2, 1)));
+        assert_eq!(total_cost(&path, &cost), 3 + DEEP_WATER_STEP_COST);
+    }
+
+    #[test]
+    fn path_is_no_dearer_than_any_other() {
+        // Four steps through the water cost more than the long way round.
+        let rows = ["#######", "#.~~~.#", "#.###.#", "#.....#", "#######"];
+        let cost = costs(&rows);
+        let path = astar((1, 1), (5, 1), &cost).unwrap();
+        let through_water = 3 * DEEP_WATER_STEP_COST + 1;
+        assert!(total_cost(&path, &cost) <= through_water);
+        assert_eq!(total_cost(&path, &cost), 6);
+    }
+}
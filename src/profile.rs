@@ -0,0 +1,334 @@
+//! The player's profile, kept between runs: achievements, unlocks and the
+//! stash.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::Backend;
+use crate::game::{Game, GameMode};
+use crate::items::{Item, add_to_inventory, create_item, take_from_inventory};
+use crate::locale::display_name;
+use crate::messages::MessageCategory;
+use crate::object::{DeathCallback, Fighter, Object};
+use crate::save::{SaveFile, load_save, write_save};
+use crate::ui::{Tcod, inventory_menu, menu, msgbox};
+
+pub const PROFILE_FILE: &str = "profile.json";
+pub const STASH_CAPACITY: usize = 3; // Items that can be kept between runs
+pub const DELVER_DEPTH: u32 = 5;
+
+/// Something the player has done in some run, which unlocks new content for later ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Achievement {
+    TrollSlayer,
+    Delver,
+    GoodSamaritan,
+    CrownBearer,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::TrollSlayer,
+        Achievement::Delver,
+        Achievement::GoodSamaritan,
+        Achievement::CrownBearer,
+    ];
+
+    /// Locale key prefix for this achievement's name and description.
+    pub fn key(self) -> &'static str {
+        match self {
+            Achievement::TrollSlayer => "achievement.troll-slayer",
+            Achievement::Delver => "achievement.delver",
+            Achievement::GoodSamaritan => "achievement.good-samaritan",
+            Achievement::CrownBearer => "achievement.crown-bearer",
+        }
+    }
+
+    pub fn name(self) -> String {
+        tr!(&format!("{}.name", self.key()))
+    }
+
+    pub fn description(self) -> String {
+        tr!(&format!("{}.description", self.key()))
+    }
+}
+
+/// What the player keeps from one run to the next.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Profile {
+    /// Items banked at the end of earlier runs.
+    pub stash: Vec<Item>,
+    pub achievements: BTreeSet<Achievement>,
+    /// Every kind of monster the player has ever killed.
+    pub first_kills: BTreeSet<String>,
+}
+
+impl SaveFile for Profile {
+    const PATH: &'static str = PROFILE_FILE;
+    const MIGRATIONS: &'static [fn(&mut serde_json::Value)] = &[
+        // 0 -> 1: profiles from before unlocks have no first kills.
+        |profile| {
+            if let Some(fields) = profile.as_object_mut() {
+                fields.entry("first_kills").or_insert_with(|| serde_json::json!([]));
+            }
+        },
+    ];
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        load_save().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_save(self)
+    }
+
+    pub fn is_unlocked(&self, content: Unlockable) -> bool {
+        UNLOCKS
+            .iter()
+            .filter(|&&(unlockable, _)| unlockable == content)
+            .all(|&(_, requirement)| requirement.is_met(self))
+    }
+
+    /// Items that have been added to the spawn pool.
+    pub fn unlocked_items(&self) -> Vec<Item> {
+        UNLOCKS
+            .iter()
+            .filter_map(|&(unlockable, requirement)| match unlockable {
+                Unlockable::Item(item) if requirement.is_met(self) => Some(item),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Content that is only available once the profile meets some requirement.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unlockable {
+    Class(PlayerClass),
+    /// An item added to the spawn pool.
+    Item(Item),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Requirement {
+    Achievement(Achievement),
+    /// Having killed a monster of this kind in any run.
+    FirstKill(&'static str),
+}
+
+impl Requirement {
+    pub fn is_met(self, profile: &Profile) -> bool {
+        match self {
+            Requirement::Achievement(achievement) => profile.achievements.contains(&achievement),
+            Requirement::FirstKill(monster) => profile.first_kills.contains(monster),
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Requirement::Achievement(achievement) => tr!("unlock.achievement", achievement.name()),
+            Requirement::FirstKill(monster) => tr!("unlock.first-kill", display_name(monster)),
+        }
+    }
+}
+
+/// Everything that is locked, and what unlocks it.
+pub const UNLOCKS: [(Unlockable, Requirement); 4] = [
+    (
+        Unlockable::Class(PlayerClass::Scholar),
+        Requirement::Achievement(Achievement::GoodSamaritan),
+    ),
+    (
+        Unlockable::Class(PlayerClass::Brute),
+        Requirement::Achievement(Achievement::TrollSlayer),
+    ),
+    (
+        Unlockable::Class(PlayerClass::Champion),
+        Requirement::FirstKill("troll king"),
+    ),
+    (
+        Unlockable::Item(Item::Teleport),
+        Requirement::Achievement(Achievement::Delver),
+    ),
+];
+
+impl Unlockable {
+    pub fn name(self) -> String {
+        match self {
+            Unlockable::Class(class) => tr!("unlock.class", class.name()),
+            Unlockable::Item(item) => tr!("unlock.item", display_name(&create_item(item, 0, 0).name)),
+        }
+    }
+}
+
+/// The kinds of character the player can start a run as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlayerClass {
+    Fighter,
+    Scholar,
+    Brute,
+    Champion,
+}
+
+impl PlayerClass {
+    pub const ALL: [PlayerClass; 4] = [
+        PlayerClass::Fighter,
+        PlayerClass::Scholar,
+        PlayerClass::Brute,
+        PlayerClass::Champion,
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            PlayerClass::Fighter => tr!("class.fighter"),
+            PlayerClass::Scholar => tr!("class.scholar"),
+            PlayerClass::Brute => tr!("class.brute"),
+            PlayerClass::Champion => tr!("class.champion"),
+        }
+    }
+
+    pub fn fighter(self) -> Fighter {
+        let (hp, defense, power) = match self {
+            PlayerClass::Fighter => (30, 2, 5),
+            PlayerClass::Scholar => (22, 1, 4),
+            PlayerClass::Brute => (40, 0, 7),
+            PlayerClass::Champion => (35, 3, 6),
+        };
+        Fighter {
+            max_hp: hp,
+            hp: hp,
+            defense: defense,
+            power: power,
+            xp: 0,
+            venom: None,
+            on_death: DeathCallback::Player,
+        }
+    }
+
+    /// Items the class starts with.
+    pub fn kit(self) -> &'static [Item] {
+        match self {
+            PlayerClass::Scholar => &[Item::Lightning, Item::Confuse, Item::Confuse],
+            PlayerClass::Champion => &[Item::Heal, Item::Heal],
+            _ => &[],
+        }
+    }
+}
+
+pub fn earn_achievement(game: &mut Game, achievement: Achievement) {
+    if game.mode == GameMode::Demo {
+        return;
+    }
+    if game.profile.achievements.insert(achievement) {
+        game.messages.add(tr!("achievement.earned", achievement.name()), MessageCategory::System);
+        announce_unlocks(game, Requirement::Achievement(achievement));
+        // An achievement that failed to save can still be earned again.
+        let _ = game.profile.save();
+    }
+}
+
+pub fn record_first_kill(game: &mut Game, monster: &str) {
+    if game.mode == GameMode::Demo {
+        return;
+    }
+    if game.profile.first_kills.insert(monster.into()) {
+        let requirement = UNLOCKS
+            .iter()
+            .map(|&(_, requirement)| requirement)
+            .find(|&requirement| match requirement {
+                Requirement::FirstKill(kind) => kind == monster,
+                _ => false,
+            });
+        if let Some(requirement) = requirement {
+            announce_unlocks(game, requirement);
+        }
+        let _ = game.profile.save();
+    }
+}
+
+/// Tell the player about the content that meeting this requirement unlocked.
+pub fn announce_unlocks(game: &mut Game, requirement: Requirement) {
+    for &(unlockable, _) in UNLOCKS.iter().filter(|&&(_, r)| r == requirement) {
+        if game.profile.is_unlocked(unlockable) {
+            game.messages.add(tr!("unlock.unlocked", unlockable.name()), MessageCategory::System);
+        }
+    }
+}
+
+/// Let the player pick a class among the ones they have unlocked.
+pub fn choose_class(tcod: &mut Tcod) -> Option<PlayerClass> {
+    let profile = Profile::load();
+    let classes: Vec<PlayerClass> = PlayerClass::ALL
+        .iter()
+        .cloned()
+        .filter(|&class| profile.is_unlocked(Unlockable::Class(class)))
+        .collect();
+    if classes.len() == 1 {
+        return Some(classes[0]);
+    }
+    let names: Vec<String> = classes.iter().map(|class| class.name()).collect();
+    menu(&tr!("class.choose"), &names, 30, &mut *tcod.ui).map(|index| classes[index])
+}
+
+/// Take any items wanted from the stash for the new run.
+pub fn withdraw_from_stash(tcod: &mut Tcod, game: &mut Game) {
+    while !game.profile.stash.is_empty() {
+        let stash: Vec<Object> = game.profile.stash.iter().map(|&item| create_item(item, 0, 0)).collect();
+        match inventory_menu(&stash, &game.identification, &tr!("stash.withdraw"), &mut *tcod.ui) {
+            Some(index) => {
+                let item = game.profile.stash.remove(index);
+                if add_to_inventory(&mut game.inventory, create_item(item, 0, 0)).is_err() {
+                    // No room for it this run; it stays in the stash.
+                    game.profile.stash.insert(index, item);
+                    break;
+                }
+            }
+            None => break,
+        }
+    }
+    let _ = game.profile.save();
+}
+
+/// Bank some of the inventory in the stash, as room allows.
+pub fn deposit_in_stash(tcod: &mut Tcod, game: &mut Game) {
+    while game.profile.stash.len() < STASH_CAPACITY && !game.inventory.is_empty() {
+        let header = tr!("stash.deposit", STASH_CAPACITY - game.profile.stash.len());
+        match inventory_menu(&game.inventory, &game.identification, &header, &mut *tcod.ui) {
+            Some(index) => match game.inventory[index].item {
+                Some(Item::Artifact) | None => {
+                    msgbox(&tr!("stash.refused"), 40, &mut *tcod.ui);
+                }
+                Some(item) => {
+                    take_from_inventory(&mut game.inventory, index);
+                    game.profile.stash.push(item);
+                }
+            },
+            None => break,
+        }
+    }
+    if let Err(e) = game.profile.save() {
+        msgbox(&format!("\n{}\n", tr!("stash.save-failed", e)), 50, &mut *tcod.ui);
+    }
+}
+
+pub fn show_achievements(profile: &Profile, ui: &mut dyn Backend) {
+    let mut text = format!("{}\n\n", tr!("achievement.title"));
+    for &achievement in &Achievement::ALL {
+        let mark = if profile.achievements.contains(&achievement) { "*" } else { " " };
+        text.push_str(&format!("[{}] {}\n    {}\n", mark, achievement.name(), achievement.description()));
+    }
+    text.push_str(&format!("\n{}\n", tr!("unlock.title")));
+    for &(unlockable, requirement) in &UNLOCKS {
+        let mark = if requirement.is_met(profile) { "*" } else { " " };
+        text.push_str(&format!("[{}] {} ({})\n", mark, unlockable.name(), requirement.description()));
+    }
+    text.push_str(&format!("\n{}\n", tr!("stash.contents", profile.stash.len(), STASH_CAPACITY)));
+    for &item in &profile.stash {
+        text.push_str(&format!("- {}\n", display_name(&create_item(item, 0, 0).name)));
+    }
+    msgbox(&text, 60, ui);
+}
@@ -0,0 +1,361 @@
+//! Quests, including the main quest for the artifact.
+
+use rand::{Rng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::Ai;
+use crate::backend::Backend;
+use crate::faction::{Faction, QUEST_REPUTATION, adjust_reputation, standing};
+use crate::game::{Game, GameEvent};
+use crate::items::{Item, add_to_inventory, create_item};
+use crate::locale::display_name;
+use crate::map::random_free_tile;
+use crate::messages::MessageCategory;
+use crate::npc::{Npc, dialogue_args};
+use crate::object::{DeathCallback, Fighter, Object, PLAYER};
+use crate::profile::{Achievement, earn_achievement};
+use crate::shop::open_shop;
+use crate::tcod_types::colors::*;
+use crate::ui::{Tcod, menu, msgbox};
+
+pub const ARTIFACT_NAME: &str = "crown of the ancient kings";
+pub const ARTIFACT_LEVEL: u32 = 10; // Depth of the main quest's artifact
+pub const QUEST_GIVER_CHANCE: f32 = 0.3; // Chance of a quest-giving NPC on each level
+pub const UNIQUE_MONSTERS: [&str; 2] = ["orc warlord", "troll king"];
+
+/// What has to be done to complete a quest.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Objective {
+    Kill { monster: String, count: u32 },
+    /// Kill a unique monster that waits on a particular level.
+    Slay { monster: String, depth: u32 },
+    Fetch { item: String, count: u32 },
+    Escort { npc: String },
+    Retrieve { item: String, depth: u32 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Quest {
+    pub objective: Objective,
+    /// Name of whoever gave the quest, if anyone.
+    pub giver: Option<String>,
+    pub reward: Option<Item>,
+    /// Faction whose standing improves when the quest is completed.
+    pub faction: Option<Faction>,
+    pub progress: u32,
+    pub completed: bool,
+}
+
+impl Quest {
+    pub fn new(objective: Objective, giver: Option<String>, reward: Option<Item>) -> Self {
+        Quest {
+            objective: objective,
+            giver: giver,
+            reward: reward,
+            faction: None,
+            progress: 0,
+            completed: false,
+        }
+    }
+
+    /// Update the quest's progress, given what the player now carries.
+    /// Returns `true` if this event completed it.
+    pub fn advance(&mut self, event: &GameEvent, inventory: &[Object]) -> bool {
+        if self.completed {
+            return false;
+        }
+        match (&self.objective, event) {
+            (Objective::Kill { monster, count }, GameEvent::MonsterKilled { name, .. }) if monster == name => {
+                self.progress += 1;
+                self.completed = self.progress >= *count;
+            }
+            (Objective::Slay { monster, .. }, GameEvent::MonsterKilled { name, .. }) if monster == name => {
+                self.progress = 1;
+                self.completed = true;
+            }
+            (Objective::Fetch { item, count }, GameEvent::ItemPickedUp { name }) if item == name => {
+                // What counts is what is in the pack, not how often it was
+                // picked up: dropping one and picking it up again is no help.
+                self.progress = inventory
+                    .iter()
+                    .filter(|held| held.name == *item)
+                    .map(|held| held.count)
+                    .sum();
+                self.completed = self.progress >= *count;
+            }
+            (Objective::Escort { npc }, GameEvent::EscortArrived { name }) if npc == name => {
+                self.progress = 1;
+                self.completed = true;
+            }
+            (Objective::Retrieve { item, .. }, GameEvent::ItemPickedUp { name }) if item == name => {
+                self.progress = 1;
+                self.completed = true;
+            }
+            _ => {}
+        }
+        self.completed
+    }
+
+    pub fn description(&self) -> String {
+        match self.objective {
+            Objective::Kill { ref monster, count } => {
+                tr!("quest.kill", count, display_name(monster), self.progress)
+            }
+            Objective::Slay { ref monster, depth } => {
+                tr!("quest.slay", display_name(monster), depth)
+            }
+            Objective::Fetch { ref item, count } => {
+                tr!("quest.fetch", count, display_name(item), self.progress)
+            }
+            Objective::Escort { ref npc } => tr!("quest.escort", display_name(npc)),
+            Objective::Retrieve { ref item, depth } => {
+                tr!("quest.retrieve", display_name(item), depth)
+            }
+        }
+    }
+}
+
+pub fn main_quest_completed(game: &Game) -> bool {
+    game.quests
+        .iter()
+        .any(|quest| quest.giver.is_none() && quest.completed)
+}
+
+pub fn complete_quest(quest: &Quest, game: &mut Game, objects: &mut Vec<Object>) {
+    game.messages.add(tr!("quest.completed", quest.description()), MessageCategory::Lore);
+    game.write_journal(tr!("journal.quest", quest.description()));
+    if let Some(faction) = quest.faction {
+        adjust_reputation(game, faction, QUEST_REPUTATION);
+    }
+    if quest.giver.is_some() {
+        earn_achievement(game, Achievement::GoodSamaritan);
+    } else {
+        earn_achievement(game, Achievement::CrownBearer);
+    }
+    if let Some(reward) = quest.reward {
+        let (x, y) = objects[PLAYER].pos();
+        let item = create_item(reward, x, y);
+        let name = game.identification.name_of(&item);
+        game.messages.add(tr!("quest.reward-received", name), MessageCategory::Item);
+        if let Err(item) = add_to_inventory(&mut game.inventory, item) {
+            // No room in the pack, so it goes on the floor.
+            objects.push(*item);
+        }
+    }
+}
+
+/// Without a dialogue, an NPC just offers its quest, if it has one.
+pub fn offer_quest(npc_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
+    let name = display_name(&objects[npc_id].name);
+    let (offered, trades) = objects[npc_id]
+        .npc
+        .as_ref()
+        .map_or((false, false), |npc| (npc.quest.is_some(), !npc.wares.is_empty()));
+    if !offered {
+        if trades {
+            open_shop(npc_id, tcod, game, objects);
+        } else {
+            game.messages.add(tr!("npc.nothing", name), MessageCategory::Lore);
+        }
+        return;
+    }
+
+    let (_, description, reward) = dialogue_args(npc_id, game, objects);
+    let offer = tr!("npc.offer", name, description, reward);
+    let choices = [tr!("npc.accept"), tr!("npc.decline")];
+    if menu(&offer, &choices, 50, &mut *tcod.ui) == Some(0) {
+        start_quest(npc_id, game, objects);
+    }
+}
+
+/// Accept the quest an NPC is offering.
+pub fn start_quest(npc_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let npc = match objects[npc_id].npc.as_mut() {
+        Some(npc) => npc,
+        None => return,
+    };
+    if let Some(quest) = npc.quest.take() {
+        game.messages.add(tr!("quest.started", quest.description()), MessageCategory::Lore);
+        let escort = matches!(quest.objective, Objective::Escort { .. });
+        npc.given = Some(game.quests.len());
+        game.quests.push(quest);
+        if escort {
+            objects[npc_id].ai = Some(Ai::Follow);
+        }
+    }
+}
+
+/// The kinds of side quest NPCs can offer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuestTemplate {
+    Cull,
+    Slay,
+    Fetch,
+    Escort,
+}
+
+impl QuestTemplate {
+    pub const ALL: [QuestTemplate; 4] = [
+        QuestTemplate::Cull,
+        QuestTemplate::Slay,
+        QuestTemplate::Fetch,
+        QuestTemplate::Escort,
+    ];
+
+    /// Name of the NPC who offers this kind of quest.
+    pub fn giver(self) -> &'static str {
+        match self {
+            QuestTemplate::Escort => "pilgrim",
+            _ => "sage",
+        }
+    }
+
+    /// Fill in the template for a quest offered at the given depth.
+    pub fn generate(self, depth: u32, rng: &mut StdRng) -> Quest {
+        let (objective, reward) = match self {
+            QuestTemplate::Cull => {
+                let (monster, count) = if rng.next_f32() < 0.7 {
+                    ("orc", rng.gen_range(2, 5))
+                } else {
+                    ("troll", rng.gen_range(1, 3))
+                };
+                let reward = if rng.gen() { Item::Lightning } else { Item::Heal };
+                (Objective::Kill { monster: monster.into(), count: count }, reward)
+            }
+            QuestTemplate::Slay => {
+                let monster = UNIQUE_MONSTERS[rng.gen_range(0, UNIQUE_MONSTERS.len())];
+                let depth = depth + rng.gen_range(1, 4);
+                (Objective::Slay { monster: monster.into(), depth: depth }, Item::Lightning)
+            }
+            QuestTemplate::Fetch => {
+                let (item, reward) = if rng.gen() {
+                    (Item::Heal, Item::Confuse)
+                } else {
+                    (Item::Confuse, Item::Lightning)
+                };
+                let item = create_item(item, 0, 0).name;
+                (Objective::Fetch { item: item, count: rng.gen_range(2, 4) }, reward)
+            }
+            QuestTemplate::Escort => {
+                (Objective::Escort { npc: self.giver().into() }, Item::Heal)
+            }
+        };
+        let mut quest = Quest::new(objective, Some(self.giver().into()), Some(reward));
+        quest.faction = Some(Faction::Tombfolk);
+        quest
+    }
+}
+
+/// Sometimes place an NPC with a randomly generated quest on the current level.
+pub fn place_quest_giver(game: &mut Game, objects: &mut Vec<Object>) {
+    let rng = &mut game.rng;
+    if rng.next_f32() >= QUEST_GIVER_CHANCE {
+        return;
+    }
+    let template = QuestTemplate::ALL[rng.gen_range(0, QuestTemplate::ALL.len())];
+    let quest = template.generate(game.dungeon_level, rng);
+
+    let (x, y) = random_free_tile(&game.map, objects, rng);
+    let mut giver = Object::new(x, y, '@', template.giver(), LIGHT_CYAN, true);
+    let wares = if template == QuestTemplate::Escort {
+        vec![]
+    } else {
+        (0..rng.gen_range(1, 4))
+            .map(|_| [Item::Heal, Item::Lightning, Item::Confuse][rng.gen_range(0, 3)])
+            .collect()
+    };
+    giver.npc = Some(Npc {
+        quest: Some(quest),
+        given: None,
+        wares: wares,
+        shopkeeper: false,
+    });
+    giver.faction = Some(Faction::Tombfolk);
+    giver.alive = true;
+    objects.push(giver);
+}
+
+/// Create one of the named monsters that side quests send the player after.
+pub fn create_unique(name: &str, x: i32, y: i32) -> Object {
+    let (glyph, color, hp, defense, power, faction) = match name {
+        "troll king" => ('T', DARK_RED, 30, 2, 7, Faction::Trolls),
+        _ => ('O', DARK_ORANGE, 20, 1, 5, Faction::Orcs),
+    };
+    let mut unique = Object::new(x, y, glyph, name, color, true);
+    unique.fighter = Some(Fighter {
+        max_hp: hp,
+        hp: hp,
+        defense: defense,
+        power: power,
+        xp: 250,
+        venom: None,
+        on_death: DeathCallback::Monster,
+    });
+    unique.ai = Some(Ai::Basic);
+    unique.faction = Some(faction);
+    unique.intelligent = faction == Faction::Orcs;
+    unique.alive = true;
+    unique
+}
+
+/// Place the targets of any active quests that wait on this level.
+pub fn spawn_quest_targets(depth: u32, game: &mut Game, objects: &mut Vec<Object>) {
+    let targets: Vec<String> = game
+        .quests
+        .iter()
+        .filter(|quest| !quest.completed)
+        .filter_map(|quest| match quest.objective {
+            Objective::Slay { ref monster, depth: d } if d == depth => Some(monster.clone()),
+            _ => None,
+        })
+        .collect();
+    for monster in targets {
+        let (x, y) = random_free_tile(&game.map, objects, &mut game.rng);
+        objects.push(create_unique(&monster, x, y));
+        game.messages.add(tr!("quest.target-near", display_name(&monster)), MessageCategory::Warning);
+    }
+}
+
+/// Place the artifact of the main quest if this is its level.
+pub fn place_artifact(game: &mut Game, objects: &mut Vec<Object>) {
+    if game.dungeon_level == ARTIFACT_LEVEL {
+        let (x, y) = random_free_tile(&game.map, objects, &mut game.rng);
+        objects.push(create_item(Item::Artifact, x, y));
+    }
+}
+
+/// Show the active quests, then the completed ones.
+pub fn show_quest_log(game: &Game, ui: &mut dyn Backend) {
+    let quests = &game.quests;
+    let mut text = format!("{}\n\n", tr!("quest.log-title"));
+    if quests.is_empty() {
+        text.push_str(&format!("{}\n", tr!("quest.none")));
+    }
+    for &completed in &[false, true] {
+        let quests: Vec<&Quest> = quests.iter().filter(|q| q.completed == completed).collect();
+        if quests.is_empty() {
+            continue;
+        }
+        let heading = if completed { tr!("quest.completed-heading") } else { tr!("quest.active-heading") };
+        text.push_str(&format!("{}\n", heading));
+        for quest in quests {
+            text.push_str(&format!("- {}\n", quest.description()));
+            if let Some(ref giver) = quest.giver {
+                text.push_str(&format!("  {}\n", tr!("quest.giver", display_name(giver))));
+            }
+        }
+        text.push('\n');
+    }
+
+    text.push_str(&format!("{}\n", tr!("reputation.heading")));
+    for (&faction, &reputation) in &game.reputation {
+        text.push_str(&format!(
+            "- {}: {} ({})\n",
+            faction.name(),
+            standing(game, faction).name(),
+            reputation
+        ));
+    }
+    msgbox(&text, 60, ui);
+}
@@ -0,0 +1,162 @@
+//! Ranged weapons: a bow or a sling, held in the hand, shoots arrows or
+//! stones from a stack in the inventory at whatever the player aims it at.
+
+use rand::{Rng, StdRng};
+
+use crate::combat::{Dice, roll_blow};
+use crate::game::Game;
+use crate::items::{Item, UseResult, take_from_inventory};
+use crate::locale::display_name;
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+use crate::render::Animation;
+use crate::throw::flight;
+use crate::ui::Tcod;
+
+pub const BOW_RANGE: i32 = 10;
+pub const BOW_DAMAGE: i32 = 5;
+pub const SLING_RANGE: i32 = 7;
+pub const SLING_DAMAGE: i32 = 3;
+pub const BOW_DICE: Dice = Dice { count: 2, sides: 4 };
+pub const SLING_DICE: Dice = Dice { count: 1, sides: 3 };
+pub const AMMO_BREAK_CHANCE: f32 = 0.3; // Chance of a shot arrow or stone being lost
+pub const AMMO_BUNDLE_MIN: u32 = 4; // Arrows and stones are found in bundles of this many or more
+pub const AMMO_BUNDLE_MAX: u32 = 10;
+
+/// A weapon that shoots: what with, how far and how hard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Launcher {
+    pub ammo: Item,
+    pub range: i32,
+    /// What a shot does on average, before the target's defense.
+    pub damage: i32,
+    /// How far above or below that each shot falls.
+    pub dice: Dice,
+}
+
+/// What the item shoots, if it is a ranged weapon.
+pub fn launcher(item: Item) -> Option<Launcher> {
+    match item {
+        Item::Bow => Some(Launcher {
+            ammo: Item::Arrow,
+            range: BOW_RANGE,
+            damage: BOW_DAMAGE,
+            dice: BOW_DICE,
+        }),
+        Item::Sling => Some(Launcher {
+            ammo: Item::SlingStone,
+            range: SLING_RANGE,
+            damage: SLING_DAMAGE,
+            dice: SLING_DICE,
+        }),
+        _ => None,
+    }
+}
+
+pub fn is_ammo(item: Item) -> bool {
+    matches!(item, Item::Arrow | Item::SlingStone)
+}
+
+/// Make ammunition just placed on a level a whole bundle of it, since
+/// nobody leaves a single arrow lying about.
+pub fn bundle_ammo(object: &mut Object, rng: &mut StdRng) {
+    if object.item.map_or(false, is_ammo) {
+        object.count = rng.gen_range(AMMO_BUNDLE_MIN, AMMO_BUNDLE_MAX + 1);
+    }
+}
+
+/// The ranged weapon the player holds, and the inventory slot of the
+/// ammunition for it. If either is missing, says so.
+pub fn ready_to_fire(game: &mut Game) -> Option<(Launcher, usize)> {
+    let held = game.inventory.iter().find(|item| {
+        item.equipment.map_or(false, |e| e.equipped) && item.item.and_then(launcher).is_some()
+    });
+    let (name, launcher) = match held {
+        Some(held) => (game.identification.name_of(held), held.item.and_then(launcher).unwrap()),
+        None => {
+            game.messages.add(tr!("fire.no-launcher"), MessageCategory::Warning);
+            return None;
+        }
+    };
+    match game.inventory.iter().position(|item| item.item == Some(launcher.ammo)) {
+        Some(index) => Some((launcher, index)),
+        None => {
+            game.messages.add(tr!("fire.no-ammo", name), MessageCategory::Warning);
+            None
+        }
+    }
+}
+
+/// Shoot one arrow or stone from the ranged weapon held towards a tile. It
+/// flies like a thrown item, only further, and hurts whoever it hits by the
+/// weapon's damage roll less their defense. Then it lands there, unless it
+/// broke.
+/// Returns whether it was shot.
+pub fn fire(target: (i32, i32), game: &mut Game, objects: &mut Vec<Object>) -> bool {
+    let (launcher, index) = match ready_to_fire(game) {
+        Some(ready) => ready,
+        None => return false,
+    };
+    let from = objects[PLAYER].pos();
+    let (landing, victim) = flight(from, target, launcher.range, &game.map, objects);
+
+    let mut shot = take_from_inventory(&mut game.inventory, index);
+    let name = game.identification.name_of(&shot);
+    game.animations.push(Animation::Projectile {
+        from: from,
+        to: landing,
+        char: shot.char,
+        color: shot.color,
+    });
+
+    if let Some(id) = victim {
+        let target_name = display_name(&objects[id].name);
+        let defense = objects[id].fighter.map_or(0, |f| f.defense);
+        let blow = roll_blow(launcher.damage, launcher.dice, defense, &mut game.rng);
+        if blow.damage > 0 {
+            if blow.critical {
+                game.messages.add(
+                    tr!("fire.critical", name, target_name, blow.damage),
+                    MessageCategory::Critical,
+                );
+            } else {
+                game.messages.add(
+                    tr!("fire.hit", name, target_name, blow.damage),
+                    MessageCategory::Combat,
+                );
+            }
+            objects[id].take_damage(blow.damage, game);
+        } else {
+            game.messages.add(
+                tr!("fire.no-effect", name, target_name),
+                MessageCategory::Combat,
+            );
+            objects[id].wake_up();
+        }
+    }
+    if game.rng.next_f32() < AMMO_BREAK_CHANCE {
+        game.messages.add(tr!("fire.breaks", name), MessageCategory::Item);
+        if let Some(price) = shot.price {
+            if let Some(shop) = game.shop.as_mut() {
+                shop.debt += price;
+                game.messages.add(tr!("shop.used-unpaid", price), MessageCategory::Item);
+            }
+        }
+    } else {
+        shot.set_pos(landing.0, landing.1);
+        objects.push(shot);
+    }
+    true
+}
+
+pub fn ready_ammo(
+    _inventory_id: usize,
+    _tcod: &mut Tcod,
+    game: &mut Game,
+    _objects: &mut [Object],
+) -> UseResult
+{
+    // Arrows and stones are shot, not used.
+    game.messages.add(tr!("ammo-use"), MessageCategory::Item);
+    UseResult::Cancelled
+}
@@ -0,0 +1,707 @@
+//! Drawing the game on screen, and dumping it to files.
+
+use std::cmp;
+use std::fs;
+
+use crate::backend::{Layer, Renderer};
+use crate::branch::is_stairs;
+use crate::config::config;
+use crate::fov::compute_fov;
+use crate::game::{Game, Glimpse};
+use crate::gold::is_gold;
+use crate::hunger::Hunger;
+use crate::identify::Identification;
+use crate::input::GAMEPAD;
+use crate::items::stack_name;
+use crate::light::sight_radius;
+use crate::lighting::{LIGHT_TINT, Lighting};
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER, level_up_xp};
+use crate::status::StatusKind;
+use crate::tcod_types::colors::*;
+use crate::tcod_types::console::{BackgroundFlag, TextAlignment};
+use crate::tcod_types::input::Mouse;
+use crate::throw::trajectory;
+use crate::ui::{Tcod, menu};
+
+// Sizes and coordinates relevant for the GUI. The window and map sizes are in `config.toml`.
+pub const BAR_WIDTH: i32 = 20;
+pub const PANEL_HEIGHT: i32 = 7;
+
+pub const MSG_X: i32 = BAR_WIDTH + 2;
+pub const MSG_HEIGHT: usize = PANEL_HEIGHT as usize - 1;
+pub const SCREENSHOT_DIR: &str = "screenshots";
+pub const MAP_EXPORT_DIR: &str = "maps";
+pub const COLOR_DARK_WALL: Color = Color { r: 0, g: 0, b: 100 };
+
+pub const COLOR_LIGHT_WALL: Color = Color {
+    r: 130,
+    g: 110,
+    b: 50,
+};
+
+pub const COLOR_DARK_GROUND: Color = Color {
+    r: 50,
+    g: 50,
+    b: 150,
+};
+
+pub const COLOR_LIGHT_GROUND: Color = Color {
+    r: 200,
+    g: 180,
+    b: 50,
+};
+
+pub const COLOR_DOOR: Color = Color {
+    r: 140,
+    g: 80,
+    b: 20,
+};
+
+pub const COLOR_DARK_SHALLOW_WATER: Color = Color {
+    r: 30,
+    g: 60,
+    b: 110,
+};
+
+pub const COLOR_LIGHT_SHALLOW_WATER: Color = Color {
+    r: 80,
+    g: 150,
+    b: 200,
+};
+
+pub const COLOR_DARK_DEEP_WATER: Color = Color {
+    r: 10,
+    g: 20,
+    b: 80,
+};
+
+pub const COLOR_LIGHT_DEEP_WATER: Color = Color {
+    r: 30,
+    g: 70,
+    b: 170,
+};
+
+pub const COLOR_DARK_LAVA: Color = Color {
+    r: 120,
+    g: 30,
+    b: 0,
+};
+
+pub const COLOR_LIGHT_LAVA: Color = Color {
+    r: 230,
+    g: 90,
+    b: 10,
+};
+
+/// How much of the map fits in the window, above the panel.
+pub fn viewport_size<R: Renderer + ?Sized>(ui: &R) -> (i32, i32) {
+    (
+        cmp::min(config().map.width, ui.width()),
+        cmp::min(config().map.height, ui.height() - PANEL_HEIGHT),
+    )
+}
+
+/// Where to place the camera so that `focus` is as close to the middle of the
+/// view as the map's edges allow.
+pub fn camera_position<R: Renderer + ?Sized>(ui: &R, focus: (i32, i32)) -> (i32, i32) {
+    let (width, height) = viewport_size(ui);
+    let x = (focus.0 - width / 2).max(0).min(config().map.width - width);
+    let y = (focus.1 - height / 2).max(0).min(config().map.height - height);
+    (x, y)
+}
+
+/// The map tile under the mouse, if it is over the map.
+pub fn mouse_tile(mouse: Mouse, tcod: &Tcod) -> Option<(i32, i32)> {
+    let (x, y) = (mouse.cx as i32, mouse.cy as i32);
+    let (width, height) = viewport_size(&*tcod.ui);
+    if x >= 0 && y >= 0 && x < width && y < height {
+        Some((x + tcod.camera.0, y + tcod.camera.1))
+    } else {
+        None
+    }
+}
+
+/// Save the screen as a timestamped PNG in the screenshots directory. With
+/// `text`, also save it as text, colored with ANSI escape codes.
+pub fn take_screenshot(tcod: &mut Tcod, game: &mut Game, text: bool) {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let path = format!("{}/{}.png", SCREENSHOT_DIR, stamp);
+    let result = fs::create_dir_all(SCREENSHOT_DIR).and_then(|_| {
+        tcod.ui.save_image(Layer::Screen, &path);
+        if text {
+            let dump = ansi_dump(&*tcod.ui, Layer::Screen);
+            fs::write(format!("{}/{}.txt", SCREENSHOT_DIR, stamp), dump)
+        } else {
+            Ok(())
+        }
+    });
+    match result {
+        Ok(()) => game.messages.add(tr!("screenshot.saved", path), MessageCategory::System),
+        Err(e) => game.messages.add(tr!("screenshot.failed", e), MessageCategory::Warning),
+    }
+}
+
+/// What the player knows of the current floor, tile by tile: explored walls
+/// and floor, with the stairs and items found there and the player on top.
+pub fn explored_map(game: &Game, objects: &[Object]) -> Vec<Vec<Option<(char, Color)>>> {
+    let mut glyphs = vec![vec![None; config().map.width as usize]; config().map.height as usize];
+    let palette = game.branch.palette();
+    for (y, row) in glyphs.iter_mut().enumerate() {
+        for (x, glyph) in row.iter_mut().enumerate() {
+            let tile = &game.map[x][y];
+            let trap = tile.trap.filter(|trap| !trap.hidden);
+            if tile.explored && tile.door && !tile.secret {
+                *glyph = Some((if tile.blocked { '+' } else { '\'' }, COLOR_DOOR));
+            } else if let (true, Some(trap)) = (tile.explored, trap) {
+                *glyph = Some(('^', trap.kind.color()));
+            } else if let (true, Some((_, color))) = (tile.explored, tile.terrain.colors()) {
+                *glyph = Some(('~', color));
+            } else if tile.explored {
+                *glyph = Some(if tile.block_sight {
+                    ('#', palette.light_wall)
+                } else {
+                    ('.', palette.light_ground)
+                });
+            }
+        }
+    }
+    // Only what the player has seen, where they saw it.
+    let player = Glimpse::of(&objects[PLAYER]);
+    for glimpse in game.remembered.iter().chain(std::iter::once(&player)) {
+        let (x, y) = (glimpse.x as usize, glimpse.y as usize);
+        if game.map[x][y].explored {
+            glyphs[y][x] = Some((glimpse.char, glimpse.color));
+        }
+    }
+    glyphs
+}
+
+/// The explored map as plain text, one line per row.
+pub fn explored_map_text(game: &Game, objects: &[Object]) -> String {
+    explored_map(game, objects)
+        .iter()
+        .map(|row| {
+            let line: String = row.iter().map(|glyph| glyph.map_or(' ', |(c, _)| c)).collect();
+            line.trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+/// Write the explored map of the current floor to a PNG or text file.
+pub fn export_map(tcod: &mut Tcod, game: &mut Game, objects: &[Object]) {
+    let formats = [tr!("export.png"), tr!("export.text")];
+    let format = match menu(&tr!("export.choose"), &formats, 24, &mut *tcod.ui) {
+        Some(format) => format,
+        None => return,
+    };
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let name = format!("{}/{}-depth{}", MAP_EXPORT_DIR, stamp, game.dungeon_level);
+    let result = fs::create_dir_all(MAP_EXPORT_DIR).and_then(|_| {
+        if format == 0 {
+            // Drawn on the window layer, which nothing is using while the game is at rest.
+            tcod.ui.resize(Layer::Window, config().map.width, config().map.height);
+            for (y, row) in explored_map(game, objects).iter().enumerate() {
+                for (x, glyph) in row.iter().enumerate() {
+                    if let Some((c, color)) = *glyph {
+                        tcod.ui.set_default_foreground(Layer::Window, color);
+                        let (x, y) = (x as i32, y as i32);
+                        tcod.ui.put_char(Layer::Window, x, y, c, BackgroundFlag::None);
+                    }
+                }
+            }
+            let path = name + ".png";
+            tcod.ui.save_image(Layer::Window, &path);
+            Ok(path)
+        } else {
+            let path = name + ".txt";
+            fs::write(&path, explored_map_text(game, objects)).map(|_| path)
+        }
+    });
+    match result {
+        Ok(path) => game.messages.add(tr!("export.saved", path), MessageCategory::System),
+        Err(e) => game.messages.add(tr!("export.failed", e), MessageCategory::Warning),
+    }
+}
+
+/// Render a layer as lines of text with 24-bit ANSI colors.
+pub fn ansi_dump<R: Renderer + ?Sized>(ui: &R, layer: Layer) -> String {
+    let mut dump = String::new();
+    let (width, height) = ui.size(layer);
+    for y in 0..height {
+        for x in 0..width {
+            let (c, fg, bg) = ui.cell(layer, x, y);
+            let c = match c {
+                '\0' => ' ',
+                c => c,
+            };
+            dump.push_str(&format!(
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m{}",
+                fg.r, fg.g, fg.b, bg.r, bg.g, bg.b, c
+            ));
+        }
+        dump.push_str("\x1b[0m\n");
+    }
+    dump
+}
+
+/// Return a string with the names of all objects under the mouse. 
+pub fn get_names_under_mouse(tcod: &Tcod, game: &Game, objects: &[Object]) -> String {
+    let (x, y) = match mouse_tile(tcod.mouse, tcod) {
+        Some(tile) => tile,
+        None => return String::new(),
+    };
+    let fov_map = &tcod.fov;
+
+    // Create a list with the names of all objects at the mouse's coordinates and in FOV. 
+    let names = objects
+        .iter()
+        .filter(|obj| obj.pos() == (x, y) && fov_map.is_in_fov(obj.x, obj.y))
+        .map(|obj| object_label(obj, &game.identification))
+        .collect::<Vec<_>>();
+    
+    names.join(", ") // Join the names, separated by commas.
+}
+
+/// An object's name as shown on screen, with its price if it is for sale.
+fn object_label(object: &Object, identification: &Identification) -> String {
+    if is_gold(object) {
+        return tr!("gold.pile", object.count);
+    }
+    match object.price {
+        Some(price) => tr!("shop.price-tag", stack_name(object, identification), price),
+        None => stack_name(object, identification),
+    }
+}
+
+/// What the player knows of a tile: what it is, and who and what can be
+/// seen on it, with how hurt any creatures there look.
+pub fn describe_tile(tcod: &Tcod, game: &Game, objects: &[Object], (x, y): (i32, i32)) -> String {
+    let tile = &game.map[x as usize][y as usize];
+    if !tile.explored {
+        return tr!("look.unexplored");
+    }
+    let ground = match tile.trap.filter(|trap| !trap.hidden) {
+        Some(trap) => trap.kind.name(),
+        None if tile.secret => tr!("look.wall"),
+        None if tile.door && tile.locked => tr!("look.locked-door"),
+        None if tile.door && tile.blocked => tr!("look.closed-door"),
+        None if tile.door => tr!("look.open-door"),
+        None if tile.block_sight => tr!("look.wall"),
+        None => tile.terrain.name().unwrap_or_else(|| tr!("look.floor")),
+    };
+    let mut parts = vec![ground];
+    if tcod.fov.is_in_fov(x, y) {
+        for object in objects.iter().filter(|object| object.pos() == (x, y)) {
+            let label = object_label(object, &game.identification);
+            parts.push(match object.fighter.filter(|_| object.alive) {
+                Some(fighter) => {
+                    let health = if fighter.hp >= fighter.max_hp {
+                        tr!("look.unhurt")
+                    } else if fighter.hp * 2 >= fighter.max_hp {
+                        tr!("look.wounded")
+                    } else if fighter.hp * 4 >= fighter.max_hp {
+                        tr!("look.badly-wounded")
+                    } else {
+                        tr!("look.near-death")
+                    };
+                    if object.is_asleep() {
+                        tr!("look.sleeping-creature", label, health)
+                    } else {
+                        tr!("look.creature", label, health)
+                    }
+                }
+                None => label,
+            });
+        }
+    }
+    parts.join(", ")
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_bar<R: Renderer + ?Sized>(
+    ui: &mut R,
+    layer: Layer,
+    x: i32,
+    y: i32,
+    total_width: i32,
+    name: &str,
+    value: i32,
+    maximum: i32,
+    bar_color: Color,
+    back_color: Color,
+) 
+{
+    // Render a bar (HP, EXP, etc.) First calculate the width of the bar.
+    let bar_width = (value as f32 / maximum as f32 * total_width as f32) as i32;
+
+    // Render the background first.
+    ui.set_default_background(layer, back_color);
+    ui.rect(layer, x, y, total_width, 1, BackgroundFlag::Screen);
+
+    // Now render the bar on top.
+    ui.set_default_background(layer, bar_color);
+    if bar_width > 0 {
+        ui.rect(layer, x, y, bar_width, 1, BackgroundFlag::Screen);
+    }
+
+    // Finally, print some centered text with the values. 
+    ui.set_default_foreground(layer, WHITE);
+    ui.print_ex(
+        layer,
+        x + total_width / 2, 
+        y, 
+        BackgroundFlag::None, 
+        TextAlignment::Center, 
+        &format!("{}: {}/{}", name, value, maximum)
+    );
+}
+
+pub fn render_all(tcod: &mut Tcod, game: &mut Game, objects: &[Object], fov_recompute: bool) {
+    let lighting = Lighting::of(&game.map, objects);
+    if fov_recompute || tcod.fov_stale {
+        let in_view: Vec<Glimpse> = objects
+            .iter()
+            .filter(|o| o.alive && o.ai.is_some() && tcod.fov.is_in_fov(o.x, o.y))
+            .map(Glimpse::of)
+            .collect();
+        // Recompute FOV if needed (player moved or something).
+        let blind = objects[PLAYER].has_effect(StatusKind::Blindness);
+        let radius = sight_radius(game, &objects[PLAYER]);
+        compute_fov(tcod, objects[PLAYER].pos(), radius, Some(&lighting).filter(|_| !blind));
+        tcod.fov_stale = false;
+        // Monsters the player just lost sight of leave an afterimage behind.
+        let fov = &tcod.fov;
+        game.glimpses
+            .extend(in_view.into_iter().filter(|glimpse| !fov.is_in_fov(glimpse.x, glimpse.y)));
+    }
+
+    // Go through all tiles, and set their background color.
+    let palette = game.branch.palette();
+    let (px, py) = objects[PLAYER].pos();
+    let reach = sight_radius(game, &objects[PLAYER]);
+    for y in 0..config().map.height {
+        for x in 0..config().map.width {
+            let visible = tcod.fov.is_in_fov(x, y);
+            let wall = game.map[x as usize][y as usize].block_sight;
+            let terrain = game.map[x as usize][y as usize].terrain;
+            let (dark, light) = match (wall, terrain.colors()) {
+                // Water, and the like
+                (false, Some(colors)) => colors,
+                (true, _) => (palette.dark_wall, palette.light_wall),
+                (false, None) => (palette.dark_ground, palette.light_ground),
+            };
+            let mut color = if visible { light } else { dark };
+            // Tiles lit by the world's lights take on their color, and those
+            // beyond the player's own light are only as bright as they are lit.
+            if let Some((brightness, tint)) = lighting.at(x, y).filter(|_| visible) {
+                let near = (x - px).pow(2) + (y - py).pow(2) <= reach * reach;
+                if !near {
+                    color = lerp(dark, light, brightness);
+                }
+                color = lerp(color, tint, LIGHT_TINT * brightness);
+            }
+
+            let explored = &mut game.map[x as usize][y as usize].explored;
+            if visible {
+                *explored = true; // Since it is visible, it has been explored.
+            }
+            if *explored {
+                // Only show explored tiles.
+                tcod.ui.set_char_background(Layer::Map, x, y, color, BackgroundFlag::Set);
+                let tile = &game.map[x as usize][y as usize];
+                if let Some((glyph, color)) = tile.terrain.glyph() {
+                    tcod.ui.set_default_foreground(Layer::Map, color);
+                    tcod.ui.put_char(Layer::Map, x, y, glyph, BackgroundFlag::None);
+                }
+                if tile.door && !tile.secret {
+                    let glyph = if wall { '+' } else { '\'' };
+                    tcod.ui.set_default_foreground(Layer::Map, COLOR_DOOR);
+                    tcod.ui.put_char(Layer::Map, x, y, glyph, BackgroundFlag::None);
+                }
+                let trap = game.map[x as usize][y as usize].trap;
+                if let Some(trap) = trap.filter(|trap| !trap.hidden) {
+                    tcod.ui.set_default_foreground(Layer::Map, trap.kind.color());
+                    tcod.ui.put_char(Layer::Map, x, y, '^', BackgroundFlag::None);
+                }
+            }
+        }
+    }
+
+    // Draw all objects in field of view.
+    let mut to_draw: Vec<_> = objects
+        .iter()
+        .filter(|o| tcod.fov.is_in_fov(o.x, o.y))
+        .collect();
+    // Sort `to_draw` so that non-blocking objects come first. 
+    to_draw.sort_by(|o1, o2| { o1.blocks.cmp(&o2.blocks) });
+    // Draw the objects in `to_draw`.
+    for object in &to_draw {
+        if tcod.fov.is_in_fov(object.x, object.y) {
+            object.draw(&mut *tcod.ui);
+        }
+    }
+
+    // Sleeping monsters in sight have a "z" drifting up from them, where there is room for it.
+    let fov = &tcod.fov;
+    for object in objects.iter().filter(|o| o.is_asleep() && fov.is_in_fov(o.x, o.y)) {
+        let (x, y) = (object.x, object.y - 1);
+        let open = y >= 0 && !game.map[x as usize][y as usize].block_sight;
+        if open && !to_draw.iter().any(|other| other.pos() == (x, y)) {
+            let glyph = if game.turn.is_multiple_of(2) { 'z' } else { 'Z' };
+            tcod.ui.set_default_foreground(Layer::Map, LIGHT_BLUE);
+            tcod.ui.put_char(Layer::Map, x, y, glyph, BackgroundFlag::None);
+        }
+    }
+
+    // What is in sight is known first hand, so glimpses of it are forgotten.
+    game.glimpses.retain(|glimpse| !fov.is_in_fov(glimpse.x, glimpse.y));
+    // Items and stairs are remembered as they are now seen, or were last seen.
+    game.remembered.retain(|glimpse| !fov.is_in_fov(glimpse.x, glimpse.y));
+    game.remembered.extend(
+        objects
+            .iter()
+            .filter(|o| (o.item.is_some() || is_stairs(o)) && fov.is_in_fov(o.x, o.y))
+            .map(Glimpse::of),
+    );
+    for glimpse in &game.glimpses {
+        tcod.ui.set_default_foreground(Layer::Map, lerp(glimpse.color, BLACK, 0.4));
+        tcod.ui.put_char(Layer::Map, glimpse.x, glimpse.y, glimpse.char, BackgroundFlag::None);
+    }
+
+    // Monsters sensed by telepathy are drawn dimmed, so they are not taken for ones in sight.
+    if game.telepathy > 0 {
+        let sensed = objects.iter().filter(|o| {
+            o.alive && o.fighter.is_some() && o.ai.is_some() && !fov.is_in_fov(o.x, o.y)
+        });
+        for object in sensed {
+            tcod.ui.set_default_foreground(Layer::Map, lerp(object.color, BLACK, 0.6));
+            tcod.ui.put_char(Layer::Map, object.x, object.y, object.char, BackgroundFlag::None);
+        }
+    }
+
+    // Blit the part of the map around the player to the screen.
+    tcod.camera = camera_position(&*tcod.ui, objects[PLAYER].pos());
+    let viewport = viewport_size(&*tcod.ui);
+    tcod.ui.blit(Layer::Map, tcod.camera, viewport, (0, 0), 1.0, 1.0);
+
+    // Prepare to render the GUI panel
+    tcod.ui.set_default_background(Layer::Panel, BLACK);
+    tcod.ui.clear(Layer::Panel);
+
+    // Print the game messages, one line at a time, skipping muted ones and
+    // those scrolled past.
+    let msg_width = tcod.ui.width() - BAR_WIDTH - 2;
+    let settings = &tcod.settings;
+    let shown = game
+        .messages
+        .iter()
+        .filter(|&&(_, category)| !settings.message_style(category).muted)
+        .count();
+    tcod.message_scroll = cmp::min(tcod.message_scroll, shown.saturating_sub(1));
+    let mut y = MSG_HEIGHT as i32;
+    for &(ref msg, category) in game
+        .messages
+        .iter()
+        .rev()
+        .filter(|&&(_, category)| !settings.message_style(category).muted)
+        .skip(tcod.message_scroll)
+    {
+        let style = settings.message_style(category);
+        let msg_height = tcod.ui.get_height_rect(Layer::Panel, MSG_X, y, msg_width, 0, msg);
+        y -= msg_height;
+        if y < 0 {
+            break;
+        }
+        tcod.ui.set_default_foreground(Layer::Panel, style.color);
+        tcod.ui.print_rect(Layer::Panel, MSG_X, y, msg_width, 0, msg);
+    }
+    if tcod.message_scroll > 0 {
+        // Make it plain that these are not the latest messages.
+        let right = tcod.ui.width() - 1;
+        tcod.ui.set_default_foreground(Layer::Panel, YELLOW);
+        tcod.ui.print_ex(
+            Layer::Panel,
+            right,
+            MSG_HEIGHT as i32,
+            BackgroundFlag::None,
+            TextAlignment::Right,
+            &tr!("messages.scrolled", tcod.message_scroll),
+        );
+    }
+
+    // Show the player's stats. 
+    let hp = objects[PLAYER].fighter.map_or(0, |f| f.hp);
+    let max_hp = objects[PLAYER].fighter.map_or(0, |f| f.max_hp);
+    render_bar(
+        &mut *tcod.ui,
+        Layer::Panel,
+        1, 
+        1, 
+        BAR_WIDTH, 
+        &tr!("hp"),
+        hp, 
+        max_hp, 
+        LIGHT_RED, 
+        DARKER_RED,
+    );
+
+    let level = objects[PLAYER].level;
+    let xp = objects[PLAYER].fighter.map_or(0, |f| f.xp);
+    render_bar(
+        &mut *tcod.ui,
+        Layer::Panel,
+        1,
+        2,
+        BAR_WIDTH,
+        &tr!("xp", level),
+        xp,
+        level_up_xp(level),
+        LIGHT_VIOLET,
+        DARKER_VIOLET,
+    );
+
+    tcod.ui.set_default_foreground(Layer::Panel, GOLD);
+    tcod.ui.print_ex(
+        Layer::Panel,
+        BAR_WIDTH,
+        3,
+        BackgroundFlag::None,
+        TextAlignment::Right,
+        &tr!("gold.panel", game.gold),
+    );
+
+    // Show the turns taken so far, or the count being typed for the next command.
+    let turn = match tcod.count {
+        Some(count) => {
+            tcod.ui.set_default_foreground(Layer::Panel, WHITE);
+            tr!("count", count)
+        }
+        None => {
+            tcod.ui.set_default_foreground(Layer::Panel, LIGHT_GREY);
+            tr!("turn.panel", game.turn)
+        }
+    };
+    tcod.ui.print_ex(Layer::Panel, 1, 3, BackgroundFlag::None, TextAlignment::Left, &turn);
+
+    // List what the player is suffering from, or enjoying, hunger first.
+    let effects: Vec<String> = Hunger::of(game.nutrition)
+        .name()
+        .into_iter()
+        .chain(
+            objects[PLAYER]
+                .effects
+                .iter()
+                .map(|effect| tr!("status.turns", effect.kind.name(), effect.turns)),
+        )
+        .collect();
+    if !effects.is_empty() {
+        tcod.ui.set_default_foreground(Layer::Panel, LIGHT_ORANGE);
+        tcod.ui.print_rect(Layer::Panel, 1, 4, BAR_WIDTH, 1, &effects.join(" "));
+    }
+
+    // With a controller plugged in, remind the player what its buttons do.
+    if GAMEPAD.with(|pad| pad.borrow().connected()) {
+        tcod.ui.set_default_foreground(Layer::Panel, LIGHT_GREY);
+        tcod.ui.print_rect(Layer::Panel, 1, 5, BAR_WIDTH, PANEL_HEIGHT - 5, &tr!("gamepad.hints"));
+    }
+
+    // Display names of objects under the mouse. 
+    tcod.ui.set_default_background(Layer::Panel, LIGHT_GREY);
+    tcod.ui.print_ex(
+        Layer::Panel,
+        1,
+        0,
+        BackgroundFlag::None,
+        TextAlignment::Left,
+        &get_names_under_mouse(tcod, game, objects),
+    );
+
+    // Blit the contents of the panel to the screen. 
+    let (width, height) = (tcod.ui.width(), tcod.ui.height());
+    tcod.ui.blit(Layer::Panel, (0, 0), (width, PANEL_HEIGHT), (0, height - PANEL_HEIGHT), 1.0, 1.0);
+}
+
+/// A visual effect, played out frame by frame before the game goes on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Animation {
+    /// Something flying in a straight line from one tile to another.
+    Projectile {
+        from: (i32, i32),
+        to: (i32, i32),
+        char: char,
+        color: Color,
+    },
+    /// A burst spreading out from a tile.
+    Explosion { x: i32, y: i32, radius: i32, color: Color },
+    /// A tile lighting up for a moment, as when something dies there.
+    Flash { x: i32, y: i32, color: Color },
+}
+
+/// The tiles drawn in one frame of an animation, with their glyphs and colors.
+pub type Frame = Vec<(i32, i32, Option<char>, Color)>;
+
+impl Animation {
+    /// The tiles drawn in each frame, in order. Tiles without a glyph keep
+    /// whatever is already there and only have their background lit up.
+    pub fn frames(self) -> Vec<Frame> {
+        match self {
+            Animation::Projectile { from, to, char, color } => trajectory(from, to)
+                .into_iter()
+                .map(|(x, y)| vec![(x, y, Some(char), color)])
+                .collect(),
+            Animation::Explosion { x, y, radius, color } => (0..radius + 1)
+                .map(|size| {
+                    // Each ring fades a little as the burst spreads.
+                    let color = lerp(color, BLACK, size as f32 / (radius + 1) as f32);
+                    let mut tiles = vec![];
+                    for tx in x - size..x + size + 1 {
+                        for ty in y - size..y + size + 1 {
+                            if (tx - x).pow(2) + (ty - y).pow(2) <= size.pow(2) {
+                                tiles.push((tx, ty, Some('*'), color));
+                            }
+                        }
+                    }
+                    tiles
+                })
+                .collect(),
+            Animation::Flash { x, y, color } => vec![vec![(x, y, None, color)]; 3],
+        }
+    }
+}
+
+/// Play this turn's animations to the end, one frame per flush. Only what
+/// happens in sight is shown.
+pub fn play_animations(tcod: &mut Tcod, game: &mut Game, objects: &[Object]) {
+    let animations: Vec<Animation> = game.animations.drain(..).collect();
+    if tcod.settings.skip_animations {
+        return;
+    }
+    let (width, height) = viewport_size(&*tcod.ui);
+    for (index, animation) in animations.into_iter().enumerate() {
+        for frame in animation.frames() {
+            tcod.ui.clear(Layer::Map);
+            // The player may have moved since the last frame was drawn.
+            render_all(tcod, game, objects, index == 0);
+            for (x, y, char, color) in frame {
+                let (screen_x, screen_y) = (x - tcod.camera.0, y - tcod.camera.1);
+                let on_screen = screen_x >= 0 && screen_y >= 0 && screen_x < width && screen_y < height;
+                if !on_screen || !tcod.fov.is_in_fov(x, y) {
+                    continue;
+                }
+                match char {
+                    Some(char) => {
+                        tcod.ui.set_default_foreground(Layer::Screen, color);
+                        let flag = BackgroundFlag::None;
+                        tcod.ui.put_char(Layer::Screen, screen_x, screen_y, char, flag);
+                    }
+                    None => {
+                        let flag = BackgroundFlag::Set;
+                        tcod.ui.set_char_background(Layer::Screen, screen_x, screen_y, color, flag);
+                    }
+                }
+            }
+            tcod.ui.flush();
+        }
+    }
+}
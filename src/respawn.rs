@@ -0,0 +1,52 @@
+//! New monsters wandering in as turns pass, so a cleared level never stays
+//! safe for long, and resting has its risks. They only ever turn up out of
+//! sight, somewhere the player has already been, and more often deeper down.
+
+use rand::Rng;
+
+use crate::config::config;
+use crate::fov::Fov;
+use crate::game::{Game, GameMode};
+use crate::object::{Object, PLAYER, random_monster};
+use crate::summon::spawn_monster;
+
+pub const RESPAWN_CHANCE: f32 = 0.005; // Chance each turn of a monster turning up at the first level
+pub const RESPAWN_PER_LEVEL: f32 = 0.002; // Added to the chance for each level deeper
+pub const RESPAWN_MIN_DISTANCE: f32 = 10.0; // No closer than this to the player
+pub const RESPAWN_MAX_MONSTERS: usize = 30; // None turn up while the level has this many
+const RESPAWN_TRIES: u32 = 20;
+
+/// Now and then, let a new monster turn up on an explored tile out of the
+/// player's sight.
+pub fn tick_respawn(fov: &Fov, game: &mut Game, objects: &mut Vec<Object>) {
+    match game.mode {
+        GameMode::Tutorial(_) | GameMode::Arena(_) => return,
+        _ if game.dungeon_level == 0 => return,
+        _ => {}
+    }
+    let chance = RESPAWN_CHANCE + RESPAWN_PER_LEVEL * (game.dungeon_level - 1) as f32;
+    if game.rng.next_f32() >= chance {
+        return;
+    }
+    let monsters = objects.iter().filter(|o| o.alive && o.ai.is_some()).count();
+    if monsters >= RESPAWN_MAX_MONSTERS {
+        return;
+    }
+    let (px, py) = objects[PLAYER].pos();
+    for _ in 0..RESPAWN_TRIES {
+        let x = game.rng.gen_range(0, config().map.width);
+        let y = game.rng.gen_range(0, config().map.height);
+        let distance = (((x - px).pow(2) + (y - py).pow(2)) as f32).sqrt();
+        let tile = &game.map[x as usize][y as usize];
+        let suitable = tile.explored
+            && !tile.terrain.deadly()
+            && !fov.is_in_fov(x, y)
+            && distance >= RESPAWN_MIN_DISTANCE
+            && !Object::is_blocked(x, y, &game.map, objects);
+        if suitable {
+            let monster = random_monster(x, y, game.branch, &mut game.rng);
+            spawn_monster(monster, game, objects);
+            return;
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Reading and writing the files the game keeps from one run to the next.
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::backend::Backend;
+use crate::ui::msgbox;
+
+/// Read a JSON file, or `None` if it is missing or unreadable.
+pub fn read_json<T: DeserializeOwned>(path: &str) -> Option<T> {
+    let mut json = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut json))
+        .ok()
+        .and_then(|_| serde_json::from_str(&json).ok())
+}
+
+pub fn write_json<T: Serialize>(path: &str, value: &T) -> Result<(), Box<dyn Error>> {
+    let json = serde_json::to_string(value)?;
+    let mut file = File::create(path)?;
+    file.write_all(json.as_bytes())?;
+    Ok(())
+}
+
+/// A file kept from one run, or one release, to the next. It is written
+/// along with the version of its format, so that files from older releases
+/// can be brought up to date when read instead of being thrown away.
+pub trait SaveFile: Serialize + DeserializeOwned {
+    const PATH: &'static str;
+    /// Whether the file is compressed and checksummed, rather than left as
+    /// plain JSON for players to edit.
+    const COMPRESSED: bool = true;
+    /// Steps from each version of the format to the next: the first one turns
+    /// version 0 into version 1, and so on. The current version is their count.
+    const MIGRATIONS: &'static [fn(&mut serde_json::Value)] = &[];
+}
+
+#[derive(Serialize)]
+pub struct Versioned<'a, T> {
+    pub format_version: usize,
+    pub data: &'a T,
+}
+
+/// Marks a compressed save file. It is followed by the deflated JSON, and
+/// then the CRC-32 of those compressed bytes.
+pub const SAVE_MAGIC: &[u8] = b"RLSAVE\0";
+
+thread_local! {
+    /// Files that could not be read, waiting to be reported to the player.
+    pub static LOAD_ERRORS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+/// Read a save file, migrating it from older versions as needed. A file that
+/// exists but cannot be read, or comes from a newer release, is copied aside
+/// to `<file>.bak` first so it is not lost when a fresh one is written, and
+/// the problem is kept for `report_load_errors`.
+pub fn load_save<T: SaveFile>() -> Option<T> {
+    let bytes = fs::read(T::PATH).ok()?;
+    match parse_save(&bytes) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            let backup = format!("{}.bak", T::PATH);
+            let _ = fs::copy(T::PATH, &backup);
+            let error = tr!("save.unreadable", T::PATH, e, backup);
+            LOAD_ERRORS.with(|errors| errors.borrow_mut().push(error));
+            None
+        }
+    }
+}
+
+/// Tell the player about any files that had to be set aside or ignored.
+pub fn report_load_errors(ui: &mut dyn Backend) {
+    let errors = LOAD_ERRORS.with(|errors| errors.replace(vec![]));
+    for error in errors {
+        msgbox(&format!("\n{}\n", error), 60, ui);
+    }
+}
+
+/// Undo the compression of a save file, checking it is whole first.
+/// Files without the marker are taken to be plain JSON.
+pub fn decompress_save(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    if !bytes.starts_with(SAVE_MAGIC) {
+        return Ok(bytes.to_vec());
+    }
+    let body = &bytes[SAVE_MAGIC.len()..];
+    if body.len() < 4 {
+        return Err(tr!("save.truncated").into());
+    }
+    let (compressed, checksum) = body.split_at(body.len() - 4);
+    let expected = u32::from_le_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+    if crc32fast::hash(compressed) != expected {
+        return Err(tr!("save.checksum").into());
+    }
+    let mut json = vec![];
+    flate2::read::DeflateDecoder::new(compressed).read_to_end(&mut json)?;
+    Ok(json)
+}
+
+pub fn parse_save<T: SaveFile>(bytes: &[u8]) -> Result<T, Box<dyn Error>> {
+    let json: serde_json::Value = serde_json::from_slice(&decompress_save(bytes)?)?;
+    let (version, mut data) = match json {
+        serde_json::Value::Object(mut fields) if fields.contains_key("format_version") => {
+            let version = fields["format_version"].as_u64().ok_or_else(|| tr!("save.bad-version"))?;
+            (version as usize, fields.remove("data").ok_or_else(|| tr!("save.no-data"))?)
+        }
+        // Files written before saves were versioned hold just the data.
+        other => (0, other),
+    };
+    if version > T::MIGRATIONS.len() {
+        return Err(tr!("save.too-new").into());
+    }
+    for migrate in &T::MIGRATIONS[version..] {
+        migrate(&mut data);
+    }
+    Ok(serde_json::from_value(data)?)
+}
+
+pub fn write_save<T: SaveFile>(value: &T) -> Result<(), Box<dyn Error>> {
+    write_save_as::<T, T>(value)
+}
+
+/// Write a save file of type `T` from something that serializes the same
+/// way, such as a view borrowing the parts of a `T` instead of owning them.
+pub fn write_save_as<T: SaveFile, V: Serialize>(value: &V) -> Result<(), Box<dyn Error>> {
+    let versioned = Versioned {
+        format_version: T::MIGRATIONS.len(),
+        data: value,
+    };
+    if !T::COMPRESSED {
+        return write_json(T::PATH, &versioned);
+    }
+    let mut file = File::create(T::PATH)?;
+    file.write_all(&compress_save(&versioned)?)?;
+    Ok(())
+}
+
+/// The bytes of a compressed save file holding the value: the marker, the
+/// deflated JSON and its checksum.
+pub fn compress_save<V: Serialize>(value: &V) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut encoder = flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+    serde_json::to_writer(&mut encoder, value)?;
+    let compressed = encoder.finish()?;
+    let mut bytes = SAVE_MAGIC.to_vec();
+    bytes.extend_from_slice(&compressed);
+    bytes.extend_from_slice(&crc32fast::hash(&compressed).to_le_bytes());
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    /// A save file whose format changed once: `hp` used to be called `health`.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Hero {
+        name: String,
+        hp: i32,
+    }
+
+    fn rename_health(data: &mut serde_json::Value) {
+        if let Some(fields) = data.as_object_mut() {
+            if let Some(health) = fields.remove("health") {
+                fields.insert("hp".into(), health);
+            }
+        }
+    }
+
+    impl SaveFile for Hero {
+        const PATH: &'static str = "hero-test-save";
+        const MIGRATIONS: &'static [fn(&mut serde_json::Value)] = &[rename_health];
+    }
+
+    fn hero() -> Hero {
+        Hero {
+            name: "Ayla".into(),
+            hp: 30,
+        }
+    }
+
+    fn saved(hero: &Hero) -> Vec<u8> {
+        let versioned = Versioned {
+            format_version: Hero::MIGRATIONS.len(),
+            data: hero,
+        };
+        compress_save(&versioned).unwrap()
+    }
+
+    #[test]
+    fn compressed_save_round_trips() {
+        let bytes = saved(&hero());
+        assert!(bytes.starts_with(SAVE_MAGIC));
+        assert_eq!(parse_save::<Hero>(&bytes).unwrap(), hero());
+    }
+
+    #[test]
+    fn damaged_save_fails_its_checksum() {
+        let mut bytes = saved(&hero());
+        let middle = SAVE_MAGIC.len() + 2;
+        bytes[middle] ^= 0xff;
+        assert!(parse_save::<Hero>(&bytes).is_err());
+    }
+
+    #[test]
+    fn truncated_save_is_rejected() {
+        let bytes = saved(&hero());
+        assert!(parse_save::<Hero>(&bytes[..SAVE_MAGIC.len() + 2]).is_err());
+        assert!(parse_save::<Hero>(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn unversioned_save_is_migrated() {
+        let bytes = br#"{"name": "Ayla", "health": 30}"#;
+        assert_eq!(parse_save::<Hero>(bytes).unwrap(), hero());
+    }
+
+    #[test]
+    fn older_version_is_migrated() {
+        let bytes = compress_save(&serde_json::json!({
+            "format_version": 0,
+            "data": {"name": "Ayla", "health": 30},
+        }))
+        .unwrap();
+        assert_eq!(parse_save::<Hero>(&bytes).unwrap(), hero());
+    }
+
+    #[test]
+    fn current_version_is_not_migrated_again() {
+        let bytes = br#"{"format_version": 1, "data": {"name": "Ayla", "hp": 30, "health": 1}}"#;
+        assert_eq!(parse_save::<Hero>(bytes).unwrap(), hero());
+    }
+
+    #[test]
+    fn newer_version_is_refused() {
+        let bytes = br#"{"format_version": 2, "data": {"name": "Ayla", "hp": 30}}"#;
+        assert!(parse_save::<Hero>(bytes).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! Scavenging: orcs and other clever monsters pick up what they find lying
+//! about, drink any healing potion they carry once badly hurt, and drop
+//! whatever they still have on them when they die.
+
+use crate::config::config;
+use crate::fov::{Fov, in_plain_sight};
+use crate::game::Game;
+use crate::items::{Item, stack_name};
+use crate::locale::display_name;
+use crate::map::Map;
+use crate::messages::MessageCategory;
+use crate::object::Object;
+
+pub const MONSTER_INVENTORY_SIZE: usize = 3;
+pub const SCAVENGE_DISTANCE: f32 = 3.0; // How far out of its way a monster goes for an item
+
+/// Whether the item is there for the taking: anything lying about, as long
+/// as it is not a shop's, waiting to be paid for.
+fn up_for_grabs(object: &Object) -> bool {
+    object.item.is_some() && object.price.is_none()
+}
+
+/// Whether the monster has the wits, and the room, to take another item.
+pub fn can_carry_more(monster: &Object) -> bool {
+    monster.intelligent && monster.inventory.len() < MONSTER_INVENTORY_SIZE
+}
+
+/// Where the item the monster would rather go for than its enemy lies, if
+/// any: the nearest one in plain sight, close by, and closer than the enemy.
+pub fn item_to_grab(
+    monster_id: usize,
+    target_id: usize,
+    map: &Map,
+    objects: &[Object],
+) -> Option<(i32, i32)>
+{
+    let monster = &objects[monster_id];
+    if !can_carry_more(monster) {
+        return None;
+    }
+    let reach = SCAVENGE_DISTANCE.min(monster.distance_to(&objects[target_id]));
+    objects
+        .iter()
+        .filter(|object| up_for_grabs(object) && object.pos() != monster.pos())
+        .filter(|object| object.distance_to(monster) < reach)
+        .filter(|object| in_plain_sight(map, monster.pos(), object.pos()))
+        .min_by_key(|object| (object.x - monster.x).pow(2) + (object.y - monster.y).pow(2))
+        .map(Object::pos)
+}
+
+/// Drink a healing potion, if the monster carries one and is badly hurt.
+/// Returns whether it did.
+pub fn drink_healing_potion(
+    monster_id: usize,
+    fov: &Fov,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> bool
+{
+    let monster = &mut objects[monster_id];
+    if !monster.badly_hurt() {
+        return false;
+    }
+    let index = match monster.inventory.iter().position(|item| item.item == Some(Item::Heal)) {
+        Some(index) => index,
+        None => return false,
+    };
+    let potion = monster.inventory.remove(index);
+    monster.heal(config().gameplay.heal_amount);
+    if fov.is_in_fov(monster.x, monster.y) {
+        game.messages.add(
+            tr!(
+                "monster-drinks",
+                display_name(&monster.name),
+                stack_name(&potion, &game.identification)
+            ),
+            MessageCategory::Combat,
+        );
+    }
+    true
+}
+
+/// Once the monsters have moved, let every one that can carry more pick up
+/// whatever it is standing on.
+pub fn pick_up_items(fov: &Fov, game: &mut Game, objects: &mut Vec<Object>) {
+    loop {
+        let found = objects.iter().enumerate().find_map(|(monster_id, monster)| {
+            if !monster.alive || monster.ai.is_none() || !can_carry_more(monster) {
+                return None;
+            }
+            objects
+                .iter()
+                .position(|object| up_for_grabs(object) && object.pos() == monster.pos())
+                .map(|item_id| (monster_id, item_id))
+        });
+        let (monster_id, item_id) = match found {
+            Some(found) => found,
+            None => return,
+        };
+        let item = objects.remove(item_id);
+        let monster_id = if monster_id > item_id { monster_id - 1 } else { monster_id };
+        let monster = &mut objects[monster_id];
+        if fov.is_in_fov(monster.x, monster.y) {
+            game.messages.add(
+                tr!(
+                    "monster-picks-up",
+                    display_name(&monster.name),
+                    stack_name(&item, &game.identification)
+                ),
+                MessageCategory::Combat,
+            );
+        }
+        monster.inventory.push(item);
+    }
+}
+
+/// Let fall everything the dead monster carried, to be put on the map where
+/// it died.
+pub fn spill_inventory(monster: &mut Object, game: &mut Game) {
+    for mut item in monster.inventory.drain(..) {
+        item.set_pos(monster.x, monster.y);
+        game.dropped.push(item);
+    }
+}
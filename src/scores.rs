@@ -0,0 +1,113 @@
+//! High scores: the best finished runs, kept across runs and shown from the
+//! main menu and at the end of each run.
+
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::Backend;
+use crate::game::{Game, GameMode};
+use crate::locale::display_name;
+use crate::object::{Object, PLAYER};
+use crate::save::{SaveFile, load_save, write_save};
+use crate::ui::msgbox;
+
+pub const SCORES_FILE: &str = "scores.json";
+pub const DEPTH_SCORE: u32 = 100; // Points for each level of the dungeon reached
+pub const SHOWN_SCORES: usize = 10;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub scores: Vec<HighScore>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    pub depth: u32,
+    /// What killed the character, if it died.
+    pub cause_of_death: Option<String>,
+    pub turns: u32,
+    pub date: String,
+}
+
+impl SaveFile for HighScores {
+    const PATH: &'static str = SCORES_FILE;
+}
+
+impl HighScores {
+    /// Load the scores from disk, starting afresh if there are none yet.
+    pub fn load() -> Self {
+        load_save().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_save(self)
+    }
+}
+
+/// The run's score: its experience and gold, and a good deal more for each
+/// level of depth reached.
+pub fn run_score(game: &Game, objects: &[Object]) -> u32 {
+    let xp = objects[PLAYER].fighter.map_or(0, |f| f.xp.max(0) as u32);
+    xp + game.gold + game.dungeon_level * DEPTH_SCORE
+}
+
+/// Add a finished run to the high scores. Only runs that can be compared
+/// count: demos, the tutorial and the arena, which has its own scores, do
+/// not. Returns the new score, if the run counted.
+pub fn record_high_score(game: &Game, objects: &[Object], name: &str) -> Option<HighScore> {
+    match game.mode {
+        GameMode::Normal | GameMode::Daily(_) | GameMode::Custom(_) => {}
+        _ => return None,
+    }
+    let score = HighScore {
+        name: name.into(),
+        score: run_score(game, objects),
+        depth: game.dungeon_level,
+        cause_of_death: if objects[PLAYER].alive {
+            None
+        } else {
+            game.last_attacker.clone()
+        },
+        turns: game.turn,
+        date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+    };
+    let mut scores = HighScores::load();
+    scores.scores.push(score.clone());
+    // There is nowhere left to report the error, so a lost score is all we can do.
+    let _ = scores.save();
+    Some(score)
+}
+
+/// Show the best scores, best first. A score just made is marked, so the
+/// player can see where their run placed.
+pub fn show_high_scores(latest: Option<&HighScore>, ui: &mut dyn Backend) {
+    let mut scores = HighScores::load().scores;
+    scores.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.turns.cmp(&b.turns)));
+
+    let mut text = format!("{}\n\n", tr!("scores.title"));
+    if scores.is_empty() {
+        text.push_str(&format!("{}\n", tr!("scores.none")));
+    }
+    for (rank, score) in scores.iter().take(SHOWN_SCORES).enumerate() {
+        let outcome = match score.cause_of_death {
+            Some(ref cause) => tr!("scores.killed-by", display_name(cause)),
+            None => tr!("scores.survived"),
+        };
+        let marker = if Some(score) == latest { '>' } else { ' ' };
+        text.push_str(&tr!(
+            "scores.entry",
+            marker,
+            format!("{:>2}", rank + 1),
+            format!("{:>6}", score.score),
+            score.name,
+            score.depth,
+            score.turns,
+            outcome
+        ));
+        text.push('\n');
+    }
+    msgbox(&text, 60, ui);
+}
@@ -0,0 +1,122 @@
+//! Settings changed from the options menu and saved by the game.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::action::Action;
+use crate::config::{FovSetting, config};
+use crate::messages::{MessageCategory, MessageStyle};
+use crate::save::{SaveFile, load_save, write_save};
+use crate::ui::Danger;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Player preferences that carry over between runs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Settings {
+    pub language: String,
+    /// Only categories the player changed are stored; the rest use their defaults.
+    #[serde(default)]
+    pub message_styles: BTreeMap<MessageCategory, MessageStyle>,
+    /// Name shown next to the player's runs on the online leaderboard.
+    #[serde(default = "default_player_name")]
+    pub player_name: String,
+    /// Where run summaries are sent. Nothing is sent unless the player sets one.
+    #[serde(default)]
+    pub score_server: Option<String>,
+    /// Background music volume, in percent.
+    #[serde(default = "default_volume")]
+    pub music_volume: u32,
+    /// Sound effect and ambience volume, in percent.
+    #[serde(default = "default_volume")]
+    pub sound_volume: u32,
+    /// Window size in cells, if the player picked one over `config.toml`'s.
+    #[serde(default)]
+    pub window_size: Option<(i32, i32)>,
+    /// FOV options, if the player picked them over `config.toml`'s.
+    #[serde(default)]
+    pub fov_algorithm: Option<FovSetting>,
+    #[serde(default)]
+    pub light_walls: Option<bool>,
+    /// Whether to carry straight on without playing visual effects.
+    #[serde(default)]
+    pub skip_animations: bool,
+    /// How long a movement key is held before it repeats, in milliseconds.
+    #[serde(default = "default_key_repeat_delay")]
+    pub key_repeat_delay: u32,
+    /// Recorded macros, by the number of the slot in `config.toml` that plays them.
+    #[serde(default)]
+    pub macros: BTreeMap<u32, Vec<Action>>,
+    /// Dangerous actions the player no longer wants to be asked about.
+    #[serde(default)]
+    pub unconfirmed: BTreeSet<Danger>,
+    /// Keep saved games when they are loaded and when the player dies, so a
+    /// run can be taken up again from its last save.
+    #[serde(default)]
+    pub casual: bool,
+}
+
+pub fn default_volume() -> u32 {
+    50
+}
+
+pub fn default_key_repeat_delay() -> u32 {
+    300
+}
+
+pub fn default_player_name() -> String {
+    "Anonymous".into()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            language: "en".into(),
+            message_styles: BTreeMap::new(),
+            player_name: default_player_name(),
+            score_server: None,
+            music_volume: default_volume(),
+            sound_volume: default_volume(),
+            window_size: None,
+            fov_algorithm: None,
+            light_walls: None,
+            skip_animations: false,
+            key_repeat_delay: default_key_repeat_delay(),
+            macros: BTreeMap::new(),
+            unconfirmed: BTreeSet::new(),
+            casual: false,
+        }
+    }
+}
+
+impl SaveFile for Settings {
+    const PATH: &'static str = SETTINGS_FILE;
+    const COMPRESSED: bool = false;
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        load_save().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_save(self)
+    }
+
+    pub fn fov_algorithm(&self) -> FovSetting {
+        self.fov_algorithm.unwrap_or(config().fov.algorithm)
+    }
+
+    pub fn light_walls(&self) -> bool {
+        self.light_walls.unwrap_or(config().fov.light_walls)
+    }
+
+    pub fn message_style(&self, category: MessageCategory) -> MessageStyle {
+        self.message_styles
+            .get(&category)
+            .cloned()
+            .unwrap_or_else(|| category.default_style())
+    }
+}
@@ -0,0 +1,321 @@
+//! Shops, vendors and their guards.
+
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::Ai;
+use crate::equipment::take_off;
+use crate::faction::{Faction, Standing, standing};
+use crate::game::{Game, GameEvent};
+use crate::items::{
+    Item, add_to_inventory, create_item, has_room_for, item_price, restack, take_from_inventory,
+};
+use crate::locale::display_name;
+use crate::map::{Rect, random_free_tile};
+use crate::messages::MessageCategory;
+use crate::npc::Npc;
+use crate::object::{DeathCallback, Fighter, Object, PLAYER};
+use crate::tcod_types::colors::*;
+use crate::ui::{Tcod, inventory_menu, menu};
+
+pub const VENDOR_CHANCE: f32 = 0.5;
+// Shops
+pub const SHOP_CHANCE: f32 = 0.25; // Chance of a shop on each level
+pub const SHOP_GUARDS: u32 = 3; // Guards who answer a shopkeeper's alarm
+
+/// Swap one of the player's items for one of the NPC's wares.
+pub fn open_shop(npc_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) {
+    let wares: Vec<Object> = objects[npc_id]
+        .npc
+        .as_ref()
+        .map_or(vec![], |npc| npc.wares.iter().map(|&item| create_item(item, 0, 0)).collect());
+    if wares.is_empty() {
+        game.messages.add(tr!("shop.nothing", display_name(&objects[npc_id].name)), MessageCategory::Item);
+        return;
+    }
+    if let Some(faction) = objects[npc_id].faction {
+        if standing(game, faction) != Standing::Friendly {
+            game.messages.add(
+                tr!("shop.distrust", display_name(&objects[npc_id].name)),
+                MessageCategory::Item,
+            );
+            return;
+        }
+    }
+
+    let identification = &game.identification;
+    let wanted = match inventory_menu(&wares, identification, &tr!("shop.wares"), &mut *tcod.ui) {
+        Some(index) => index,
+        None => return,
+    };
+    let header = tr!("shop.offer");
+    let offered = match inventory_menu(&game.inventory, identification, &header, &mut *tcod.ui) {
+        Some(index) => index,
+        None => return,
+    };
+    let item = match game.inventory[offered].item {
+        Some(Item::Artifact) | None => {
+            game.messages.add(tr!("shop.refused"), MessageCategory::Item);
+            return;
+        }
+        Some(item) => item,
+    };
+
+    // What is bought needs a slot of its own if what is sold leaves one behind.
+    let leaves_slot = game.inventory[offered].count == 1;
+    if !leaves_slot && !has_room_for(&game.inventory, &wares[wanted]) {
+        game.messages.add(
+            tr!("inventory-full", game.identification.name_of(&wares[wanted])),
+            MessageCategory::Warning,
+        );
+        return;
+    }
+    // Anything worn has to come off before it can change hands.
+    if !take_off(offered, game, objects) {
+        return;
+    }
+
+    if let Some(npc) = objects[npc_id].npc.as_mut() {
+        let bought = npc.wares.swap_remove(wanted);
+        npc.wares.push(item);
+        let sold = take_from_inventory(&mut game.inventory, offered);
+        let (x, y) = objects[PLAYER].pos();
+        let bought = create_item(bought, x, y);
+        let ids = &game.identification;
+        game.messages.add(
+            tr!("shop.traded", ids.name_of(&sold), ids.name_of(&bought)),
+            MessageCategory::Item,
+        );
+        let _ = add_to_inventory(&mut game.inventory, bought);
+    }
+}
+
+/// The shop on the current level. Its goods are the items on the floor of
+/// its room that still carry a price.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Shop {
+    pub room: Rect,
+    pub faction: Faction,
+    /// Whether the player was in the shop at the end of their last turn.
+    pub customer_inside: bool,
+    /// The price of unpaid goods the player has already used up.
+    pub debt: u32,
+}
+
+/// Sometimes turn one of the level's rooms into a shop.
+pub fn place_shop(rooms: &[Rect], game: &mut Game, objects: &mut Vec<Object>) {
+    game.shop = None;
+    let rng = &mut game.rng;
+    // Never in the first room, where the player starts, nor the last, where the stairs are.
+    if rooms.len() < 3 || rng.next_f32() >= SHOP_CHANCE {
+        return;
+    }
+    let room = rooms[rng.gen_range(1, rooms.len() - 1)];
+    let faction = Faction::Tombfolk;
+
+    // Clear out the monsters and items the room was generated with.
+    objects.retain(|object| {
+        !room.contains(object.x, object.y) || (object.ai.is_none() && object.item.is_none())
+    });
+
+    let (x, y) = room.center();
+    let mut keeper = Object::new(x, y, '@', "shopkeeper", GOLD, true);
+    keeper.npc = Some(Npc {
+        quest: None,
+        given: None,
+        wares: vec![],
+        shopkeeper: true,
+    });
+    keeper.faction = Some(faction);
+    objects.push(keeper);
+
+    for _ in 0..rng.gen_range(3, 7) {
+        let x = rng.gen_range(room.x1 + 1, room.x2);
+        let y = rng.gen_range(room.y1 + 1, room.y2);
+        if !objects.iter().any(|object| object.pos() == (x, y)) {
+            let item = [Item::Heal, Item::Lightning, Item::Confuse][rng.gen_range(0, 3)];
+            let mut goods = create_item(item, x, y);
+            goods.price = Some(item_price(item));
+            objects.push(goods);
+        }
+    }
+
+    game.shop = Some(Shop {
+        room: room,
+        faction: faction,
+        customer_inside: false,
+        debt: 0,
+    });
+}
+
+/// Let the player pay for the goods they are carrying, or hand them back.
+pub fn serve_customer(npc_id: usize, tcod: &mut Tcod, game: &mut Game, objects: &mut Vec<Object>) {
+    let name = display_name(&objects[npc_id].name);
+    if objects[npc_id].faction.map_or(false, |faction| game.thief_of.contains(&faction)) {
+        game.messages.add(tr!("shop.banned", name), MessageCategory::Warning);
+        return;
+    }
+    let debt = game.shop.map_or(0, |shop| shop.debt);
+    let bill = debt
+        + game
+            .inventory
+            .iter()
+            .filter_map(|item| item.price.map(|price| price * item.count))
+            .sum::<u32>();
+    if bill == 0 {
+        game.messages.add(tr!("shop.welcome", name), MessageCategory::Item);
+        return;
+    }
+
+    let choices = [tr!("shop.pay"), tr!("shop.return"), tr!("shop.not-yet")];
+    match menu(&tr!("shop.bill", bill, game.gold), &choices, 50, &mut *tcod.ui) {
+        Some(0) if game.gold >= bill => {
+            game.gold -= bill;
+            for item in game.inventory.iter_mut() {
+                item.price = None;
+            }
+            restack(&mut game.inventory);
+            if let Some(shop) = game.shop.as_mut() {
+                shop.debt = 0;
+            }
+            game.messages.add(tr!("shop.paid", bill), MessageCategory::Item);
+        }
+        Some(0) => {
+            game.messages.add(tr!("shop.cannot-afford"), MessageCategory::Warning);
+        }
+        Some(1) => {
+            // Unpaid goods go back on the floor, right where the player stands.
+            let (x, y) = objects[PLAYER].pos();
+            let (mut unpaid, paid): (Vec<Object>, Vec<Object>) =
+                game.inventory.drain(..).partition(|item| item.price.is_some());
+            game.inventory = paid;
+            for item in unpaid.iter_mut() {
+                item.set_pos(x, y);
+            }
+            objects.extend(unpaid);
+            game.messages.add(tr!("shop.returned"), MessageCategory::Item);
+        }
+        _ => {}
+    }
+}
+
+/// The shopkeeper still waiting on the player to pay for something, if any.
+pub fn unpaid_keeper(game: &Game, objects: &[Object]) -> Option<usize> {
+    let debt = game.shop.as_ref()?.debt;
+    let unpaid = debt > 0 || game.inventory.iter().any(|item| item.price.is_some());
+    if !unpaid {
+        return None;
+    }
+    objects
+        .iter()
+        .position(|object| object.npc.as_ref().map_or(false, |npc| npc.shopkeeper))
+}
+
+/// Catch the player walking out of the shop with unpaid goods.
+pub fn watch_shop(game: &mut Game, objects: &mut Vec<Object>) {
+    let (x, y) = objects[PLAYER].pos();
+    let shop = match game.shop.as_mut() {
+        Some(shop) => shop,
+        None => return,
+    };
+    let left = shop.customer_inside && !shop.room.contains(x, y);
+    shop.customer_inside = shop.room.contains(x, y);
+    let (room, faction) = (shop.room, shop.faction);
+
+    let keeper_id = match unpaid_keeper(game, objects) {
+        Some(id) if left && objects[PLAYER].alive => id,
+        _ => return,
+    };
+
+    // The shopkeeper is no pushover.
+    game.messages.add(tr!("shop.stop-thief", display_name(&objects[keeper_id].name)), MessageCategory::Warning);
+    let keeper = &mut objects[keeper_id];
+    keeper.npc = None;
+    keeper.fighter = Some(Fighter {
+        max_hp: 60,
+        hp: 60,
+        defense: 4,
+        power: 10,
+        xp: 300,
+        venom: None,
+        on_death: DeathCallback::Monster,
+    });
+    keeper.ai = Some(Ai::Basic);
+    keeper.alive = true;
+
+    // The alarm brings the guards, and stirs the rest of the faction.
+    for object in objects.iter_mut() {
+        if object.faction != Some(faction) {
+            continue;
+        }
+        object.wake_up();
+        if object.ai == Some(Ai::Idle) {
+            object.ai = Some(Ai::Basic);
+        }
+    }
+    let rng = &mut game.rng;
+    for _ in 0..SHOP_GUARDS {
+        let x = rng.gen_range(room.x1 + 1, room.x2);
+        let y = rng.gen_range(room.y1 + 1, room.y2);
+        if !Object::is_blocked(x, y, &game.map, objects) {
+            let mut guard = Object::new(x, y, 'G', "guard", LIGHT_BLUE, true);
+            guard.fighter = Some(Fighter {
+                max_hp: 20,
+                hp: 20,
+                defense: 2,
+                power: 6,
+                xp: 75,
+                venom: None,
+                on_death: DeathCallback::Monster,
+            });
+            guard.ai = Some(Ai::Basic);
+            guard.faction = Some(faction);
+            guard.alive = true;
+            objects.push(guard);
+        }
+    }
+    game.messages.add(tr!("shop.alarm"), MessageCategory::Warning);
+    game.events.push(GameEvent::Theft { faction: faction });
+}
+
+/// Make a talking NPC into an ordinary hostile monster.
+pub fn turn_hostile(npc_id: usize, game: &mut Game, objects: &mut [Object]) {
+    let npc = &mut objects[npc_id];
+    game.messages.add(tr!("npc.hostile", display_name(&npc.name)), MessageCategory::Warning);
+    npc.npc = None;
+    npc.fighter = Some(Fighter {
+        max_hp: 12,
+        hp: 12,
+        defense: 1,
+        power: 4,
+        xp: 35,
+        venom: None,
+        on_death: DeathCallback::Monster,
+    });
+    npc.ai = Some(Ai::Basic);
+    npc.alive = true;
+}
+
+/// Factions the player is friends with send traders to the level.
+pub fn place_vendors(game: &mut Game, objects: &mut Vec<Object>) {
+    for &faction in &Faction::ALL {
+        if standing(game, faction) != Standing::Friendly || game.rng.next_f32() >= VENDOR_CHANCE {
+            continue;
+        }
+        let (name, glyph) = faction.vendor();
+        let rng = &mut game.rng;
+        let (x, y) = random_free_tile(&game.map, objects, rng);
+        let mut vendor = Object::new(x, y, glyph, name, GOLD, true);
+        vendor.npc = Some(Npc {
+            quest: None,
+            given: None,
+            wares: (0..rng.gen_range(3, 6))
+                .map(|_| [Item::Heal, Item::Lightning, Item::Confuse][rng.gen_range(0, 3)])
+                .collect(),
+            shopkeeper: false,
+        });
+        vendor.faction = Some(faction);
+        objects.push(vendor);
+    }
+}
@@ -0,0 +1,40 @@
+//! Sleeping monsters: most are found dozing, and wake when the player comes
+//! close, when something makes a noise nearby, or when they get hurt. A
+//! careful player can sneak past them, or strike first for extra damage.
+
+use rand::{Rng, StdRng};
+
+use crate::ai::Ai;
+use crate::object::Object;
+
+pub const SLEEP_CHANCE: f32 = 0.6; // Chance of a monster being found asleep
+pub const WAKE_DISTANCE: f32 = 6.0; // The player can only disturb sleepers closer than this
+pub const WAKE_CHANCE: f32 = 0.3; // Chance each turn of waking with the player right beside it
+pub const SNEAK_ATTACK_MULTIPLIER: i32 = 2; // Damage done to a sleeper is multiplied by this
+
+/// Put the monster to sleep, now and then, as it is placed on a new level.
+pub fn maybe_put_to_sleep(monster: &mut Object, rng: &mut StdRng) {
+    if rng.next_f32() < SLEEP_CHANCE {
+        if let Some(ai) = monster.ai.take() {
+            monster.ai = Some(Ai::Sleeping {
+                previous_ai: Box::new(ai),
+            });
+        }
+    }
+}
+
+impl Object {
+    pub fn is_asleep(&self) -> bool {
+        matches!(self.ai, Some(Ai::Sleeping { .. }))
+    }
+
+    /// Wake it up, if it is asleep, to go back to its usual ways.
+    pub fn wake_up(&mut self) {
+        if !self.is_asleep() {
+            return;
+        }
+        if let Some(Ai::Sleeping { previous_ai }) = self.ai.take() {
+            self.ai = Some(*previous_ai);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Status effects: lasting conditions such as poison, burning, confusion
+//! and blindness, which act on whoever carries them once a turn until they
+//! wear off. The player and monsters carry them alike.
+
+use rand::{Rng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::fov::Fov;
+use crate::game::Game;
+use crate::locale::display_name;
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+
+/// Chance, each turn, that a burning creature sets each of its neighbours alight.
+pub const BURN_SPREAD_CHANCE: f32 = 0.3;
+/// Chance that a confused player's step goes some other way than meant.
+pub const CONFUSED_STUMBLE_CHANCE: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// Damage every turn.
+    Poison,
+    /// Damage every turn, and it spreads to whoever stands close by.
+    Burning,
+    /// Healing every turn.
+    Regeneration,
+    /// Loses every other turn.
+    Slow,
+    /// Stumbles about at random.
+    Confusion,
+    /// Sees no further than arm's length; a monster stumbles about.
+    Blindness,
+}
+
+impl StatusKind {
+    pub fn name(self) -> String {
+        match self {
+            StatusKind::Poison => tr!("status.poison"),
+            StatusKind::Burning => tr!("status.burning"),
+            StatusKind::Regeneration => tr!("status.regeneration"),
+            StatusKind::Slow => tr!("status.slow"),
+            StatusKind::Confusion => tr!("status.confusion"),
+            StatusKind::Blindness => tr!("status.blindness"),
+        }
+    }
+}
+
+/// A status effect with the turns it has left, and the hit points it takes
+/// or gives each turn.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub turns: i32,
+    pub strength: i32,
+}
+
+impl Object {
+    /// Come down with an effect. Catching one again lasts for whichever of
+    /// the two would last longer, rather than doubling up.
+    pub fn add_effect(&mut self, effect: StatusEffect) {
+        match self.effects.iter_mut().find(|e| e.kind == effect.kind) {
+            Some(existing) => {
+                existing.turns = existing.turns.max(effect.turns);
+                existing.strength = existing.strength.max(effect.strength);
+            }
+            None => self.effects.push(effect),
+        }
+    }
+
+    pub fn has_effect(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Whether it is in no state to go where it means to.
+    pub fn is_stumbling(&self) -> bool {
+        self.has_effect(StatusKind::Confusion) || self.has_effect(StatusKind::Blindness)
+    }
+}
+
+/// One of the eight directions, picked at random.
+pub fn random_direction(rng: &mut StdRng) -> (i32, i32) {
+    let directions = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+    directions[rng.gen_range(0, directions.len())]
+}
+
+/// Let every effect act once on whoever carries it, then wear down.
+pub fn tick_effects(fov: &Fov, game: &mut Game, objects: &mut [Object]) {
+    // Fire that spreads this turn only takes hold once everyone has burned.
+    let mut spread = vec![];
+    for id in 0..objects.len() {
+        if objects[id].effects.is_empty() {
+            continue;
+        }
+        let effects = std::mem::take(&mut objects[id].effects);
+        let mut remaining = vec![];
+        for mut effect in effects {
+            if !objects[id].alive {
+                // Dead, so there is nothing left to act on.
+                break;
+            }
+            let (x, y) = objects[id].pos();
+            let seen = id == PLAYER || fov.is_in_fov(x, y);
+            match effect.kind {
+                StatusKind::Poison | StatusKind::Burning => {
+                    if seen {
+                        let message = if id == PLAYER {
+                            tr!("status.player-hurt", effect.kind.name(), effect.strength)
+                        } else {
+                            tr!(
+                                "status.hurt",
+                                display_name(&objects[id].name),
+                                effect.kind.name(),
+                                effect.strength
+                            )
+                        };
+                        game.messages.add(message, MessageCategory::Combat);
+                    }
+                    if id == PLAYER {
+                        game.last_attacker = Some(effect.kind.name());
+                    }
+                    if effect.kind == StatusKind::Burning {
+                        for (other_id, other) in objects.iter().enumerate() {
+                            if other_id != id
+                                && other.alive
+                                && other.fighter.is_some()
+                                && (other.x - x).abs() <= 1
+                                && (other.y - y).abs() <= 1
+                                && game.rng.next_f32() < BURN_SPREAD_CHANCE
+                            {
+                                spread.push((other_id, effect));
+                            }
+                        }
+                    }
+                    objects[id].take_damage(effect.strength, game);
+                }
+                StatusKind::Regeneration => objects[id].heal(effect.strength),
+                StatusKind::Slow | StatusKind::Confusion | StatusKind::Blindness => {}
+            }
+            effect.turns -= 1;
+            if effect.turns > 0 {
+                remaining.push(effect);
+            } else if id == PLAYER && objects[id].alive {
+                game.messages.add(tr!("status.ends", effect.kind.name()), MessageCategory::Item);
+            } else if seen && effect.kind == StatusKind::Confusion {
+                game.messages.add(
+                    tr!("no-longer-confused", display_name(&objects[id].name)),
+                    MessageCategory::Combat,
+                );
+            }
+        }
+        if objects[id].alive {
+            objects[id].effects = remaining;
+        }
+    }
+
+    for (id, effect) in spread {
+        if objects[id].alive && !objects[id].effects.iter().any(|e| e.kind == effect.kind) {
+            if id == PLAYER {
+                game.messages.add(tr!("status.player-catches-fire"), MessageCategory::Warning);
+            } else if fov.is_in_fov(objects[id].x, objects[id].y) {
+                game.messages.add(
+                    tr!("status.catches-fire", display_name(&objects[id].name)),
+                    MessageCategory::Combat,
+                );
+            }
+            objects[id].add_effect(effect);
+        }
+    }
+}
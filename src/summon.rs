@@ -0,0 +1,45 @@
+//! Monsters brought into play during the game rather than placed with the
+//! level: ones that wander in, and ones called up by summoners, like the
+//! orc shaman's jackals and the lich's skeletons.
+
+use crate::faction::apply_reputation;
+use crate::game::Game;
+use crate::map::{Map, map_blocked};
+use crate::object::Object;
+
+pub const SUMMONER_COOLDOWN: i32 = 6; // Turns between an orc shaman's calls
+pub const SUMMONS_PER_CALL: usize = 2;
+pub const SUMMONER_CAP: usize = 4; // It calls no more while this many are about
+pub const SUMMONER_REACH: f32 = 10.0; // How near its summons must be to count as about
+pub const SUMMONED_MONSTER: &str = "jackal";
+
+/// Put a monster on the map now, on whatever terms with the player its
+/// faction is on. Returns its index.
+pub fn spawn_monster(monster: Object, game: &Game, objects: &mut Vec<Object>) -> usize {
+    objects.push(monster);
+    let index = objects.len() - 1;
+    apply_reputation(game, &mut objects[index..]);
+    index
+}
+
+/// The tiles next to the given one that nothing stands on.
+pub fn free_tiles_around(x: i32, y: i32, map: &Map, objects: &[Object]) -> Vec<(i32, i32)> {
+    (-1..2)
+        .flat_map(|dx| (-1..2).map(move |dy| (x + dx, y + dy)))
+        .filter(|&(tile_x, tile_y)| {
+            !map_blocked(tile_x, tile_y, map)
+                && !map[tile_x as usize][tile_y as usize].terrain.deadly()
+                && !Object::is_blocked(tile_x, tile_y, map, objects)
+        })
+        .collect()
+}
+
+/// How many of the summoner's kind of summons are about, near enough to it.
+pub fn summons_about(summoner_id: usize, objects: &[Object]) -> usize {
+    let summoner = &objects[summoner_id];
+    objects
+        .iter()
+        .filter(|object| object.alive && object.name == SUMMONED_MONSTER)
+        .filter(|object| object.distance_to(summoner) < SUMMONER_REACH)
+        .count()
+}
@@ -0,0 +1,277 @@
+//! libtcod's colors, keys, mouse events and console flags, which the whole
+//! game uses as plain data whatever it is played in. With the `tcod` feature
+//! they are libtcod's own; without it, they are stand-ins of the same shape,
+//! so that the game builds for the terminal and headless backends on a
+//! machine without libtcod.
+
+#[cfg(feature = "tcod")]
+pub use tcod::{colors, console, input};
+
+#[cfg(not(feature = "tcod"))]
+pub mod colors {
+    use std::ops::{Add, Mul};
+
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Color {
+        pub r: u8,
+        pub g: u8,
+        pub b: u8,
+    }
+
+    impl Color {
+        pub const fn new(r: u8, g: u8, b: u8) -> Color {
+            Color { r: r, g: g, b: b }
+        }
+    }
+
+    impl Add for Color {
+        type Output = Color;
+
+        fn add(self, other: Color) -> Color {
+            Color {
+                r: self.r.saturating_add(other.r),
+                g: self.g.saturating_add(other.g),
+                b: self.b.saturating_add(other.b),
+            }
+        }
+    }
+
+    impl Mul for Color {
+        type Output = Color;
+
+        fn mul(self, other: Color) -> Color {
+            let channel = |a: u8, b: u8| (a as u32 * b as u32 / 255) as u8;
+            Color {
+                r: channel(self.r, other.r),
+                g: channel(self.g, other.g),
+                b: channel(self.b, other.b),
+            }
+        }
+    }
+
+    impl Mul<f32> for Color {
+        type Output = Color;
+
+        fn mul(self, value: f32) -> Color {
+            let channel = |c: u8| (c as f32 * value).clamp(0.0, 255.0) as u8;
+            Color {
+                r: channel(self.r),
+                g: channel(self.g),
+                b: channel(self.b),
+            }
+        }
+    }
+
+    /// The color `coefficient` of the way from one color to the other.
+    pub fn lerp(from: Color, to: Color, coefficient: f32) -> Color {
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * coefficient) as u8;
+        Color {
+            r: channel(from.r, to.r),
+            g: channel(from.g, to.g),
+            b: channel(from.b, to.b),
+        }
+    }
+
+    // The colors of libtcod's palette that the game uses, with libtcod's values.
+    pub const BLACK: Color = Color::new(0, 0, 0);
+    pub const DARK_GREY: Color = Color::new(95, 95, 95);
+    pub const GREY: Color = Color::new(127, 127, 127);
+    pub const LIGHT_GREY: Color = Color::new(159, 159, 159);
+    pub const WHITE: Color = Color::new(255, 255, 255);
+    pub const SEPIA: Color = Color::new(127, 101, 63);
+    pub const LIGHT_SEPIA: Color = Color::new(158, 134, 100);
+    pub const LIGHTEST_SEPIA: Color = Color::new(222, 211, 195);
+    pub const GOLD: Color = Color::new(229, 191, 0);
+    pub const RED: Color = Color::new(255, 0, 0);
+    pub const DARK_RED: Color = Color::new(191, 0, 0);
+    pub const DARKER_RED: Color = Color::new(127, 0, 0);
+    pub const LIGHT_RED: Color = Color::new(255, 63, 63);
+    pub const FLAME: Color = Color::new(255, 63, 0);
+    pub const ORANGE: Color = Color::new(255, 127, 0);
+    pub const DARK_ORANGE: Color = Color::new(191, 95, 0);
+    pub const DARKER_ORANGE: Color = Color::new(127, 63, 0);
+    pub const LIGHT_ORANGE: Color = Color::new(255, 159, 63);
+    pub const DESATURATED_ORANGE: Color = Color::new(127, 95, 63);
+    pub const DARK_AMBER: Color = Color::new(191, 143, 0);
+    pub const YELLOW: Color = Color::new(255, 255, 0);
+    pub const LIGHT_YELLOW: Color = Color::new(255, 255, 63);
+    pub const DARKER_GREEN: Color = Color::new(0, 127, 0);
+    pub const LIGHT_GREEN: Color = Color::new(63, 255, 63);
+    pub const DESATURATED_GREEN: Color = Color::new(63, 127, 63);
+    pub const LIGHT_CYAN: Color = Color::new(63, 255, 255);
+    pub const SKY: Color = Color::new(0, 191, 255);
+    pub const LIGHT_AZURE: Color = Color::new(63, 159, 255);
+    pub const LIGHT_BLUE: Color = Color::new(63, 63, 255);
+    pub const VIOLET: Color = Color::new(127, 0, 255);
+    pub const DARKER_VIOLET: Color = Color::new(63, 0, 127);
+    pub const LIGHT_VIOLET: Color = Color::new(159, 63, 255);
+    pub const LIGHT_PURPLE: Color = Color::new(207, 63, 255);
+}
+
+#[cfg(not(feature = "tcod"))]
+pub mod console {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum BackgroundFlag {
+        None,
+        Set,
+        Multiply,
+        Lighten,
+        Darken,
+        Screen,
+        ColorDodge,
+        ColorBurn,
+        Add,
+        AddA,
+        Burn,
+        Overlay,
+        Alph,
+        Default,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum TextAlignment {
+        Left,
+        Right,
+        Center,
+    }
+}
+
+#[cfg(not(feature = "tcod"))]
+pub mod input {
+    use std::ops::BitOr;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum KeyCode {
+        #[default]
+        NoKey,
+        Escape,
+        Backspace,
+        Tab,
+        Enter,
+        Shift,
+        Control,
+        Alt,
+        Pause,
+        CapsLock,
+        PageUp,
+        PageDown,
+        End,
+        Home,
+        Up,
+        Left,
+        Right,
+        Down,
+        PrintScreen,
+        Insert,
+        Delete,
+        LeftWin,
+        RightWin,
+        Apps,
+        Number0,
+        Number1,
+        Number2,
+        Number3,
+        Number4,
+        Number5,
+        Number6,
+        Number7,
+        Number8,
+        Number9,
+        NumPad0,
+        NumPad1,
+        NumPad2,
+        NumPad3,
+        NumPad4,
+        NumPad5,
+        NumPad6,
+        NumPad7,
+        NumPad8,
+        NumPad9,
+        NumPadAdd,
+        NumPadSubtract,
+        NumPadDivide,
+        NumPadMultiply,
+        NumPadDecimal,
+        NumPadEnter,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        NumLock,
+        ScrollLock,
+        Spacebar,
+        Char,
+        Text,
+    }
+
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Key {
+        pub code: KeyCode,
+        pub printable: char,
+        pub pressed: bool,
+        pub left_alt: bool,
+        pub left_ctrl: bool,
+        pub right_alt: bool,
+        pub right_ctrl: bool,
+        pub shift: bool,
+        pub alt: bool,
+        pub ctrl: bool,
+    }
+
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Mouse {
+        pub x: isize,
+        pub y: isize,
+        pub dx: isize,
+        pub dy: isize,
+        pub cx: isize,
+        pub cy: isize,
+        pub dcx: isize,
+        pub dcy: isize,
+        pub lbutton: bool,
+        pub rbutton: bool,
+        pub mbutton: bool,
+        pub lbutton_pressed: bool,
+        pub rbutton_pressed: bool,
+        pub mbutton_pressed: bool,
+        pub wheel_up: bool,
+        pub wheel_down: bool,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub enum Event {
+        Key(Key),
+        Mouse(Mouse),
+    }
+
+    /// The kinds of event to wait for, which combine with `|`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct EventFlags(u32);
+
+    impl BitOr for EventFlags {
+        type Output = EventFlags;
+
+        fn bitor(self, other: EventFlags) -> EventFlags {
+            EventFlags(self.0 | other.0)
+        }
+    }
+
+    pub const KEY_PRESS: EventFlags = EventFlags(1);
+    pub const KEY_RELEASE: EventFlags = EventFlags(2);
+    pub const KEY: EventFlags = EventFlags(1 | 2);
+    pub const MOUSE_MOVE: EventFlags = EventFlags(4);
+    pub const MOUSE_PRESS: EventFlags = EventFlags(8);
+    pub const MOUSE_RELEASE: EventFlags = EventFlags(16);
+    pub const MOUSE: EventFlags = EventFlags(4 | 8 | 16);
+    pub const ANY: EventFlags = EventFlags(1 | 2 | 4 | 8 | 16);
+}
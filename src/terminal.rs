@@ -0,0 +1,679 @@
+//! A backend that plays the game in the terminal it was started from, with
+//! crossterm, for platforms where a libtcod window is more trouble than it
+//! is worth. Each layer is a grid of cells kept in memory, drawn the way
+//! libtcod would draw it; flushing writes the cells of the screen that
+//! changed since the last flush, in 24-bit ANSI colors.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Stdout, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    self as term_event, Event as TermEvent, KeyCode as TermKey, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::style::{Color as TermColor, Print, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+
+use crate::backend::{InputSource, Layer, Renderer, wants};
+use crate::config::config;
+use crate::tcod_types::colors::*;
+use crate::tcod_types::console::{BackgroundFlag, TextAlignment};
+use crate::tcod_types::input::{self, Event, EventFlags, Key, KeyCode, Mouse};
+
+/// The screen size used when the terminal will not say how big it is.
+pub const FALLBACK_SIZE: (i32, i32) = (80, 24);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cell {
+    glyph: char,
+    foreground: Color,
+    background: Color,
+}
+
+const BLANK: Cell = Cell {
+    glyph: ' ',
+    foreground: WHITE,
+    background: BLACK,
+};
+
+/// One layer: its cells, row by row, and the colors drawn with.
+#[derive(Clone)]
+struct Grid {
+    width: i32,
+    height: i32,
+    cells: Vec<Cell>,
+    foreground: Color,
+    background: Color,
+}
+
+impl Grid {
+    fn new(width: i32, height: i32) -> Self {
+        Grid {
+            width: width,
+            height: height,
+            cells: vec![BLANK; (width.max(0) * height.max(0)) as usize],
+            foreground: WHITE,
+            background: BLACK,
+        }
+    }
+
+    fn cell_mut(&mut self, x: i32, y: i32) -> Option<&mut Cell> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get_mut((y * self.width + x) as usize)
+    }
+
+    fn cell(&self, x: i32, y: i32) -> Cell {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return BLANK;
+        }
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    fn set_background(&mut self, x: i32, y: i32, color: Color, flag: BackgroundFlag) {
+        if let Some(cell) = self.cell_mut(x, y) {
+            cell.background = blend(cell.background, color, flag);
+        }
+    }
+
+    fn put_char(&mut self, x: i32, y: i32, glyph: char, flag: BackgroundFlag) {
+        let (foreground, background) = (self.foreground, self.background);
+        if let Some(cell) = self.cell_mut(x, y) {
+            cell.glyph = glyph;
+            cell.foreground = foreground;
+            cell.background = blend(cell.background, background, flag);
+        }
+    }
+
+    /// Print one line of text, aligned on `x`.
+    fn print_line(
+        &mut self,
+        x: i32,
+        y: i32,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        line: &str,
+    ) {
+        let length = line.chars().count() as i32;
+        let start = match alignment {
+            TextAlignment::Left => x,
+            TextAlignment::Right => x - length + 1,
+            TextAlignment::Center => x - length / 2,
+        };
+        for (offset, glyph) in line.chars().enumerate() {
+            self.put_char(start + offset as i32, y, glyph, flag);
+        }
+    }
+}
+
+/// A background color drawn over another, as libtcod's flags blend them.
+fn blend(old: Color, new: Color, flag: BackgroundFlag) -> Color {
+    let channels = |f: &dyn Fn(i32, i32) -> i32| Color {
+        r: f(old.r as i32, new.r as i32).clamp(0, 255) as u8,
+        g: f(old.g as i32, new.g as i32).clamp(0, 255) as u8,
+        b: f(old.b as i32, new.b as i32).clamp(0, 255) as u8,
+    };
+    match flag {
+        BackgroundFlag::None => old,
+        BackgroundFlag::Multiply => channels(&|o, n| o * n / 255),
+        BackgroundFlag::Lighten => channels(&|o, n| o.max(n)),
+        BackgroundFlag::Darken => channels(&|o, n| o.min(n)),
+        BackgroundFlag::Screen => channels(&|o, n| 255 - (255 - o) * (255 - n) / 255),
+        BackgroundFlag::Add => channels(&|o, n| o + n),
+        _ => new,
+    }
+}
+
+/// Break text into the lines it takes in the given width: at newlines, and
+/// between words where it can, or else in the middle of a word.
+fn wrap(text: &str, width: i32) -> Vec<String> {
+    let width = width.max(1) as usize;
+    let mut lines = vec![];
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let needed = line.chars().count() + 1 + word.chars().count();
+            if needed > width && !line.is_empty() {
+                lines.push(line);
+                line = String::new();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+            while line.chars().count() > width {
+                let rest = line.chars().skip(width).collect();
+                lines.push(line.chars().take(width).collect());
+                line = rest;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// The lines of wrapped text that fit in a rectangle; a height of 0 fits
+/// all of them.
+fn wrap_rect(text: &str, width: i32, height: i32) -> Vec<String> {
+    let mut lines = wrap(text, width);
+    if height > 0 {
+        lines.truncate(height as usize);
+    }
+    lines
+}
+
+fn term_color(color: Color) -> TermColor {
+    TermColor::Rgb {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+/// The terminal the game was started from, taken over for the whole screen.
+pub struct TerminalBackend {
+    out: Stdout,
+    layers: Vec<Grid>,
+    /// What the terminal shows, to write only what changed.
+    shown: Option<Grid>,
+    /// Events read but not handed out yet.
+    pending: VecDeque<Event>,
+    mouse: Mouse,
+    closed: bool,
+    frame: Duration,
+    last_flush: Instant,
+}
+
+impl TerminalBackend {
+    /// Take over the terminal, with a screen as big as it is.
+    pub fn new() -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(
+            out,
+            terminal::EnterAlternateScreen,
+            term_event::EnableMouseCapture,
+            cursor::Hide
+        )?;
+        let (width, height) = terminal::size()
+            .map(|(width, height)| (width as i32, height as i32))
+            .unwrap_or(FALLBACK_SIZE);
+        let mut layers = vec![Grid::new(1, 1); 5];
+        layers[Layer::Screen as usize] = Grid::new(width, height);
+        Ok(TerminalBackend {
+            out: out,
+            layers: layers,
+            shown: None,
+            pending: VecDeque::new(),
+            mouse: Default::default(),
+            closed: false,
+            frame: Duration::from_secs(1) / config().screen.fps as u32,
+            last_flush: Instant::now(),
+        })
+    }
+
+    fn grid(&self, layer: Layer) -> &Grid {
+        &self.layers[layer as usize]
+    }
+
+    fn grid_mut(&mut self, layer: Layer) -> &mut Grid {
+        &mut self.layers[layer as usize]
+    }
+
+    /// Read everything the terminal has sent, without waiting.
+    fn read_events(&mut self) {
+        while !self.closed && term_event::poll(Duration::from_secs(0)).unwrap_or(false) {
+            self.read_event();
+        }
+    }
+
+    /// Read the next thing the terminal sends, waiting for it if need be.
+    fn read_event(&mut self) {
+        match term_event::read() {
+            Ok(TermEvent::Key(event)) => self.key_event(event),
+            Ok(TermEvent::Mouse(event)) => self.mouse_event(event),
+            // The screen keeps its size; the terminal just shows what fits.
+            Ok(TermEvent::Resize(..)) => self.shown = None,
+            Ok(_) => {}
+            Err(_) => self.closed = true,
+        }
+    }
+
+    fn key_event(&mut self, event: KeyEvent) {
+        if event.kind == KeyEventKind::Release {
+            return;
+        }
+        // In raw mode Ctrl+C no longer interrupts, so it closes the "window" instead.
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl && event.code == TermKey::Char('c') {
+            self.closed = true;
+            return;
+        }
+        let mut key = Key::default();
+        key.code = match event.code {
+            TermKey::Char(' ') => KeyCode::Spacebar,
+            TermKey::Char(_) => KeyCode::Char,
+            TermKey::Enter => KeyCode::Enter,
+            TermKey::Esc => KeyCode::Escape,
+            TermKey::Backspace => KeyCode::Backspace,
+            TermKey::Tab | TermKey::BackTab => KeyCode::Tab,
+            TermKey::Up => KeyCode::Up,
+            TermKey::Down => KeyCode::Down,
+            TermKey::Left => KeyCode::Left,
+            TermKey::Right => KeyCode::Right,
+            TermKey::Home => KeyCode::Home,
+            TermKey::End => KeyCode::End,
+            TermKey::PageUp => KeyCode::PageUp,
+            TermKey::PageDown => KeyCode::PageDown,
+            TermKey::Insert => KeyCode::Insert,
+            TermKey::Delete => KeyCode::Delete,
+            TermKey::F(n) => match n {
+                1 => KeyCode::F1,
+                2 => KeyCode::F2,
+                3 => KeyCode::F3,
+                4 => KeyCode::F4,
+                5 => KeyCode::F5,
+                6 => KeyCode::F6,
+                7 => KeyCode::F7,
+                8 => KeyCode::F8,
+                9 => KeyCode::F9,
+                10 => KeyCode::F10,
+                11 => KeyCode::F11,
+                _ => KeyCode::F12,
+            },
+            _ => return,
+        };
+        key.printable = match event.code {
+            TermKey::Char(c) => c,
+            _ => '\0',
+        };
+        key.pressed = true;
+        key.shift = event.modifiers.contains(KeyModifiers::SHIFT) || event.code == TermKey::BackTab;
+        key.ctrl = ctrl;
+        key.left_ctrl = ctrl;
+        key.alt = event.modifiers.contains(KeyModifiers::ALT);
+        key.left_alt = key.alt;
+        self.pending.push_back(Event::Key(key));
+        // Terminals do not say when a key is let go, so it is let go at once;
+        // holding it down repeats it at the terminal's own rate.
+        key.pressed = false;
+        self.pending.push_back(Event::Key(key));
+    }
+
+    fn mouse_event(&mut self, event: MouseEvent) {
+        let mouse = &mut self.mouse;
+        let (x, y) = (event.column as isize, event.row as isize);
+        mouse.dcx = x - mouse.cx;
+        mouse.dcy = y - mouse.cy;
+        mouse.dx = mouse.dcx;
+        mouse.dy = mouse.dcy;
+        mouse.cx = x;
+        mouse.cy = y;
+        mouse.x = x;
+        mouse.y = y;
+        // Clicks and wheel turns are news only in the event that brings them.
+        mouse.lbutton_pressed = false;
+        mouse.rbutton_pressed = false;
+        mouse.mbutton_pressed = false;
+        mouse.wheel_up = false;
+        mouse.wheel_down = false;
+        match event.kind {
+            MouseEventKind::Down(button) | MouseEventKind::Drag(button) => match button {
+                MouseButton::Left => mouse.lbutton = true,
+                MouseButton::Right => mouse.rbutton = true,
+                MouseButton::Middle => mouse.mbutton = true,
+            },
+            // As in libtcod, a button counts as pressed once it comes back up.
+            MouseEventKind::Up(button) => match button {
+                MouseButton::Left => {
+                    mouse.lbutton = false;
+                    mouse.lbutton_pressed = true;
+                }
+                MouseButton::Right => {
+                    mouse.rbutton = false;
+                    mouse.rbutton_pressed = true;
+                }
+                MouseButton::Middle => {
+                    mouse.mbutton = false;
+                    mouse.mbutton_pressed = true;
+                }
+            },
+            MouseEventKind::ScrollUp => mouse.wheel_up = true,
+            MouseEventKind::ScrollDown => mouse.wheel_down = true,
+            _ => {}
+        }
+        let mouse = *mouse;
+        self.pending.push_back(Event::Mouse(mouse));
+    }
+
+    /// The first pending event of the kinds asked for. Others before it are
+    /// dropped, as libtcod drops them.
+    fn next_pending(&mut self, mask: EventFlags) -> Option<Event> {
+        while let Some(event) = self.pending.pop_front() {
+            let wanted = match event {
+                Event::Key(key) if key.pressed => wants(mask, input::KEY_PRESS),
+                Event::Key(_) => wants(mask, input::KEY_RELEASE),
+                Event::Mouse(_) => {
+                    wants(mask, input::MOUSE_MOVE) || wants(mask, input::MOUSE_PRESS)
+                }
+            };
+            if wanted {
+                return Some(event);
+            }
+        }
+        None
+    }
+
+    /// Write the cells of the screen that changed since the last flush.
+    fn draw(&mut self) -> io::Result<()> {
+        let screen = &self.layers[Layer::Screen as usize];
+        let (columns, rows) = terminal::size()
+            .map(|(width, height)| (width as i32, height as i32))
+            .unwrap_or(FALLBACK_SIZE);
+        let redraw = self
+            .shown
+            .as_ref()
+            .map_or(true, |shown| shown.width != screen.width || shown.height != screen.height);
+        if redraw {
+            queue!(self.out, SetBackgroundColor(TermColor::Black))?;
+            queue!(self.out, terminal::Clear(terminal::ClearType::All))?;
+        }
+        for y in 0..cmp::min(screen.height, rows) {
+            for x in 0..cmp::min(screen.width, columns) {
+                let cell = screen.cell(x, y);
+                let shown = self.shown.as_ref().map(|shown| shown.cell(x, y));
+                if !redraw && shown == Some(cell) {
+                    continue;
+                }
+                let glyph = if cell.glyph == '\0' { ' ' } else { cell.glyph };
+                queue!(
+                    self.out,
+                    cursor::MoveTo(x as u16, y as u16),
+                    SetForegroundColor(term_color(cell.foreground)),
+                    SetBackgroundColor(term_color(cell.background)),
+                    Print(glyph)
+                )?;
+            }
+        }
+        self.shown = Some(screen.clone());
+        self.out.flush()
+    }
+}
+
+impl Drop for TerminalBackend {
+    fn drop(&mut self) {
+        // Give the terminal back the way it was found.
+        let _ = execute!(
+            self.out,
+            cursor::Show,
+            term_event::DisableMouseCapture,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Renderer for TerminalBackend {
+    fn size(&self, layer: Layer) -> (i32, i32) {
+        let grid = self.grid(layer);
+        (grid.width, grid.height)
+    }
+
+    fn resize(&mut self, layer: Layer, width: i32, height: i32) {
+        *self.grid_mut(layer) = Grid::new(width, height);
+    }
+
+    fn set_default_foreground(&mut self, layer: Layer, color: Color) {
+        self.grid_mut(layer).foreground = color;
+    }
+
+    fn set_default_background(&mut self, layer: Layer, color: Color) {
+        self.grid_mut(layer).background = color;
+    }
+
+    fn clear(&mut self, layer: Layer) {
+        let grid = self.grid_mut(layer);
+        let blank = Cell {
+            glyph: ' ',
+            foreground: grid.foreground,
+            background: grid.background,
+        };
+        for cell in &mut grid.cells {
+            *cell = blank;
+        }
+    }
+
+    fn put_char(&mut self, layer: Layer, x: i32, y: i32, glyph: char, flag: BackgroundFlag) {
+        self.grid_mut(layer).put_char(x, y, glyph, flag);
+    }
+
+    fn set_char_background(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        color: Color,
+        flag: BackgroundFlag,
+    ) {
+        self.grid_mut(layer).set_background(x, y, color, flag);
+    }
+
+    fn rect(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        flag: BackgroundFlag,
+    ) {
+        let grid = self.grid_mut(layer);
+        let color = grid.background;
+        for cy in y..y + height {
+            for cx in x..x + width {
+                grid.set_background(cx, cy, color, flag);
+            }
+        }
+    }
+
+    fn print_ex(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        flag: BackgroundFlag,
+        alignment: TextAlignment,
+        text: &str,
+    ) {
+        let grid = self.grid_mut(layer);
+        for (index, line) in text.split('\n').enumerate() {
+            grid.print_line(x, y + index as i32, flag, alignment, line);
+        }
+    }
+
+    fn print_rect(
+        &mut self,
+        layer: Layer,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+    ) -> i32 {
+        let grid = self.grid_mut(layer);
+        // An unbounded rectangle still ends at the bottom of the layer.
+        let height = if height > 0 { height } else { grid.height - y };
+        let lines = wrap_rect(text, width, height);
+        for (index, line) in lines.iter().enumerate() {
+            grid.print_line(x, y + index as i32, BackgroundFlag::None, TextAlignment::Left, line);
+        }
+        lines.len() as i32
+    }
+
+    fn get_height_rect(
+        &self,
+        _layer: Layer,
+        _x: i32,
+        _y: i32,
+        width: i32,
+        height: i32,
+        text: &str,
+    ) -> i32 {
+        wrap_rect(text, width, height).len() as i32
+    }
+
+    fn cell(&self, layer: Layer, x: i32, y: i32) -> (char, Color, Color) {
+        let cell = self.grid(layer).cell(x, y);
+        (cell.glyph, cell.foreground, cell.background)
+    }
+
+    fn blit(
+        &mut self,
+        from: Layer,
+        position: (i32, i32),
+        size: (i32, i32),
+        to: (i32, i32),
+        foreground_alpha: f32,
+        background_alpha: f32,
+    ) {
+        if from == Layer::Screen {
+            return;
+        }
+        let source = self.grid(from).clone();
+        let screen = self.grid_mut(Layer::Screen);
+        for dy in 0..size.1 {
+            for dx in 0..size.0 {
+                let (sx, sy) = (position.0 + dx, position.1 + dy);
+                if sx < 0 || sy < 0 || sx >= source.width || sy >= source.height {
+                    continue;
+                }
+                let above = source.cell(sx, sy);
+                let below = match screen.cell_mut(to.0 + dx, to.1 + dy) {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+                below.background = lerp(below.background, above.background, background_alpha);
+                if foreground_alpha >= 1.0 {
+                    below.glyph = above.glyph;
+                    below.foreground = above.foreground;
+                } else if above.glyph == ' ' {
+                    // Blank cells only tint what shows through them.
+                    below.foreground = lerp(below.foreground, above.background, foreground_alpha);
+                } else {
+                    below.glyph = above.glyph;
+                    below.foreground = lerp(below.foreground, above.foreground, foreground_alpha);
+                }
+            }
+        }
+    }
+
+    fn copy_screen(&mut self, to: Layer) {
+        let screen = self.grid(Layer::Screen).clone();
+        *self.grid_mut(to) = screen;
+    }
+
+    fn flush(&mut self) {
+        if self.draw().is_err() {
+            self.closed = true;
+        }
+        // Keep to the frame rate, as libtcod does.
+        let next = self.last_flush + self.frame;
+        let now = Instant::now();
+        if next > now {
+            thread::sleep(next - now);
+        }
+        self.last_flush = Instant::now();
+    }
+
+    fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    fn set_fullscreen(&mut self, _fullscreen: bool) {}
+
+    fn window_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// There is no font to draw the cells with, so each becomes one pixel,
+    /// in the color of its glyph, or of its background if it has none.
+    fn save_image(&self, layer: Layer, path: &str) {
+        let grid = self.grid(layer);
+        let mut pixels = Vec::with_capacity((grid.width * grid.height) as usize);
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                let cell = grid.cell(x, y);
+                let blank = cell.glyph == ' ' || cell.glyph == '\0';
+                pixels.push(if blank { cell.background } else { cell.foreground });
+            }
+        }
+        // As with libtcod, an image that cannot be written is simply not there.
+        let _ = write_png(path, grid.width as u32, grid.height as u32, &pixels);
+    }
+}
+
+/// Write the pixels, row by row, as an 8-bit RGB PNG. This needs nothing but
+/// the deflate and CRC-32 the save files already use, so it works in builds
+/// without libtcod.
+fn write_png(path: &str, width: u32, height: u32, pixels: &[Color]) -> io::Result<()> {
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+    let mut rows = Vec::with_capacity(pixels.len() * 3 + height as usize);
+    for row in pixels.chunks(width as usize) {
+        rows.push(0); // Each row is stored as it is, unfiltered.
+        for color in row {
+            rows.extend_from_slice(&[color.r, color.g, color.b]);
+        }
+    }
+    let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+    encoder.write_all(&rows)?;
+    let data = encoder.finish()?;
+
+    let mut header = vec![];
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8 bits a channel, RGB, not interlaced
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, body) in [(b"IHDR", &header[..]), (b"IDAT", &data[..]), (b"IEND", &[][..])].iter() {
+        png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(*kind);
+        png.extend_from_slice(body);
+        let crc = crc32fast::hash(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+    fs::write(path, png)
+}
+
+impl InputSource for TerminalBackend {
+    fn check_for_event(&mut self, mask: EventFlags) -> Option<Event> {
+        self.read_events();
+        self.next_pending(mask)
+    }
+
+    fn wait_for_event(&mut self, mask: EventFlags) -> Option<Event> {
+        self.read_events();
+        self.pending.clear();
+        while !self.closed {
+            if let Some(event) = self.next_pending(mask) {
+                return Some(event);
+            }
+            self.read_event();
+        }
+        None
+    }
+
+    fn wait_for_keypress(&mut self) -> Key {
+        match self.wait_for_event(input::KEY_PRESS) {
+            Some(Event::Key(key)) => key,
+            _ => Key::default(),
+        }
+    }
+}
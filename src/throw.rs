@@ -0,0 +1,122 @@
+//! Throwing items: where they fly, and what they do where they land.
+
+use std::cmp;
+
+use crate::config::config;
+use crate::game::Game;
+use crate::items::{Item, take_from_inventory};
+use crate::locale::display_name;
+use crate::map::{Map, map_blocked};
+use crate::messages::MessageCategory;
+use crate::noise::{LANDING_NOISE, SHATTER_NOISE, make_noise};
+use crate::object::{Object, PLAYER};
+use crate::render::Animation;
+use crate::status::{StatusEffect, StatusKind};
+
+pub const THROW_RANGE: i32 = 8; // Tiles a thrown item can fly
+pub const DAGGER_DAMAGE: i32 = 4;
+
+/// The tiles on a straight line from `from` to `to`, leaving out `from`.
+pub fn trajectory(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = cmp::max(dx.abs(), dy.abs());
+    (1..steps + 1)
+        .map(|step| {
+            let x = from.0 + (dx as f32 * step as f32 / steps as f32).round() as i32;
+            let y = from.1 + (dy as f32 * step as f32 / steps as f32).round() as i32;
+            (x, y)
+        })
+        .collect()
+}
+
+/// Where something sent flying from `from` towards `target` comes down, and
+/// who it hits, if anyone. It flies until it hits a wall or a creature, or
+/// has gone `range` tiles.
+pub fn flight(
+    from: (i32, i32),
+    target: (i32, i32),
+    range: i32,
+    map: &Map,
+    objects: &[Object],
+) -> ((i32, i32), Option<usize>)
+{
+    let mut landing = from;
+    for (x, y) in trajectory(from, target).into_iter().take(range as usize) {
+        if map_blocked(x, y, map) {
+            break;
+        }
+        landing = (x, y);
+        let victim = objects
+            .iter()
+            .position(|object| object.pos() == (x, y) && object.alive && object.fighter.is_some());
+        if victim.is_some() {
+            return (landing, victim);
+        }
+    }
+    (landing, None)
+}
+
+/// Throw one item from the given inventory slot towards a tile. It flies
+/// until it hits a wall or a creature, or runs out of range. Daggers wound
+/// whoever they hit, potions shatter over them, and anything else just
+/// lands there.
+pub fn throw_item(index: usize, target: (i32, i32), game: &mut Game, objects: &mut Vec<Object>) {
+    let from = objects[PLAYER].pos();
+    let (landing, victim) = flight(from, target, THROW_RANGE, &game.map, objects);
+    let mut thrown = take_from_inventory(&mut game.inventory, index);
+    let name = game.identification.name_of(&thrown);
+    game.animations.push(Animation::Projectile {
+        from: from,
+        to: landing,
+        char: thrown.char,
+        color: thrown.color,
+    });
+
+    let shatters = matches!(
+        thrown.item,
+        Some(Item::Heal) | Some(Item::Telepathy) | Some(Item::Regeneration)
+    );
+    let loudness = if shatters { SHATTER_NOISE } else { LANDING_NOISE };
+    make_noise(game, landing.0, landing.1, loudness);
+    if shatters {
+        game.messages.add(tr!("throw.shatters", name), MessageCategory::Item);
+        if let Some(price) = thrown.price {
+            if let Some(shop) = game.shop.as_mut() {
+                shop.debt += price;
+                game.messages.add(tr!("shop.used-unpaid", price), MessageCategory::Item);
+            }
+        }
+    }
+    if let Some(id) = victim {
+        let target_name = display_name(&objects[id].name);
+        match thrown.item {
+            Some(Item::Dagger) => {
+                game.messages.add(
+                    tr!("throw.hit", name, target_name, DAGGER_DAMAGE),
+                    MessageCategory::Combat,
+                );
+                objects[id].take_damage(DAGGER_DAMAGE, game);
+            }
+            Some(Item::Heal) => {
+                game.messages.add(tr!("throw.healed", target_name), MessageCategory::Combat);
+                objects[id].heal(config().gameplay.heal_amount);
+                game.identification.learn(Item::Heal);
+            }
+            Some(Item::Regeneration) => {
+                game.messages.add(tr!("throw.regenerates", target_name), MessageCategory::Combat);
+                game.identification.learn(Item::Regeneration);
+                objects[id].add_effect(StatusEffect {
+                    kind: StatusKind::Regeneration,
+                    turns: config().gameplay.regeneration_turns,
+                    strength: config().gameplay.regeneration_amount,
+                });
+            }
+            _ if shatters => {}
+            _ => game.messages.add(tr!("throw.bounces", name, target_name), MessageCategory::Combat),
+        }
+    }
+    if !shatters {
+        thrown.set_pos(landing.0, landing.1);
+        objects.push(thrown);
+    }
+}
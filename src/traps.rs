@@ -0,0 +1,214 @@
+//! Traps hidden in the floor, sprung by whoever steps on them.
+
+use rand::{Rng, StdRng};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{Sound, SoundEffect};
+use crate::fov::Fov;
+use crate::game::Game;
+use crate::locale::display_name;
+use crate::map::{Map, Rect, on_map};
+use crate::messages::MessageCategory;
+use crate::noise::make_noise;
+use crate::object::{Object, PLAYER};
+use crate::render::Animation;
+use crate::status::{StatusEffect, StatusKind};
+use crate::tcod_types::colors::*;
+
+pub const TRAP_CHANCE: f32 = 0.2; // Chance of a trap in each room
+pub const SPIKE_DAMAGE: i32 = 5;
+pub const DART_POISON_TURNS: i32 = 6;
+pub const ALARM_NOISE: u32 = 15; // How far off monsters hear the alarm, and come to see
+pub const FLASH_BLIND_TURNS: i32 = 8;
+pub const GAS_CONFUSION_TURNS: i32 = 6;
+pub const NET_SLOW_TURNS: i32 = 10;
+pub const SEARCH_CHANCE: f32 = 0.35; // Chance a turn of searching finds each hidden thing close by
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TrapKind {
+    Spikes,
+    /// Shoots a poisoned dart.
+    Dart,
+    /// Brings the monsters nearby running.
+    Alarm,
+    /// Blinds with a burst of light.
+    Flash,
+    /// Lets out a puff of gas that leaves the head spinning.
+    Gas,
+    /// Drops a net that slows whoever is caught in it.
+    Net,
+}
+
+/// A trap in a tile's floor. It stays hidden until it goes off, or someone
+/// finds it, and keeps working after that.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trap {
+    pub kind: TrapKind,
+    pub hidden: bool,
+}
+
+impl TrapKind {
+    pub fn name(self) -> String {
+        match self {
+            TrapKind::Spikes => tr!("trap.spikes"),
+            TrapKind::Dart => tr!("trap.dart"),
+            TrapKind::Alarm => tr!("trap.alarm"),
+            TrapKind::Flash => tr!("trap.flash"),
+            TrapKind::Gas => tr!("trap.gas"),
+            TrapKind::Net => tr!("trap.net"),
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            TrapKind::Spikes => LIGHT_GREY,
+            TrapKind::Dart => LIGHT_GREEN,
+            TrapKind::Alarm => LIGHT_YELLOW,
+            TrapKind::Flash => WHITE,
+            TrapKind::Gas => LIGHT_VIOLET,
+            TrapKind::Net => LIGHT_SEPIA,
+        }
+    }
+}
+
+/// Sometimes hide a trap somewhere on the room's floor.
+pub fn place_trap(room: Rect, map: &mut Map, objects: &[Object], rng: &mut StdRng) {
+    if rng.next_f32() >= TRAP_CHANCE {
+        return;
+    }
+    let x = rng.gen_range(room.x1 + 1, room.x2);
+    let y = rng.gen_range(room.y1 + 1, room.y2);
+    // Nothing is left standing on it, not even the stairs.
+    if map[x as usize][y as usize].blocked || objects.iter().any(|object| object.pos() == (x, y)) {
+        return;
+    }
+    let dice = rng.next_f32();
+    let kind = if dice < 0.35 {
+        // 35% chance of spikes
+        TrapKind::Spikes
+    } else if dice < 0.35 + 0.25 {
+        // 25% chance of a dart trap
+        TrapKind::Dart
+    } else if dice < 0.35 + 0.25 + 0.1 {
+        // 10% chance of a flash trap
+        TrapKind::Flash
+    } else if dice < 0.35 + 0.25 + 0.1 + 0.1 {
+        // 10% chance of a gas trap
+        TrapKind::Gas
+    } else if dice < 0.35 + 0.25 + 0.1 + 0.1 + 0.1 {
+        // 10% chance of a net trap
+        TrapKind::Net
+    } else {
+        TrapKind::Alarm
+    };
+    map[x as usize][y as usize].trap = Some(Trap {
+        kind: kind,
+        hidden: true,
+    });
+}
+
+/// Look for hidden traps on the tiles around the player, finding each with
+/// some luck.
+pub fn search_for_traps(game: &mut Game, objects: &[Object]) {
+    let (px, py) = objects[PLAYER].pos();
+    for x in px - 1..px + 2 {
+        for y in py - 1..py + 2 {
+            if !on_map(x, y) {
+                continue;
+            }
+            let found = match game.map[x as usize][y as usize].trap {
+                Some(ref mut trap) if trap.hidden && game.rng.next_f32() < SEARCH_CHANCE => {
+                    trap.hidden = false;
+                    trap.kind
+                }
+                _ => continue,
+            };
+            game.messages.add(tr!("trap.found", found.name()), MessageCategory::Warning);
+            game.animations.push(Animation::Flash {
+                x: x,
+                y: y,
+                color: found.color(),
+            });
+        }
+    }
+}
+
+/// Set off the trap, if any, under whoever just stepped onto its tile. A
+/// trap sprung out of the player's sight stays hidden, and goes unremarked.
+pub fn spring_trap(id: usize, fov: &Fov, game: &mut Game, objects: &mut [Object]) {
+    let (x, y) = objects[id].pos();
+    let seen = id == PLAYER || fov.is_in_fov(x, y);
+    let trap = match game.map[x as usize][y as usize].trap {
+        Some(ref mut trap) => {
+            if seen {
+                trap.hidden = false;
+            }
+            *trap
+        }
+        None => return,
+    };
+    if objects[id].fighter.is_none() {
+        return;
+    }
+
+    if seen {
+        let message = if id == PLAYER {
+            tr!("trap.player", trap.kind.name())
+        } else {
+            tr!("trap.monster", display_name(&objects[id].name), trap.kind.name())
+        };
+        game.messages.add(message, MessageCategory::Combat);
+        game.animations.push(Animation::Flash {
+            x: x,
+            y: y,
+            color: trap.kind.color(),
+        });
+    }
+
+    match trap.kind {
+        TrapKind::Spikes => {
+            if id == PLAYER {
+                game.last_attacker = Some(trap.kind.name());
+            }
+            game.sounds.push(Sound {
+                effect: SoundEffect::Hit,
+                x: x,
+                y: y,
+            });
+            objects[id].take_dungeon_damage(SPIKE_DAMAGE, game);
+        }
+        TrapKind::Dart => {
+            objects[id].add_effect(StatusEffect {
+                kind: StatusKind::Poison,
+                turns: DART_POISON_TURNS,
+                strength: 1,
+            });
+        }
+        TrapKind::Alarm => {
+            game.messages.add(tr!("trap.alarm-rings"), MessageCategory::Warning);
+            make_noise(game, x, y, ALARM_NOISE);
+        }
+        TrapKind::Flash => {
+            objects[id].add_effect(StatusEffect {
+                kind: StatusKind::Blindness,
+                turns: FLASH_BLIND_TURNS,
+                strength: 0,
+            });
+        }
+        TrapKind::Gas => {
+            objects[id].add_effect(StatusEffect {
+                kind: StatusKind::Confusion,
+                turns: GAS_CONFUSION_TURNS,
+                strength: 0,
+            });
+        }
+        TrapKind::Net => {
+            objects[id].add_effect(StatusEffect {
+                kind: StatusKind::Slow,
+                turns: NET_SLOW_TURNS,
+                strength: 0,
+            });
+        }
+    }
+}
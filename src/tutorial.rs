@@ -0,0 +1,116 @@
+//! The tutorial, a fixed map with a step-by-step guide.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ai::Ai;
+use crate::config::config;
+use crate::game::{Game, GameMode};
+use crate::items::{Item, create_item};
+use crate::map::{Map, Rect, Tile, create_h_tunnel, create_room};
+use crate::object::{DeathCallback, Fighter, Object, PLAYER};
+use crate::tcod_types::colors::*;
+use crate::ui::{Tcod, msgbox};
+
+/// The rooms of the tutorial level, laid out from west to east.
+pub fn tutorial_rooms() -> [Rect; 4] {
+    [
+        Rect::new(4, 18, 10, 8),
+        Rect::new(20, 18, 10, 8),
+        Rect::new(36, 18, 10, 8),
+        Rect::new(52, 18, 10, 8),
+    ]
+}
+
+/// Build the handcrafted tutorial level: a potion, a lone orc and the stairs,
+/// one per room.
+pub fn make_tutorial_map(objects: &mut Vec<Object>) -> Map {
+    let mut map = vec![vec![Tile::wall(); config().map.height as usize]; config().map.width as usize];
+
+    let rooms = tutorial_rooms();
+    for room in &rooms {
+        create_room(*room, &mut map);
+    }
+    let (first_x, y) = rooms[0].center();
+    let (last_x, _) = rooms[rooms.len() - 1].center();
+    create_h_tunnel(first_x, last_x, y, &mut map);
+
+    // The player starts out wounded, so the potion is worth drinking.
+    objects[PLAYER].set_pos(first_x, y);
+    if let Some(fighter) = objects[PLAYER].fighter.as_mut() {
+        fighter.hp = fighter.max_hp * 2 / 3;
+    }
+
+    let (x, y) = rooms[1].center();
+    objects.push(create_item(Item::Heal, x, y));
+
+    let (x, y) = rooms[2].center();
+    let mut orc = Object::new(x, y, 'o', "orc", DESATURATED_GREEN, true);
+    orc.fighter = Some(Fighter {
+        max_hp: 6,
+        hp: 6,
+        defense: 0,
+        power: 3,
+        xp: 35,
+        venom: None,
+        on_death: DeathCallback::Monster,
+    });
+    orc.ai = Some(Ai::Basic);
+    orc.alive = true;
+    objects.push(orc);
+
+    let (x, y) = rooms[3].center();
+    objects.push(Object::new(x, y, '>', "stairs", WHITE, false));
+
+    map
+}
+
+/// Steps of the tutorial, in the order they are taught.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TutorialStep {
+    Start,
+    Move,
+    PickUp,
+    Inventory,
+    Combat,
+    Stairs,
+}
+
+impl TutorialStep {
+    /// The prompt shown when this step begins.
+    pub fn prompt(self) -> String {
+        use TutorialStep::*;
+        match self {
+            Start => String::new(),
+            Move => tr!("tutorial.move"),
+            PickUp => tr!("tutorial.pick-up"),
+            Inventory => tr!("tutorial.inventory"),
+            Combat => tr!("tutorial.combat"),
+            Stairs => tr!("tutorial.stairs"),
+        }
+    }
+}
+
+/// Check the current tutorial objective, and prompt for the next one once it is met.
+pub fn update_tutorial(tcod: &mut Tcod, game: &mut Game, objects: &[Object]) {
+    use TutorialStep::*;
+    let step = match game.mode {
+        GameMode::Tutorial(step) => step,
+        _ => return,
+    };
+
+    let rooms = tutorial_rooms();
+    let next = match step {
+        Start => Some(Move),
+        Move if objects[PLAYER].x > rooms[0].x2 => Some(PickUp),
+        PickUp if !game.inventory.is_empty() => Some(Inventory),
+        Inventory if game.inventory.is_empty() => Some(Combat),
+        Combat if game.kills > 0 => Some(Stairs),
+        // Descending the stairs ends the tutorial in `next_level`.
+        _ => None,
+    };
+
+    if let Some(next) = next {
+        game.mode = GameMode::Tutorial(next);
+        msgbox(&format!("\n{}\n", next.prompt()), 50, &mut *tcod.ui);
+    }
+}
@@ -0,0 +1,777 @@
+//! The player's session, and the menus and windows drawn for it.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::action::{Action, ActionQueue};
+use crate::audio::Audio;
+use crate::backend::{Backend, Layer};
+use crate::config::{FovSetting, config};
+use crate::fov::Fov;
+use crate::game::{Game, GameMode, JournalEntry};
+use crate::identify::Identification;
+use crate::input::{GAMEPAD, KEY_REPEAT_DELAYS, wait_for_key};
+use crate::items::{Item, ItemCategory, describe_item, stack_name};
+use crate::keybindings::keybindings;
+use crate::locale::{available_languages, set_language};
+use crate::messages::{MESSAGE_PALETTE, MessageCategory, Messages, color_name};
+use crate::object::{Object, PLAYER};
+use crate::observer::Observer;
+use crate::render::{PANEL_HEIGHT, describe_tile, mouse_tile, render_all};
+use crate::settings::Settings;
+use crate::tcod_types::colors::*;
+use crate::tcod_types::console::{BackgroundFlag, TextAlignment};
+use crate::tcod_types::input::{self, Event, Key, KeyCode, Mouse};
+
+pub const INVENTORY_WIDTH: i32 = 50;
+pub const MENU_PAGE_SIZE: usize = 26; // Options on a page of a menu, one for each letter
+pub const HELP_KEYS_X: i32 = 30; // Column where the help screen lists the keys
+
+// Window sizes offered in the options, in cells.
+pub const WINDOW_SIZES: [(i32, i32); 4] = [(64, 40), (80, 50), (100, 60), (120, 70)];
+
+/// Ask the player to type in a line of text. Returns `None` if they cancel.
+pub fn text_prompt(header: &str, tcod: &mut Tcod) -> Option<String> {
+    use crate::tcod_types::input::KeyCode::*;
+
+    let mut text = String::new();
+    loop {
+        let prompt = format!("{}\n\n> {}_", header, text);
+        let width = 50;
+        let height = tcod.ui.get_height_rect(Layer::Window, 0, 0, width, tcod.ui.height(), &prompt);
+        tcod.ui.resize(Layer::Window, width, height);
+        tcod.ui.set_default_foreground(Layer::Window, WHITE);
+        tcod.ui.print_rect(Layer::Window, 0, 0, width, height, &prompt);
+        let x = tcod.ui.width() / 2 - width / 2;
+        let y = tcod.ui.height() / 2 - height / 2;
+        tcod.ui.blit(Layer::Window, (0, 0), (width, height), (x, y), 1.0, 0.7);
+        tcod.ui.flush();
+
+        let key = tcod.ui.wait_for_keypress();
+        match key {
+            Key { code: Enter, .. } if !text.is_empty() => return Some(text),
+            Key { code: Escape, .. } => return None,
+            Key { code: Backspace, .. } => {
+                text.pop();
+            }
+            Key { code: Char, printable, .. } => text.push(printable),
+            _ => {}
+        }
+    }
+}
+
+/// Let the player pick the language for all game text.
+pub fn choose_language(tcod: &mut Tcod) {
+    let languages = available_languages();
+    let names: Vec<&str> = languages.iter().map(|(_, name)| name.as_str()).collect();
+    if let Some(choice) = menu(&tr!("language.header"), &names, 30, &mut *tcod.ui) {
+        let code = &languages[choice].0;
+        if set_language(code).is_ok() {
+            tcod.settings.language = code.clone();
+            let _ = tcod.settings.save();
+        }
+    }
+}
+
+/// Let the player change the language, and the color and visibility of each
+/// message category.
+pub fn options_menu(tcod: &mut Tcod) {
+    loop {
+        let language = available_languages()
+            .into_iter()
+            .find(|(code, _)| *code == tcod.settings.language)
+            .map_or_else(|| tcod.settings.language.clone(), |(_, name)| name);
+        let mut options = vec![tr!("options.language", language)];
+        options.push(tr!("options.player-name", tcod.settings.player_name));
+        let server = tcod.settings.score_server.clone().unwrap_or_else(|| tr!("options.off"));
+        options.push(tr!("options.score-server", server));
+        options.push(tr!("options.music-volume", tcod.settings.music_volume));
+        options.push(tr!("options.sound-volume", tcod.settings.sound_volume));
+        options.push(tr!("options.window-size", tcod.ui.width(), tcod.ui.height()));
+        options.push(tr!("options.fov-algorithm", tcod.settings.fov_algorithm().name()));
+        let light_walls = if tcod.settings.light_walls() { tr!("options.on") } else { tr!("options.off") };
+        options.push(tr!("options.light-walls", light_walls));
+        let animations = if tcod.settings.skip_animations { tr!("options.off") } else { tr!("options.on") };
+        options.push(tr!("options.animations", animations));
+        let repeat = match tcod.settings.key_repeat_delay {
+            0 => tr!("options.off"),
+            delay => tr!("options.milliseconds", delay),
+        };
+        options.push(tr!("options.key-repeat", repeat));
+        let casual = if tcod.settings.casual { tr!("options.on") } else { tr!("options.off") };
+        options.push(tr!("options.casual", casual));
+        for &danger in &Danger::ALL {
+            let asked = if tcod.settings.unconfirmed.contains(&danger) { tr!("options.off") } else { tr!("options.on") };
+            options.push(tr!("options.confirm", danger.name(), asked));
+        }
+        for &category in &MessageCategory::ALL {
+            let style = tcod.settings.message_style(category);
+            let mut option = tr!("options.category", category.name(), color_name(style.color));
+            if style.muted {
+                option = format!("{} {}", option, tr!("options.muted"));
+            }
+            options.push(option);
+        }
+
+        match menu(&tr!("options.title"), &options, 50, &mut *tcod.ui) {
+            Some(0) => choose_language(tcod),
+            Some(1) => {
+                if let Some(name) = text_prompt(&tr!("options.enter-name"), tcod) {
+                    tcod.settings.player_name = name;
+                    let _ = tcod.settings.save();
+                }
+            }
+            Some(2) => {
+                // Choosing the entry again turns submission back off.
+                tcod.settings.score_server = match tcod.settings.score_server {
+                    Some(_) => None,
+                    None => text_prompt(&tr!("options.enter-server"), tcod),
+                };
+                let _ = tcod.settings.save();
+            }
+            Some(3) => {
+                tcod.settings.music_volume = (tcod.settings.music_volume + 25) % 125;
+                let _ = tcod.settings.save();
+            }
+            Some(4) => {
+                tcod.settings.sound_volume = (tcod.settings.sound_volume + 25) % 125;
+                let _ = tcod.settings.save();
+            }
+            Some(5) => {
+                let current = (tcod.ui.width(), tcod.ui.height());
+                let next = WINDOW_SIZES
+                    .iter()
+                    .position(|&size| size == current)
+                    .map_or(0, |index| (index + 1) % WINDOW_SIZES.len());
+                let (width, height) = WINDOW_SIZES[next];
+                resize_window(tcod, width, height);
+                tcod.settings.window_size = Some((width, height));
+                let _ = tcod.settings.save();
+            }
+            Some(6) => {
+                let names: Vec<String> = FovSetting::ALL.iter().map(|algorithm| algorithm.name()).collect();
+                if let Some(choice) = menu(&tr!("options.choose-fov"), &names, 30, &mut *tcod.ui) {
+                    tcod.settings.fov_algorithm = Some(FovSetting::ALL[choice]);
+                    tcod.fov_stale = true;
+                    let _ = tcod.settings.save();
+                }
+            }
+            Some(7) => {
+                tcod.settings.light_walls = Some(!tcod.settings.light_walls());
+                tcod.fov_stale = true;
+                let _ = tcod.settings.save();
+            }
+            Some(8) => {
+                tcod.settings.skip_animations = !tcod.settings.skip_animations;
+                let _ = tcod.settings.save();
+            }
+            Some(9) => {
+                let next = KEY_REPEAT_DELAYS
+                    .iter()
+                    .position(|&delay| delay == tcod.settings.key_repeat_delay)
+                    .map_or(0, |index| (index + 1) % KEY_REPEAT_DELAYS.len());
+                tcod.settings.key_repeat_delay = KEY_REPEAT_DELAYS[next];
+                let _ = tcod.settings.save();
+            }
+            Some(10) => {
+                tcod.settings.casual = !tcod.settings.casual;
+                let _ = tcod.settings.save();
+            }
+            Some(choice) if choice < 11 + Danger::ALL.len() => {
+                let danger = Danger::ALL[choice - 11];
+                if !tcod.settings.unconfirmed.remove(&danger) {
+                    tcod.settings.unconfirmed.insert(danger);
+                }
+                let _ = tcod.settings.save();
+            }
+            Some(choice) => {
+                let category = MessageCategory::ALL[choice - 11 - Danger::ALL.len()];
+                configure_category(tcod, category);
+            }
+            None => break,
+        }
+    }
+}
+
+pub fn configure_category(tcod: &mut Tcod, category: MessageCategory) {
+    let mut style = tcod.settings.message_style(category);
+    let mute = if style.muted {
+        tr!("options.unmute")
+    } else {
+        tr!("options.mute")
+    };
+    let choices = [tr!("options.change-color"), mute];
+    match menu(&category.name(), &choices, 30, &mut *tcod.ui) {
+        Some(0) => {
+            let colors: Vec<String> = MESSAGE_PALETTE.iter().map(|&(key, _)| tr!(key)).collect();
+            let header = tr!("options.choose-color", category.name());
+            match menu(&header, &colors, 30, &mut *tcod.ui) {
+                Some(choice) => style.color = MESSAGE_PALETTE[choice].1,
+                None => return,
+            }
+        }
+        Some(1) => style.muted = !style.muted,
+        _ => return,
+    }
+    tcod.settings.message_styles.insert(category, style);
+    let _ = tcod.settings.save();
+}
+
+/// Show past messages on a full screen, optionally only those of one category.
+pub fn show_message_history(tcod: &mut Tcod, messages: &Messages) {
+    let mut filter: Option<MessageCategory> = None;
+    loop {
+        let (width, height) = (tcod.ui.width(), tcod.ui.height());
+        tcod.ui.resize(Layer::Window, width, height);
+        tcod.ui.set_default_foreground(Layer::Window, WHITE);
+        let showing = filter.map_or_else(|| tr!("history.all"), |category| category.name());
+        let mut legend = tr!("history.title", showing);
+        legend.push_str(&format!("\n0: {}", tr!("history.all")));
+        for (index, category) in MessageCategory::ALL.iter().enumerate() {
+            legend.push_str(&format!("  {}: {}", index + 1, category.name()));
+        }
+        let header_height = tcod.ui.print_rect(Layer::Window, 1, 0, width - 2, 0, &legend) + 1;
+
+        // Newest messages at the bottom, as in the panel.
+        let mut y = height;
+        for &(ref msg, category) in messages.iter().rev() {
+            if filter.map_or(false, |filter| filter != category) {
+                continue;
+            }
+            let msg_height = tcod.ui.get_height_rect(Layer::Window, 1, 0, width - 2, 0, msg);
+            y -= msg_height;
+            if y < header_height {
+                break;
+            }
+            let color = tcod.settings.message_style(category).color;
+            tcod.ui.set_default_foreground(Layer::Window, color);
+            tcod.ui.print_rect(Layer::Window, 1, y, width - 2, 0, msg);
+        }
+
+        tcod.ui.blit(Layer::Window, (0, 0), (width, height), (0, 0), 1.0, 1.0);
+        tcod.ui.flush();
+
+        let key = wait_for_key(&mut *tcod.ui);
+        match key.printable.to_digit(10) {
+            Some(0) => filter = None,
+            Some(digit) if (digit as usize) <= MessageCategory::ALL.len() => {
+                filter = Some(MessageCategory::ALL[digit as usize - 1]);
+            }
+            _ => break,
+        }
+    }
+}
+
+/// Show the adventure journal on a full screen, newest entries at the bottom.
+pub fn show_journal(tcod: &mut Tcod, journal: &[JournalEntry]) {
+    let (width, height) = (tcod.ui.width(), tcod.ui.height());
+    tcod.ui.resize(Layer::Window, width, height);
+    tcod.ui.set_default_foreground(Layer::Window, WHITE);
+    let title = tr!("journal.title");
+    let header_height = tcod.ui.print_rect(Layer::Window, 1, 0, width - 2, 0, &title) + 1;
+    if journal.is_empty() {
+        tcod.ui.print_rect(Layer::Window, 1, header_height, width - 2, 0, &tr!("journal.empty"));
+    }
+
+    let mut y = height;
+    for entry in journal.iter().rev() {
+        let line = tr!("journal.entry", entry.turn, entry.depth, entry.text);
+        y -= tcod.ui.get_height_rect(Layer::Window, 1, 0, width - 2, 0, &line);
+        if y < header_height {
+            break;
+        }
+        tcod.ui.print_rect(Layer::Window, 1, y, width - 2, 0, &line);
+    }
+
+    tcod.ui.blit(Layer::Window, (0, 0), (width, height), (0, 0), 1.0, 1.0);
+    tcod.ui.flush();
+    wait_for_key(&mut *tcod.ui);
+}
+
+/// List every command with the keys bound to it, followed by the controls
+/// that are not rebindable.
+pub fn show_help(tcod: &mut Tcod) {
+    let (width, height) = (tcod.ui.width(), tcod.ui.height());
+    tcod.ui.resize(Layer::Window, width, height);
+    tcod.ui.set_default_foreground(Layer::Window, WHITE);
+    let mut y = tcod.ui.print_rect(Layer::Window, 1, 0, width - 2, 0, &tr!("help.title")) + 1;
+
+    let bindings = keybindings();
+    let mut lines: Vec<(String, String)> = bindings
+        .table()
+        .iter()
+        .map(|&(command, names)| (command.description(), names.join(", ")))
+        .collect();
+    let macro_keys = config().macros.keys.join(", ");
+    for &(keys, description) in &[
+        ("help.keys-click", "help.click"),
+        ("help.keys-count", "help.count"),
+    ] {
+        lines.push((tr!(description), tr!(keys)));
+    }
+    lines.push((tr!("help.macro"), macro_keys.clone()));
+    lines.push((tr!("help.record"), tr!("help.keys-shift", macro_keys)));
+    lines.push((tr!("help.fullscreen"), tr!("help.keys-fullscreen")));
+    for (description, keys) in lines {
+        if y >= height - 1 {
+            break;
+        }
+        let (none, left) = (BackgroundFlag::None, TextAlignment::Left);
+        tcod.ui.print_ex(Layer::Window, 1, y, none, left, &description);
+        tcod.ui.set_default_foreground(Layer::Window, LIGHT_YELLOW);
+        tcod.ui.print_ex(Layer::Window, HELP_KEYS_X, y, none, left, &keys);
+        tcod.ui.set_default_foreground(Layer::Window, WHITE);
+        y += 1;
+    }
+    let footer = tr!("help.close");
+    let (none, center) = (BackgroundFlag::None, TextAlignment::Center);
+    tcod.ui.print_ex(Layer::Window, width / 2, height - 1, none, center, &footer);
+
+    tcod.ui.blit(Layer::Window, (0, 0), (width, height), (0, 0), 1.0, 1.0);
+    tcod.ui.flush();
+    wait_for_key(&mut *tcod.ui);
+}
+
+/// Everything about the player's session that is not part of the game
+/// itself: the window, what they are pressing, and their settings.
+pub struct Tcod {
+    /// The window the game is drawn in and read from.
+    pub ui: Box<dyn Backend>,
+    pub fov: Fov,
+    pub key: Key,
+    pub mouse: Mouse,
+    /// Map position shown in the window's top left corner.
+    pub camera: (i32, i32),
+    /// Set when the FOV options change, so the FOV is recomputed right away.
+    pub fov_stale: bool,
+    pub settings: Settings,
+    pub audio: Audio,
+    pub observer: Observer,
+    /// Key presses not handled yet, oldest first.
+    pub keys: VecDeque<Key>,
+    /// The movement key being held down, and when it next repeats.
+    pub held_key: Option<(Key, Instant)>,
+    /// The count being typed in front of a command, as in `10j`.
+    pub count: Option<u32>,
+    /// Actions waiting to be done, one per turn.
+    pub queue: Option<ActionQueue>,
+    /// The macro slot being recorded, and the actions done so far.
+    pub recording: Option<(u32, Vec<Action>)>,
+    /// How many messages back the panel is scrolled from the newest.
+    pub message_scroll: usize,
+    /// Seed given on the command line, used for every run but daily challenges.
+    pub seed: Option<usize>,
+    /// Set in headless runs, to print the game's state as a line of JSON
+    /// after every frame.
+    pub snapshots: bool,
+}
+
+/// Resize the window, and the panel along with it.
+pub fn resize_window(tcod: &mut Tcod, width: i32, height: i32) {
+    tcod.ui.resize(Layer::Screen, width, height);
+    tcod.ui.resize(Layer::Panel, width, PANEL_HEIGHT);
+}
+
+/// Show the items in a menu, grouped by category and sorted by name, and
+/// return the inventory index of the one chosen.
+pub fn inventory_menu(
+    inventory: &[Object],
+    identification: &Identification,
+    header: &str,
+    ui: &mut dyn Backend,
+) -> Option<usize> {
+    if inventory.is_empty() {
+        menu(header, &[tr!("inventory-empty")], INVENTORY_WIDTH, ui);
+        return None;
+    }
+
+    let mut entries: Vec<(ItemCategory, String, usize)> = inventory
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let category = item.item.map_or(ItemCategory::Misc, Item::category);
+            let name = match item.price {
+                Some(price) => tr!("shop.unpaid", stack_name(item, identification), price),
+                None => stack_name(item, identification),
+            };
+            (category, name, index)
+        })
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.to_lowercase().cmp(&b.1.to_lowercase())));
+
+    let mut headings = vec![];
+    for (row, entry) in entries.iter().enumerate() {
+        if row == 0 || entries[row - 1].0 != entry.0 {
+            headings.push((row, entry.0.name()));
+        }
+    }
+    let options: Vec<&str> = entries.iter().map(|entry| entry.1.as_str()).collect();
+    let header = format!("{}\n{}\n", header.trim_end(), tr!("inventory.describe-hint"));
+    let describe = |row: usize| describe_item(&inventory[entries[row].2], identification);
+    grouped_menu(&header, &options, &headings, Some(&describe), INVENTORY_WIDTH, ui)
+        .map(|row| entries[row].2)
+}
+
+/// Actions that are clearly bad for the player, and so are checked first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Danger {
+    /// Attacking someone who is not hostile.
+    AttackPeaceful,
+    /// Taking the stairs badly hurt, with enemies right there.
+    DescendWounded,
+    /// Walking into lava.
+    EnterLava,
+    /// Stepping onto a trap the player knows is there.
+    EnterTrap,
+}
+
+impl Danger {
+    pub const ALL: [Danger; 4] = [
+        Danger::AttackPeaceful,
+        Danger::DescendWounded,
+        Danger::EnterLava,
+        Danger::EnterTrap,
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            Danger::AttackPeaceful => tr!("danger.attack-peaceful"),
+            Danger::DescendWounded => tr!("danger.descend-wounded"),
+            Danger::EnterLava => tr!("danger.enter-lava"),
+            Danger::EnterTrap => tr!("danger.enter-trap"),
+        }
+    }
+}
+
+/// Ask the player whether they really mean to do something dangerous,
+/// unless they turned that question off. The bot is never asked.
+pub fn confirm(tcod: &mut Tcod, game: &Game, danger: Danger, question: &str) -> bool {
+    if game.mode == GameMode::Demo || tcod.settings.unconfirmed.contains(&danger) {
+        return true;
+    }
+    let choices = [tr!("confirm.yes"), tr!("confirm.no")];
+    menu(question, &choices, 40, &mut *tcod.ui) == Some(0)
+}
+
+/// Let the player pick a tile with a cursor, starting at `start`. The cursor
+/// is moved with the arrow or vi keys, or follows the mouse. Enter or a left
+/// click picks the tile under it, and Escape or a right click cancels.
+pub fn pick_tile(
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &[Object],
+    prompt: &str,
+    start: (i32, i32),
+) -> Option<(i32, i32)> {
+    pick_tile_described(tcod, game, objects, prompt, start, |_, _, _, _| String::new())
+}
+
+/// `pick_tile`, with a line under the prompt saying what is under the cursor.
+pub fn pick_tile_described<F>(
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &[Object],
+    prompt: &str,
+    start: (i32, i32),
+    describe: F,
+) -> Option<(i32, i32)>
+where
+    F: Fn(&Tcod, &Game, &[Object], (i32, i32)) -> String,
+{
+    use crate::tcod_types::input::KeyCode::*;
+
+    // Don't let a key pressed beforehand pick a tile straight away.
+    while tcod.ui.check_for_event(input::KEY_PRESS).is_some() {}
+    let (mut x, mut y) = start;
+    while !tcod.ui.window_closed() {
+        tcod.ui.clear(Layer::Map);
+        render_all(tcod, game, objects, false);
+        let (camera_x, camera_y) = tcod.camera;
+        let cursor = (x - camera_x, y - camera_y);
+        let flag = BackgroundFlag::Set;
+        tcod.ui.set_char_background(Layer::Screen, cursor.0, cursor.1, LIGHT_RED, flag);
+        tcod.ui.set_default_foreground(Layer::Screen, WHITE);
+        tcod.ui.print_ex(Layer::Screen, 1, 0, BackgroundFlag::None, TextAlignment::Left, prompt);
+        let description = describe(tcod, game, objects, (x, y));
+        let (none, left) = (BackgroundFlag::None, TextAlignment::Left);
+        tcod.ui.print_ex(Layer::Screen, 1, 1, none, left, &description);
+        tcod.ui.flush();
+
+        let key = match tcod.ui.check_for_event(input::KEY_PRESS | input::MOUSE) {
+            Some(Event::Mouse(mouse)) => {
+                tcod.mouse = mouse;
+                if mouse.rbutton_pressed {
+                    return None;
+                }
+                if let Some(tile) = mouse_tile(mouse, tcod) {
+                    x = tile.0;
+                    y = tile.1;
+                    if mouse.lbutton_pressed {
+                        return Some((x, y));
+                    }
+                }
+                continue;
+            }
+            Some(Event::Key(key)) => key,
+            _ => match GAMEPAD.with(|pad| pad.borrow_mut().poll()).into_iter().next() {
+                Some(key) => key,
+                None => continue,
+            },
+        };
+        let (dx, dy) = match (key.code, key.printable) {
+            (Enter, _) => return Some((x, y)),
+            (Escape, _) | (Backspace, _) => return None,
+            (Up, _) | (NumPad8, _) | (_, 'k') => (0, -1),
+            (Down, _) | (NumPad2, _) | (_, 'j') => (0, 1),
+            (Left, _) | (NumPad4, _) | (_, 'h') => (-1, 0),
+            (Right, _) | (NumPad6, _) | (_, 'l') => (1, 0),
+            (NumPad7, _) | (_, 'y') => (-1, -1),
+            (NumPad9, _) | (_, 'u') => (1, -1),
+            (NumPad1, _) | (_, 'b') => (-1, 1),
+            (NumPad3, _) | (_, 'n') => (1, 1),
+            _ => (0, 0),
+        };
+        x = cmp::max(0, cmp::min(x + dx, config().map.width - 1));
+        y = cmp::max(0, cmp::min(y + dy, config().map.height - 1));
+    }
+    None
+}
+
+/// Let the player pick a monster in view and within range, with the cursor
+/// starting on the closest one. In demos the bot just takes the closest.
+pub fn target_monster(
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &[Object],
+    prompt: &str,
+    max_range: i32,
+) -> Option<usize> {
+    let closest = Object::closest_monster(tcod, objects, max_range)?;
+    if game.mode == GameMode::Demo {
+        return Some(closest);
+    }
+    let mut start = objects[closest].pos();
+    loop {
+        let (x, y) = pick_tile(tcod, game, objects, prompt, start)?;
+        let target = objects.iter().enumerate().position(|(id, object)| {
+            id != PLAYER
+                && object.pos() == (x, y)
+                && object.fighter.is_some()
+                && object.ai.is_some()
+                && tcod.fov.is_in_fov(x, y)
+                && objects[PLAYER].distance_to(object) <= max_range as f32
+        });
+        if target.is_some() {
+            return target;
+        }
+        // Nothing to aim at there; keep looking from where the cursor was left.
+        start = (x, y);
+    }
+}
+
+/// Move a cursor about the map to see what is where, without taking a turn.
+pub fn look_around(tcod: &mut Tcod, game: &mut Game, objects: &[Object]) {
+    let start = objects[PLAYER].pos();
+    pick_tile_described(tcod, game, objects, &tr!("look.prompt"), start, describe_tile);
+}
+
+/// Let the player pick one of the options, by its letter, or with the arrow
+/// keys and Enter. Options that do not fit are split into pages, each
+/// lettered from (a), and turned with Page Up and Page Down or Left and
+/// Right. Returns `None` if the player backs out.
+pub fn menu<T: AsRef<str>>(
+    header: &str,
+    options: &[T],
+    width: i32,
+    ui: &mut dyn Backend,
+) -> Option<usize> {
+    grouped_menu(header, options, &[], None, width, ui)
+}
+
+/// A line of a menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MenuRow<'a> {
+    Heading(&'a str),
+    /// The option with the given index.
+    Option(usize),
+}
+
+/// A menu whose options are sorted into groups, each shown under a heading.
+/// Each heading comes with the index of the first option under it. With
+/// `describe`, the player can read about an option before picking it.
+pub fn grouped_menu<T: AsRef<str>>(
+    header: &str,
+    options: &[T],
+    headings: &[(usize, String)],
+    describe: Option<&dyn Fn(usize) -> String>,
+    width: i32,
+    ui: &mut dyn Backend,
+) -> Option<usize> {
+    // Calculate total height for the header (after auto-wrap) and one line per option. 
+    let header_height = if header.is_empty() {
+        0
+    } else {
+        ui.get_height_rect(Layer::Window, 0, 0, width, ui.height(), header)
+    };
+
+    let mut rows = vec![];
+    for index in 0..options.len() {
+        let above = headings.iter().filter(|&&(first, _)| first == index);
+        rows.extend(above.map(|(_, heading)| MenuRow::Heading(heading)));
+        rows.push(MenuRow::Option(index));
+    }
+
+    // As many rows as fit on the screen are shown at once, leaving a line to
+    // say which page is shown if they do not all fit.
+    let room = cmp::max(ui.height() - header_height, 2) as usize;
+    let page_size = if rows.len() <= cmp::min(room, MENU_PAGE_SIZE) {
+        cmp::max(rows.len(), 1)
+    } else {
+        cmp::min(room - 1, MENU_PAGE_SIZE)
+    };
+    // A heading never ends a page, away from its options.
+    let mut pages: Vec<Vec<MenuRow>> = vec![vec![]];
+    for row in rows {
+        let filled = pages.last().map_or(0, Vec::len);
+        let heading = matches!(row, MenuRow::Heading(_));
+        if filled == page_size || (heading && filled > 0 && filled + 1 == page_size) {
+            pages.push(vec![]);
+        }
+        if let Some(page) = pages.last_mut() {
+            page.push(row);
+        }
+    }
+    let page_of = |index: usize| {
+        pages.iter().position(|page| page.contains(&MenuRow::Option(index))).unwrap_or(0)
+    };
+    let footer_height = if pages.len() > 1 { 1 } else { 0 };
+    let longest = pages.iter().map(Vec::len).max().unwrap_or(0);
+    let height = longest as i32 + header_height + footer_height;
+
+    // Make the window layer the size of the menu. 
+    ui.resize(Layer::Window, width, height);
+    // Keep what is behind the menu, to draw it over afresh each time.
+    let (screen_width, screen_height) = (ui.width(), ui.height());
+    ui.copy_screen(Layer::Backdrop);
+
+    // The option picked with the arrow keys or a controller, if any.
+    let mut selected: Option<usize> = None;
+    let mut page = 0;
+    loop {
+        // Print the header, with auto-wrap. 
+        ui.set_default_background(Layer::Window, BLACK);
+        ui.clear(Layer::Window);
+        ui.set_default_foreground(Layer::Window, WHITE);
+        ui.print_rect(Layer::Window, 0, 0, width, height, header);
+
+        // Print the headings and options on this page, lettering the options. 
+        let mut lettered = vec![];
+        for (line, row) in pages[page].iter().enumerate() {
+            let y = header_height + line as i32;
+            let index = match *row {
+                MenuRow::Heading(heading) => {
+                    ui.set_default_foreground(Layer::Window, LIGHT_YELLOW);
+                    let (none, left) = (BackgroundFlag::None, TextAlignment::Left);
+                    ui.print_ex(Layer::Window, 0, y, none, left, heading);
+                    ui.set_default_foreground(Layer::Window, WHITE);
+                    continue;
+                }
+                MenuRow::Option(index) => index,
+            };
+            let menu_letter = (b'a' + lettered.len() as u8) as char;
+            lettered.push(index);
+            let text = format!("({}) {}", menu_letter, options[index].as_ref());
+            let flag = if selected == Some(index) {
+                ui.set_default_background(Layer::Window, DARK_GREY);
+                BackgroundFlag::Set
+            } else {
+                BackgroundFlag::None
+            };
+            ui.print_ex(
+                Layer::Window,
+                0, 
+                y, 
+                flag, 
+                TextAlignment::Left, 
+                &text
+            );
+        }
+        if pages.len() > 1 {
+            ui.set_default_foreground(Layer::Window, LIGHT_GREY);
+            let footer = tr!("menu.page", page + 1, pages.len());
+            let (none, center) = (BackgroundFlag::None, TextAlignment::Center);
+            ui.print_ex(Layer::Window, width / 2, height - 1, none, center, &footer);
+        }
+
+        // Blit the contents of the window to the screen, over the backdrop. 
+        ui.blit(Layer::Backdrop, (0, 0), (screen_width, screen_height), (0, 0), 1.0, 1.0);
+        let x = screen_width / 2 - width / 2;
+        let y = screen_height / 2 - height / 2;
+        ui.blit(Layer::Window, (0, 0), (width, height), (x, y), 1.0, 0.7);
+
+        // Present the screen to the player and wait for a key-press. 
+        ui.flush();
+        let key = wait_for_key(ui);
+
+        match key.code {
+            KeyCode::Up | KeyCode::Down if !options.is_empty() => {
+                let last = options.len() - 1;
+                let index = match (key.code, selected) {
+                    (KeyCode::Up, Some(index)) if index > 0 => index - 1,
+                    (KeyCode::Up, _) => last,
+                    (_, Some(index)) if index < last => index + 1,
+                    _ => 0,
+                };
+                selected = Some(index);
+                page = page_of(index);
+                continue;
+            }
+            KeyCode::PageUp | KeyCode::Left => {
+                page = page.checked_sub(1).unwrap_or(pages.len() - 1);
+                continue;
+            }
+            KeyCode::PageDown | KeyCode::Right => {
+                page = (page + 1) % pages.len();
+                continue;
+            }
+            KeyCode::Enter => return selected,
+            _ => {}
+        }
+
+        // Read about the highlighted option with ?, or any with Shift and its letter.
+        if let Some(describe) = describe {
+            let described = match key.printable {
+                '?' => selected,
+                c if c.is_ascii_uppercase() => lettered.get(c as usize - 'A' as usize).cloned(),
+                _ => None,
+            };
+            if let Some(index) = described {
+                msgbox(&describe(index), width, ui);
+                // The message box had the window layer; give it back to the menu.
+                ui.resize(Layer::Window, width, height);
+                continue;
+            }
+            if key.printable == '?' {
+                continue;
+            }
+        }
+
+        // Convert the letter to an index; if it is an option on this page, return it. 
+        if key.printable.is_ascii_alphabetic() {
+            let letter = key.printable.to_ascii_lowercase() as usize - 'a' as usize;
+            if let Some(&index) = lettered.get(letter) {
+                return Some(index);
+            }
+        }
+        return None;
+    }
+}
+
+/// Show a message box: a menu with no options.
+pub fn msgbox(text: &str, width: i32, ui: &mut dyn Backend) {
+    let options: &[&str] = &[];
+    menu(text, options, width, ui);
+}
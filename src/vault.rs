@@ -0,0 +1,104 @@
+//! Vaults: handmade set pieces stamped into generated levels, loaded from
+//! the text templates in `vaults/`.
+//!
+//! A vault is written like a prefab, with two more markers: `M` for a
+//! monster and `*` for an item, both picked at random like any others on the
+//! level. A space leaves the map as it was.
+
+use rand::{Rng, StdRng};
+
+use crate::branch::Branch;
+use crate::editor::{Prefab, prefab_object, template_names};
+use crate::equipment::maybe_curse;
+use crate::items::{Item, create_item, random_item};
+use crate::locks::lock_vault;
+use crate::map::{Map, Rect, Tile};
+use crate::object::{Object, PLAYER, random_monster};
+use crate::ranged::bundle_ammo;
+use crate::sleep::maybe_put_to_sleep;
+
+pub const VAULT_DIR: &str = "vaults";
+pub const VAULT_CHANCE: f32 = 0.3; // Chance of a vault on each level
+
+/// Sometimes stamp a vault into one of the rooms. It goes in a room big
+/// enough to leave a ring of floor around it, so every way through the room
+/// stays open, and never in the first or last room, where the player starts
+/// and the stairs go. Its doors may be locked, with the key left elsewhere.
+pub fn place_vault(
+    branch: Branch,
+    rooms: &[Rect],
+    map: &mut Map,
+    objects: &mut Vec<Object>,
+    unlocked_items: &[Item],
+    rng: &mut StdRng,
+) {
+    if rooms.len() < 3 || rng.next_f32() >= VAULT_CHANCE {
+        return;
+    }
+    let names = template_names(VAULT_DIR);
+    if names.is_empty() {
+        return;
+    }
+    let vault = match Prefab::load_from(VAULT_DIR, &names[rng.gen_range(0, names.len())]) {
+        Ok(vault) => vault,
+        Err(_) => return,
+    };
+    let (width, height) = (vault.width(), vault.height());
+    let fits: Vec<&Rect> = rooms[1..rooms.len() - 1]
+        .iter()
+        .filter(|room| room.x2 - room.x1 - 3 >= width && room.y2 - room.y1 - 3 >= height)
+        .collect();
+    if fits.is_empty() {
+        return;
+    }
+    let room = fits[rng.gen_range(0, fits.len())];
+    let x0 = rng.gen_range(room.x1 + 2, room.x2 - width);
+    let y0 = rng.gen_range(room.y1 + 2, room.y2 - height);
+
+    // Clear out whatever the room was given, where the vault now stands.
+    let inside = |x: i32, y: i32| x >= x0 && x < x0 + width && y >= y0 && y < y0 + height;
+    let mut id = objects.len();
+    while id > 0 {
+        id -= 1;
+        if id != PLAYER && inside(objects[id].x, objects[id].y) {
+            objects.remove(id);
+        }
+    }
+
+    let mut doors = vec![];
+    for (dy, row) in vault.rows.iter().enumerate() {
+        for (dx, &glyph) in row.iter().enumerate() {
+            let (x, y) = (x0 + dx as i32, y0 + dy as i32);
+            if glyph == ' ' {
+                continue;
+            }
+            map[x as usize][y as usize] = match glyph {
+                '#' => Tile {
+                    undiggable: true,
+                    ..Tile::wall()
+                },
+                '+' => Tile::door(),
+                _ => Tile::empty(),
+            };
+            match glyph {
+                '+' => doors.push((x, y)),
+                'M' => {
+                    let mut monster = random_monster(x, y, branch, rng);
+                    maybe_put_to_sleep(&mut monster, rng);
+                    objects.push(monster);
+                }
+                '*' => {
+                    let item = random_item(unlocked_items, rng);
+                    let mut object = create_item(item, x, y);
+                    maybe_curse(&mut object, rng);
+                    bundle_ammo(&mut object, rng);
+                    objects.push(object);
+                }
+                // The player starts where the level says, not in a vault.
+                '@' => {}
+                _ => objects.extend(prefab_object(glyph, x, y)),
+            }
+        }
+    }
+    lock_vault(&doors, inside, map, objects, rng);
+}
@@ -0,0 +1,150 @@
+//! Wands: items that hold a few charges of an effect, and are only used up
+//! once the last charge is spent.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::config;
+use crate::digging::dig;
+use crate::game::Game;
+use crate::items::{Item, UseResult, cast_lightning};
+use crate::locale::display_name;
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+use crate::render::Animation;
+use crate::status::{StatusEffect, StatusKind};
+use crate::tcod_types::colors::*;
+use crate::throw::trajectory;
+use crate::ui::{Tcod, pick_tile, target_monster};
+
+pub const DIG_RANGE: i32 = 8; // Tiles of rock a wand of digging bores through
+pub const SLOW_RANGE: i32 = 8;
+pub const SLOW_TURNS: i32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum WandKind {
+    /// A lightning bolt, like the scroll's.
+    Lightning,
+    /// Bores a tunnel through rock.
+    Digging,
+    /// Makes a monster lose every other turn.
+    Slow,
+}
+
+impl WandKind {
+    pub const ALL: [WandKind; 3] = [WandKind::Lightning, WandKind::Digging, WandKind::Slow];
+
+    /// The charges a new wand holds.
+    pub fn charges(self) -> u32 {
+        match self {
+            WandKind::Lightning => 3,
+            WandKind::Digging => 5,
+            WandKind::Slow => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WandKind::Lightning => "wand of lightning",
+            WandKind::Digging => "wand of digging",
+            WandKind::Slow => "wand of slowness",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            WandKind::Lightning => LIGHT_BLUE,
+            WandKind::Digging => LIGHT_SEPIA,
+            WandKind::Slow => LIGHT_CYAN,
+        }
+    }
+}
+
+/// Spend one of the wand's charges. The wand is kept while it has charges
+/// left, and crumbles once the last one is gone.
+pub fn zap_wand(
+    inventory_id: usize,
+    tcod: &mut Tcod,
+    game: &mut Game,
+    objects: &mut [Object],
+) -> UseResult
+{
+    let kind = match game.inventory[inventory_id].item {
+        Some(Item::Wand(kind)) => kind,
+        _ => return UseResult::Cancelled,
+    };
+    let result = match kind {
+        WandKind::Lightning => cast_lightning(inventory_id, tcod, game, objects),
+        WandKind::Digging => zap_digging(tcod, game, objects),
+        WandKind::Slow => zap_slow(tcod, game, objects),
+    };
+    if let UseResult::Cancelled = result {
+        return result;
+    }
+    let wand = &mut game.inventory[inventory_id];
+    wand.charges = wand.charges.saturating_sub(1);
+    if wand.charges > 0 {
+        UseResult::UsedAndKept
+    } else {
+        let name = display_name(&wand.name);
+        game.messages.add(tr!("wand.crumbles", name), MessageCategory::Item);
+        UseResult::UsedUp
+    }
+}
+
+fn zap_digging(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    let from = objects[PLAYER].pos();
+    let target = match pick_tile(tcod, game, objects, &tr!("wand.dig-target"), from) {
+        Some(target) if target != from => target,
+        _ => return UseResult::Cancelled,
+    };
+    // Bore through rock, but stop at the edge of the map or a vault's walls.
+    let (width, height) = (config().map.width, config().map.height);
+    let mut dug = 0;
+    let mut end = from;
+    for (x, y) in trajectory(from, target).into_iter().take(DIG_RANGE as usize) {
+        if x <= 0 || y <= 0 || x >= width - 1 || y >= height - 1 {
+            break;
+        }
+        if game.map[x as usize][y as usize].undiggable {
+            break;
+        }
+        end = (x, y);
+        if dig(x, y, tcod, &mut game.map) {
+            dug += 1;
+        }
+    }
+    game.animations.push(Animation::Projectile {
+        from: from,
+        to: end,
+        char: '*',
+        color: LIGHT_SEPIA,
+    });
+    if dug > 0 {
+        game.messages.add(tr!("wand.dig"), MessageCategory::Item);
+    } else {
+        game.messages.add(tr!("wand.dig-nothing"), MessageCategory::Item);
+    }
+    UseResult::UsedUp
+}
+
+fn zap_slow(tcod: &mut Tcod, game: &mut Game, objects: &mut [Object]) -> UseResult {
+    if Object::closest_monster(tcod, objects, SLOW_RANGE).is_none() {
+        game.messages.add(tr!("no-enemy-in-range"), MessageCategory::Warning);
+        return UseResult::Cancelled;
+    }
+    let prompt = tr!("wand.slow-target");
+    let monster_id = match target_monster(tcod, game, objects, &prompt, SLOW_RANGE) {
+        Some(monster_id) => monster_id,
+        None => return UseResult::Cancelled,
+    };
+    objects[monster_id].add_effect(StatusEffect {
+        kind: StatusKind::Slow,
+        turns: SLOW_TURNS,
+        strength: 0,
+    });
+    game.messages.add(
+        tr!("wand.slowed", display_name(&objects[monster_id].name)),
+        MessageCategory::Item,
+    );
+    UseResult::UsedUp
+}
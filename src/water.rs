@@ -0,0 +1,103 @@
+//! Water: pools left in some rooms, shallow at the edges and deep in the
+//! middle. Deep water is slow to swim through, and takes whatever the player
+//! lets slip; monsters that cannot swim keep out of it, or drown.
+
+use rand::{Rng, StdRng};
+
+use crate::branch::Branch;
+use crate::fov::Fov;
+use crate::game::Game;
+use crate::items::take_from_inventory;
+use crate::locale::display_name;
+use crate::map::{Map, Rect, Terrain};
+use crate::messages::MessageCategory;
+use crate::object::{Object, PLAYER};
+
+pub const POOL_MAX_RADIUS: i32 = 3;
+pub const FLOUNDER_CHANCE: f32 = 0.5; // Chance of a move out of deep water going nowhere
+pub const SINK_CHANCE: f32 = 0.1; // Chance, each turn in deep water, of an item slipping away
+
+/// Sometimes flood part of the room, with deep water in the middle of the
+/// pool and shallow water around it.
+pub fn place_pool(branch: Branch, room: Rect, map: &mut Map, rng: &mut StdRng) {
+    if rng.next_f32() >= branch.pool_chance() {
+        return;
+    }
+    let radius = rng.gen_range(1, POOL_MAX_RADIUS + 1);
+    let cx = rng.gen_range(room.x1 + 1, room.x2);
+    let cy = rng.gen_range(room.y1 + 1, room.y2);
+    for x in (room.x1 + 1)..room.x2 {
+        for y in (room.y1 + 1)..room.y2 {
+            let distance = (((x - cx).pow(2) + (y - cy).pow(2)) as f32).sqrt();
+            let tile = &mut map[x as usize][y as usize];
+            if tile.blocked || distance > radius as f32 + 0.5 {
+                continue;
+            }
+            tile.terrain = if distance < radius as f32 - 0.5 {
+                Terrain::DeepWater
+            } else {
+                Terrain::ShallowWater
+            };
+        }
+    }
+}
+
+/// Whether the object is in deep water.
+pub fn in_deep_water(object: &Object, map: &Map) -> bool {
+    map[object.x as usize][object.y as usize].terrain == Terrain::DeepWater
+}
+
+/// Whether the one trying to move out of deep water fails to get anywhere
+/// this turn.
+pub fn flounders(id: usize, game: &mut Game, objects: &[Object]) -> bool {
+    if !in_deep_water(&objects[id], &game.map) || objects[id].swims {
+        return false;
+    }
+    if game.rng.next_f32() >= FLOUNDER_CHANCE {
+        return false;
+    }
+    if id == PLAYER {
+        game.messages.add(tr!("water.flounder"), MessageCategory::Warning);
+    }
+    true
+}
+
+/// Let a turn in the water pass: the player may lose hold of something,
+/// and monsters out of their depth drown.
+pub fn tick_water(fov: &Fov, game: &mut Game, objects: &mut [Object]) {
+    if objects[PLAYER].alive && in_deep_water(&objects[PLAYER], &game.map) {
+        // Only what is in the hands or the pack, not what is worn.
+        let loose: Vec<usize> = (0..game.inventory.len())
+            .filter(|&index| game.inventory[index].equipment.map_or(true, |e| !e.equipped))
+            .collect();
+        if !loose.is_empty() && game.rng.next_f32() < SINK_CHANCE {
+            let index = loose[game.rng.gen_range(0, loose.len())];
+            // It sinks out of reach, and is lost for good.
+            let item = take_from_inventory(&mut game.inventory, index);
+            let name = game.identification.name_of(&item);
+            game.messages.add(tr!("water.sinks", name), MessageCategory::Warning);
+            if let (Some(price), Some(shop)) = (item.price, game.shop.as_mut()) {
+                shop.debt += price;
+                game.messages.add(tr!("shop.used-unpaid", price), MessageCategory::Item);
+            }
+        }
+    }
+
+    for (id, object) in objects.iter_mut().enumerate() {
+        let drowning = id != PLAYER
+            && object.alive
+            && object.ai.is_some()
+            && object.fighter.is_some()
+            && !object.swims
+            && in_deep_water(object, &game.map);
+        if !drowning {
+            continue;
+        }
+        if fov.is_in_fov(object.x, object.y) {
+            let name = display_name(&object.name);
+            game.messages.add(tr!("water.drowns", name), MessageCategory::Combat);
+        }
+        let hp = object.fighter.map_or(0, |f| f.hp);
+        object.take_dungeon_damage(hp, game);
+    }
+}
@@ -0,0 +1,66 @@
+//! Scripted runs played with `--headless`, checked by the snapshots they print.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::Value;
+
+/// The script in `examples/` with the given name.
+fn example(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join(name)
+}
+
+/// Play the script with the given seed, and return every snapshot printed.
+/// Each run starts in an empty directory of its own, since the game writes
+/// its config and profile wherever it is started.
+fn play(run: &str, script: &Path, seed: u32) -> Vec<Value> {
+    let dir = env::temp_dir().join(format!("roguelike-headless-{}-{}", run, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_roguelike-tutorial"))
+        .arg("--headless")
+        .arg(script)
+        .arg("--seed")
+        .arg(seed.to_string())
+        .current_dir(&dir)
+        .output()
+        .expect("cannot start the game");
+    let _ = fs::remove_dir_all(&dir);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("a snapshot is not JSON"))
+        .collect()
+}
+
+#[test]
+fn a_scripted_run_prints_the_game_as_it_goes() {
+    let snapshots = play("explore", &example("explore.txt"), 7);
+    let last = snapshots.last().expect("no snapshots were printed");
+
+    // Eight moves, counting the two waits, though walking into a wall takes
+    // no time, and neither does looking in the pack.
+    let turns: Vec<u64> = snapshots.iter().map(|s| s["turn"].as_u64().unwrap()).collect();
+    assert!(turns.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(turns.last().map_or(false, |&turn| turn > 0 && turn <= 8));
+    assert_eq!(last["depth"], 1);
+    assert!(last["hp"].as_i64().unwrap() > 0);
+
+    let player = &last["entities"][0];
+    assert_eq!(player["char"], "@");
+    let x = player["x"].as_u64().unwrap() as usize;
+    let row = last["map"][player["y"].as_u64().unwrap() as usize].as_str().unwrap();
+    assert_eq!(row.chars().nth(x), Some('@'));
+    let messages = last["messages"].as_array().unwrap();
+    assert!(messages.iter().any(|message| message.as_str().unwrap().contains("(Seed 7)")));
+}
+
+#[test]
+fn the_same_seed_plays_the_same_run() {
+    let script = example("explore.txt");
+    assert_eq!(play("seed-a", &script, 7), play("seed-b", &script, 7));
+    assert_ne!(play("seed-c", &script, 7), play("seed-d", &script, 8));
+}